@@ -0,0 +1,182 @@
+use std::collections::HashMap;
+
+use serde_json::{Map, Value};
+
+use crate::scale::{get_path, value_to_f64, value_to_string};
+use crate::spec::{Aggregate, AggregateSpec, ChannelDef, Encoding};
+use crate::stats;
+
+/// Group rows by the fields of the encoding's unaggregated channels and reduce each
+/// aggregated channel's field per group, giving one row per group in first-seen order.
+/// `None` when no channel aggregates.
+///
+/// Output rows hold the group-by fields and the aggregated fields under their original
+/// names, so the mark compilers read them like raw data.
+pub fn aggregate_rows(encoding: &Encoding, data: &[Value]) -> Option<Vec<Value>> {
+    let channels: Vec<&ChannelDef> = encoding.channels().map(|(_, channel)| channel).collect();
+
+    let aggregated: Vec<(&str, &AggregateSpec)> = channels
+        .iter()
+        .filter_map(|channel| Some((channel.field()?, channel.aggregate()?)))
+        .collect();
+    if aggregated.is_empty() {
+        return None;
+    }
+    let mut group_by: Vec<&str> = Vec::new();
+    for channel in channels.iter().filter(|channel| channel.aggregate().is_none()) {
+        if let Some(field) = channel.field().filter(|field| !group_by.contains(field)) {
+            group_by.push(field);
+        }
+    }
+
+    let mut groups: Vec<Vec<&Value>> = Vec::new();
+    let mut index: HashMap<Vec<String>, usize> = HashMap::new();
+    for row in data {
+        let key: Vec<String> = group_by
+            .iter()
+            .map(|field| get_path(row, field).map(value_to_string).unwrap_or_default())
+            .collect();
+        let i = *index.entry(key).or_insert_with(|| {
+            groups.push(Vec::new());
+            groups.len() - 1
+        });
+        groups[i].push(row);
+    }
+
+    let rows = groups
+        .into_iter()
+        .map(|rows| {
+            let mut out = Map::new();
+            for field in &group_by {
+                if let Some(value) = get_path(rows[0], field) {
+                    out.insert(field.to_string(), value.clone());
+                }
+            }
+            for (field, spec) in &aggregated {
+                let value = reduce(&rows, field, spec);
+                out.insert(field.to_string(), value.map_or(Value::Null, Value::from));
+            }
+            Value::Object(out)
+        })
+        .collect();
+    Some(rows)
+}
+
+/// Apply `spec` to `field` across one group's rows; `None` when there is nothing to reduce
+fn reduce(rows: &[&Value], field: &str, spec: &AggregateSpec) -> Option<f64> {
+    let number = |row: &Value, field: &str| get_path(row, field).and_then(value_to_f64);
+    let values: Vec<f64> = rows.iter().filter_map(|row| number(row, field)).collect();
+
+    match spec.op() {
+        Aggregate::Count => Some(rows.len() as f64),
+        Aggregate::Distinct => {
+            let mut distinct: Vec<String> = rows
+                .iter()
+                .filter_map(|row| get_path(row, field).map(value_to_string))
+                .collect();
+            distinct.sort();
+            distinct.dedup();
+            Some(distinct.len() as f64)
+        }
+        Aggregate::Sum => Some(stats::sum(&values)),
+        Aggregate::Mean => match spec.weight() {
+            Some(weight) => {
+                let pairs: Vec<(f64, f64)> = rows
+                    .iter()
+                    .filter_map(|row| Some((number(row, field)?, number(row, weight)?)))
+                    .collect();
+                stats::weighted_mean(&pairs)
+            }
+            None => stats::mean(&values),
+        },
+        Aggregate::Median => stats::median(&values),
+        Aggregate::Quantile => stats::quantile(&values, spec.p()),
+        Aggregate::Min => stats::min(&values),
+        Aggregate::Max => stats::max(&values),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn test_count_without_field_counts_rows() {
+        let encoding: Encoding = serde_json::from_value(json!({
+            "x": { "field": "product" },
+            "y": { "aggregate": "count" }
+        }))
+        .unwrap();
+        let data = vec![
+            json!({ "product": "a" }),
+            json!({ "product": "b" }),
+            json!({ "product": "a" }),
+        ];
+
+        assert_eq!(
+            aggregate_rows(&encoding, &data).unwrap(),
+            vec![json!({ "product": "a", "*": 2.0 }), json!({ "product": "b", "*": 1.0 })]
+        );
+    }
+
+    #[test]
+    fn test_rows_group_by_unaggregated_fields() {
+        let encoding: Encoding = serde_json::from_value(json!({
+            "x": { "field": "region" },
+            "y": { "field": "sales", "aggregate": "sum" }
+        }))
+        .unwrap();
+        let data = vec![
+            json!({ "region": "north", "sales": 10 }),
+            json!({ "region": "south", "sales": 5 }),
+            json!({ "region": "north", "sales": 20 }),
+        ];
+
+        assert_eq!(
+            aggregate_rows(&encoding, &data).unwrap(),
+            vec![
+                json!({ "region": "north", "sales": 30.0 }),
+                json!({ "region": "south", "sales": 5.0 }),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_weighted_mean_per_group() {
+        let encoding: Encoding = serde_json::from_value(json!({
+            "x": { "field": "product" },
+            "y": { "field": "price", "aggregate": { "op": "mean", "weight": "qty" } }
+        }))
+        .unwrap();
+        let data = vec![
+            json!({ "product": "a", "price": 10, "qty": 3 }),
+            json!({ "product": "a", "price": 20, "qty": 1 }),
+            json!({ "product": "b", "price": 5, "qty": 2 }),
+        ];
+
+        assert_eq!(
+            aggregate_rows(&encoding, &data).unwrap(),
+            vec![
+                json!({ "product": "a", "price": 12.5 }),
+                json!({ "product": "b", "price": 5.0 }),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_quantile_interpolates_between_order_statistics() {
+        let encoding: Encoding = serde_json::from_value(json!({
+            "x": { "field": "k" },
+            "y": { "field": "v", "aggregate": { "op": "quantile", "p": 0.9 } },
+            "size": { "field": "v2", "aggregate": "median" }
+        }))
+        .unwrap();
+        let data: Vec<Value> = (1..=5).map(|v| json!({ "k": "a", "v": v * 10, "v2": v })).collect();
+
+        // p90 of 10..50 sits 60% of the way from 40 to 50
+        let rows = aggregate_rows(&encoding, &data).unwrap();
+        assert_eq!(rows, vec![json!({ "k": "a", "v": 46.0, "v2": 3.0 })]);
+    }
+}
@@ -0,0 +1,266 @@
+use std::f64::consts::PI;
+
+use serde_json::Value;
+
+use super::{color_categories, with_percent, CompileContext, CompileError};
+use crate::ir::{
+    Color, Geometry, Group, Mark, MarkItem, MarkType, Stroke, TextAnchor, TextBaseline, Transform,
+};
+use crate::scale::{get_path, value_to_f64, value_to_string};
+use crate::spec::Encoding;
+use crate::template::format_template;
+
+/// Space kept between the pie and the plot edge for wedge labels
+const LABEL_MARGIN: f64 = 40.0;
+
+/// Radial length of the leader line from the wedge edge to its label
+const LEADER_LENGTH: f64 = 12.0;
+
+/// Gap between the end of a leader line and the label text
+const LABEL_GAP: f64 = 3.0;
+
+/// Screen angle of 12 o'clock, where `mark.startAngle` is measured from
+const TWELVE_O_CLOCK: f64 = -PI / 2.0;
+
+/// Compile arc (pie) encoding to scene graph: one wedge per row, sized by `theta`
+pub fn compile_arc(
+    encoding: &Encoding,
+    data: &[Value],
+    ctx: &CompileContext,
+) -> Result<Group, CompileError> {
+    let plot_area = &ctx.plot_area;
+
+    let theta_channel = encoding
+        .theta
+        .as_ref()
+        .ok_or_else(|| CompileError::MissingField("encoding.theta".to_string()))?;
+    let theta_field = theta_channel
+        .field()
+        .ok_or_else(|| CompileError::InvalidEncoding("theta must have a field".to_string()))?;
+
+    // Color names the wedges; without it each wedge gets its own palette color
+    let color_field = encoding.color.as_ref().and_then(|c| c.field());
+    let categories = color_categories(encoding, data);
+
+    let wedges: Vec<(&Value, f64)> = data
+        .iter()
+        .filter_map(|row| {
+            let value = get_path(row, theta_field).and_then(value_to_f64)?;
+            (value > 0.0).then_some((row, value))
+        })
+        .collect();
+    let total: f64 = wedges.iter().map(|(_, value)| value).sum();
+
+    let cx = plot_area.width / 2.0;
+    let cy = plot_area.height / 2.0;
+    let outer_radius = (plot_area.width.min(plot_area.height) / 2.0 - LABEL_MARGIN).max(0.0);
+
+    // Outline wedges in the background color so adjacent slices read as separate
+    let wedge_separator = ctx.theme.background.unwrap_or(Color::rgb(255, 255, 255));
+
+    let start_angle = ctx.config.start_angle.unwrap_or(0.0).to_radians();
+    let direction = if ctx.config.clockwise == Some(false) { -1.0 } else { 1.0 };
+
+    let mut arc_items = Vec::new();
+    let mut labels = Vec::new();
+    let mut angle = TWELVE_O_CLOCK + start_angle;
+
+    for (i, (row, value)) in wedges.iter().enumerate() {
+        let sweep = direction * if total > 0.0 { value / total * 2.0 * PI } else { 0.0 };
+        let category = color_field.and_then(|field| get_path(row, field)).map(value_to_string);
+        let datum = with_percent(row, if total > 0.0 { value / total } else { 0.0 });
+        let series_idx = category
+            .as_ref()
+            .and_then(|c| categories.iter().position(|known| known == c))
+            .unwrap_or(i);
+
+        arc_items.push(
+            MarkItem::new(Geometry::Arc {
+                cx,
+                cy,
+                inner_radius: 0.0,
+                outer_radius,
+                // Renderers sweep from start to end clockwise, so counterclockwise
+                // wedges are emitted with their ends swapped
+                start_angle: angle.min(angle + sweep),
+                end_angle: angle.max(angle + sweep),
+            })
+            .with_fill(match &category {
+                Some(category) => ctx.category_fill(category, series_idx),
+                None => ctx.series_fill(series_idx),
+            })
+            .with_stroke(Stroke::solid(wedge_separator, 1.0))
+            .with_datum(datum.clone()),
+        );
+
+        let text = match &ctx.config.label {
+            Some(template) => format_template(template, &datum),
+            None => category.unwrap_or_else(|| get_path(row, theta_field).map(value_to_string).unwrap_or_default()),
+        };
+        labels.push(WedgeLabel {
+            mid_angle: angle + sweep / 2.0,
+            text,
+        });
+        angle += sweep;
+    }
+
+    let mut root = Group::new().with_transform(Transform::translate(plot_area.x, plot_area.y));
+
+    let mut arcs = Mark::new(MarkType::Arc, arc_items).as_data();
+    if let Some(template) = &ctx.config.tooltip {
+        arcs = arcs.with_tooltip(template.clone());
+    }
+    if ctx.config.shadow == Some(true) {
+        arcs = arcs.with_shadow();
+    }
+    root.add_mark(arcs);
+
+    let (leaders, texts) = layout_labels(&labels, (cx, cy), outer_radius, ctx);
+    root.add_mark(Mark::new(MarkType::Rule, leaders).as_data());
+    root.add_mark(Mark::new(MarkType::Text, texts).as_data());
+
+    Ok(root)
+}
+
+struct WedgeLabel {
+    mid_angle: f64,
+    text: String,
+}
+
+/// Place labels just outside each wedge's mid-angle with a leader line from the wedge edge.
+/// Labels on each side are pushed down so neighbours (typically tiny wedges) don't overlap.
+fn layout_labels(
+    labels: &[WedgeLabel],
+    center: (f64, f64),
+    outer_radius: f64,
+    ctx: &CompileContext,
+) -> (Vec<MarkItem>, Vec<MarkItem>) {
+    let (cx, cy) = center;
+    let label_radius = outer_radius + LEADER_LENGTH;
+    let min_gap = ctx.theme.font_size + 2.0;
+
+    let mut leaders = Vec::new();
+    let mut texts = Vec::new();
+
+    for right_side in [true, false] {
+        let mut side: Vec<&WedgeLabel> = labels
+            .iter()
+            .filter(|label| (label.mid_angle.cos() >= 0.0) == right_side)
+            .collect();
+        side.sort_by(|a, b| a.mid_angle.sin().total_cmp(&b.mid_angle.sin()));
+
+        let mut last_y = f64::NEG_INFINITY;
+        for label in side {
+            let (cos, sin) = (label.mid_angle.cos(), label.mid_angle.sin());
+            let x = cx + label_radius * cos;
+            let y = (cy + label_radius * sin).max(last_y + min_gap);
+            last_y = y;
+
+            leaders.push(
+                MarkItem::new(Geometry::Rule {
+                    x1: cx + outer_radius * cos,
+                    y1: cy + outer_radius * sin,
+                    x2: x,
+                    y2: y,
+                })
+                .with_stroke(Stroke::solid(ctx.theme.axis_color, 1.0)),
+            );
+
+            let (text_x, anchor) = if right_side {
+                (x + LABEL_GAP, TextAnchor::Start)
+            } else {
+                (x - LABEL_GAP, TextAnchor::End)
+            };
+            texts.push(
+                MarkItem::new(Geometry::Text {
+                    x: text_x,
+                    y,
+                    text: label.text.clone(),
+                    font: ctx.theme.label_font(),
+                    anchor,
+                    baseline: TextBaseline::Middle,
+                    angle: 0.0,
+                })
+                .with_fill(ctx.theme.axis_color),
+            );
+        }
+    }
+
+    (leaders, texts)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ir::{Geometry, TextAnchor};
+
+    #[test]
+    fn test_tiny_wedge_labels_do_not_overlap() {
+        let scene = crate::chart(
+            r#"{
+                "data": { "values": [
+                    { "k": "big", "v": 97 }, { "k": "a", "v": 1 }, { "k": "b", "v": 1 }, { "k": "c", "v": 1 }
+                ] },
+                "mark": "arc",
+                "encoding": { "theta": { "field": "v" }, "color": { "field": "k" } }
+            }"#,
+        )
+        .unwrap();
+
+        let mut label_ys: Vec<f64> = scene
+            .mark_items()
+            .filter_map(|item| match &item.geometry {
+                Geometry::Text { y, anchor, .. } if *anchor == TextAnchor::End => Some(*y),
+                _ => None,
+            })
+            .collect();
+        label_ys.sort_by(f64::total_cmp);
+        assert_eq!(label_ys.len(), 3);
+        assert!(label_ys.windows(2).all(|w| w[1] - w[0] >= 14.0));
+    }
+
+    #[test]
+    fn test_label_template_renders_percent_of_total() {
+        let scene = crate::chart(
+            r#"{
+                "data": { "values": [{ "k": "a", "v": 1 }, { "k": "b", "v": 2 }] },
+                "mark": { "type": "arc", "label": "{k}: {__percent}" },
+                "encoding": { "theta": { "field": "v" }, "color": { "field": "k" } }
+            }"#,
+        )
+        .unwrap();
+
+        let labels: Vec<_> = scene
+            .mark_items()
+            .filter_map(|item| match &item.geometry {
+                Geometry::Text { text, .. } => Some(text.as_str()),
+                _ => None,
+            })
+            .collect();
+        assert!(labels.contains(&"a: 33.3%"));
+        assert!(labels.contains(&"b: 66.7%"));
+    }
+
+    #[test]
+    fn test_counterclockwise_from_custom_start_angle() {
+        let scene = crate::chart(
+            r#"{
+                "data": { "values": [{ "k": "a", "v": 1 }, { "k": "b", "v": 3 }] },
+                "mark": { "type": "arc", "startAngle": 90, "clockwise": false },
+                "encoding": { "theta": { "field": "v" }, "color": { "field": "k" } }
+            }"#,
+        )
+        .unwrap();
+
+        let wedges: Vec<(f64, f64)> = scene
+            .mark_items()
+            .filter_map(|item| match item.geometry {
+                Geometry::Arc { start_angle, end_angle, .. } => Some((start_angle, end_angle)),
+                _ => None,
+            })
+            .collect();
+        // "a" runs from 3 o'clock back up to 12 o'clock, "b" continues around the other way
+        let quarter = std::f64::consts::FRAC_PI_2;
+        assert!((wedges[0].0 + quarter).abs() < 1e-9 && wedges[0].1.abs() < 1e-9);
+        assert!((wedges[1].0 + 4.0 * quarter).abs() < 1e-9 && (wedges[1].1 + quarter).abs() < 1e-9);
+    }
+}
@@ -1,10 +1,16 @@
 use serde_json::Value;
 
-use super::stack::{compute_stack, max_stacked_value, min_stacked_value};
-use super::{extract_categories, extract_numbers, generate_axis, infer_data_type, CompileError, PlotArea};
-use crate::ir::{Color, Geometry, Group, Mark, MarkItem, MarkType, Transform};
-use crate::scale::{BandScale, LinearScale};
-use crate::spec::{AxisOrient, DataType, Encoding, StackConfig, StackMode};
+use super::stack::{compute_stack, max_stacked_value, min_stacked_value, StackedValue};
+use super::position::{PositionScale, ValueScale};
+use super::{
+    channel_categories, color_categories, extract_numbers, generate_axis, generate_grid,
+    generate_legend, generate_zero_line, with_percent, ColorScale, CompileContext, CompileError,
+};
+use crate::ir::{
+    Color, Fill, Geometry, Group, Mark, MarkItem, MarkType, Stroke, TextAnchor, TextBaseline, Transform,
+};
+use crate::scale::{format_number_with, get_path, value_to_f64, value_to_string, BandScale, LinearScale, LogScale};
+use crate::spec::{AxisOrient, ChannelDef, Encoding, ScaleType, StackConfig, StackMode};
 
 /// Default color palette (hotpink is the default/first color)
 pub const COLORS: &[&str] = &[
@@ -12,13 +18,23 @@ pub const COLORS: &[&str] = &[
     "#9c755f", "#bab0ab",
 ];
 
+/// Band padding between and around bars when the mark config doesn't set one
+const DEFAULT_BAR_PADDING: f64 = 0.2;
+
+/// Padding between the bars of a group when the mark config doesn't set one
+const DEFAULT_GROUP_PADDING: f64 = 0.1;
+
+/// Distance between the end of a stack and its `mark.totalLabel`
+const TOTAL_LABEL_GAP: f64 = 4.0;
+
 /// Compile bar chart encoding to scene graph
 pub fn compile_bar(
     encoding: &Encoding,
     data: &[Value],
-    plot_area: &PlotArea,
-    stack_config: Option<&StackConfig>,
+    ctx: &CompileContext,
 ) -> Result<Group, CompileError> {
+    let plot_area = &ctx.plot_area;
+
     // Get x and y channels
     let x_channel = encoding
         .x
@@ -36,13 +52,9 @@ pub fn compile_bar(
         .field()
         .ok_or_else(|| CompileError::InvalidEncoding("y must have a field".to_string()))?;
 
-    // Infer data types if not specified
-    let x_type = x_channel.data_type().unwrap_or_else(|| infer_data_type(data, x_field));
-    let y_type = y_channel.data_type().unwrap_or_else(|| infer_data_type(data, y_field));
-
-    // Determine orientation: if x is quantitative and y is nominal, horizontal bars
-    let is_horizontal = matches!(x_type, DataType::Quantitative)
-        && matches!(y_type, DataType::Nominal | DataType::Ordinal);
+    // Orientation and stacking, as validation reads them
+    let layout = encoding.bar_layout(data, ctx.stack);
+    let is_horizontal = layout.horizontal;
 
     // Create scales based on orientation
     let (cat_field, val_field) = if is_horizontal {
@@ -51,26 +63,28 @@ pub fn compile_bar(
         (x_field, y_field)
     };
 
-    let categories = extract_categories(data, cat_field);
-    let unique_categories: Vec<String> = {
-        let mut seen = std::collections::HashSet::new();
-        categories
-            .iter()
-            .filter(|c| seen.insert((*c).clone()))
-            .cloned()
-            .collect()
-    };
+    let (cat_channel, val_channel) = if is_horizontal { (y_channel, x_channel) } else { (x_channel, y_channel) };
+    let nice = val_channel.nice();
+    let unique_categories = channel_categories(cat_channel, data, cat_field);
+
+    // Coloring bars by their own quantitative value grades each bar along a sequential
+    // ramp rather than splitting the bars into series
+    let value_color = encoding
+        .color
+        .as_ref()
+        .filter(|c| c.field() == Some(val_field))
+        .map(|c| ColorScale::from_channel(c, data, val_field, ctx.theme))
+        .filter(|scale| matches!(scale, ColorScale::Sequential(_)));
 
     // Check for color encoding (grouped/stacked bars)
     let color_field = encoding
         .color
         .as_ref()
         .and_then(|c| c.field())
+        .filter(|_| value_color.is_none())
         .map(|s| s.to_string());
 
-    // Determine if we should stack
-    let should_stack = color_field.is_some()
-        && stack_config.map_or(false, |sc| !matches!(sc, StackConfig::Enabled(false)));
+    let should_stack = layout.stacked;
 
     // Build bar marks
     let mut bar_items = Vec::new();
@@ -78,7 +92,10 @@ pub fn compile_bar(
     if let Some(ref color_f) = color_field {
         if should_stack {
             // Stacked bars
-            let stack_cfg = stack_config.cloned().unwrap_or(StackConfig::Enabled(true));
+            if val_channel.scale_type() == ScaleType::Log {
+                return Err(CompileError::InvalidEncoding("log scales aren't supported on stacked bars".to_string()));
+            }
+            let stack_cfg = ctx.stack.cloned().unwrap_or(StackConfig::Enabled(true));
             let stacked = compute_stack(data, cat_field, val_field, color_f, &stack_cfg);
 
             // Determine scale domain from stacked values
@@ -94,173 +111,154 @@ pub fn compile_bar(
 
             // Create scales
             let (cat_scale, val_scale) = if is_horizontal {
-                let cat_scale = BandScale::new(unique_categories.clone(), (0.0, plot_area.height)).padding(0.2);
-                let val_scale = LinearScale::new((domain_min, domain_max), (0.0, plot_area.width)).nice();
-                (cat_scale, val_scale)
+                let cat_scale = band_scale(unique_categories.clone(), (0.0, plot_area.height), ctx);
+                let val_scale = LinearScale::new((domain_min, domain_max), (0.0, plot_area.width)).nice_if(nice);
+                (cat_scale, ValueScale::Linear(val_scale))
             } else {
-                let cat_scale = BandScale::new(unique_categories.clone(), (0.0, plot_area.width)).padding(0.2);
-                let val_scale = LinearScale::new((domain_min, domain_max), (plot_area.height, 0.0)).nice();
-                (cat_scale, val_scale)
+                let cat_scale = band_scale(unique_categories.clone(), (0.0, plot_area.width), ctx);
+                let val_scale = ctx.resolve_y(LinearScale::new((domain_min, domain_max), (plot_area.height, 0.0)).nice_if(nice));
+                (cat_scale, ValueScale::Linear(val_scale))
             };
 
             // Get unique color values for color assignment
-            let color_values: Vec<String> = extract_categories(data, color_f);
-            let unique_colors: Vec<String> = {
-                let mut seen = std::collections::HashSet::new();
-                color_values
-                    .iter()
-                    .filter(|c| seen.insert((*c).clone()))
-                    .cloned()
-                    .collect()
-            };
+            let unique_colors = color_categories(encoding, data);
 
             let bandwidth = cat_scale.bandwidth();
+            let separator = stack_separator(ctx);
 
             for sv in &stacked {
                 let color_idx = unique_colors.iter().position(|c| c == &sv.series).unwrap_or(0);
-                let color = Color::from_hex(COLORS[color_idx % COLORS.len()]).unwrap();
+                let fill = ctx.category_fill(&sv.series, color_idx);
 
-                if is_horizontal {
+                let geometry = if is_horizontal {
                     let y = cat_scale.scale(&sv.category).unwrap_or(0.0);
                     let x0 = val_scale.scale(sv.y0);
                     let x1 = val_scale.scale(sv.y1);
-                    bar_items.push(
-                        MarkItem::new(Geometry::Rect {
-                            x: x0,
-                            y,
-                            width: x1 - x0,
-                            height: bandwidth,
-                            corner_radius: 0.0,
-                        })
-                        .with_fill(color)
-                        .with_datum(sv.row.clone()),
-                    );
+                    Geometry::Rect {
+                        x: x0,
+                        y,
+                        width: x1 - x0,
+                        height: bandwidth,
+                        corner_radius: 0.0,
+                    }
                 } else {
                     let x = cat_scale.scale(&sv.category).unwrap_or(0.0);
                     let y0 = val_scale.scale(sv.y0);
                     let y1 = val_scale.scale(sv.y1);
-                    bar_items.push(
-                        MarkItem::new(Geometry::Rect {
-                            x,
-                            y: y1, // y1 is smaller (higher on screen) for vertical
-                            width: bandwidth,
-                            height: y0 - y1,
-                            corner_radius: 0.0,
-                        })
-                        .with_fill(color)
-                        .with_datum(sv.row.clone()),
-                    );
+                    Geometry::Rect {
+                        x,
+                        y: y1, // y1 is smaller (higher on screen) for vertical
+                        width: bandwidth,
+                        height: y0 - y1,
+                        corner_radius: 0.0,
+                    }
+                };
+
+                let mut item = MarkItem::new(geometry)
+                    .with_fill(fill)
+                    .with_datum(with_percent(&sv.row, sv.share));
+                if let Some(stroke) = &separator {
+                    item = item.with_stroke(stroke.clone());
                 }
+                bar_items.push(item);
             }
 
-            return build_bar_group(bar_items, &cat_scale, &val_scale, encoding, plot_area, is_horizontal);
+            let mut root = build_bar_group(bar_items, &cat_scale, &val_scale, encoding, ctx, is_horizontal)?;
+            if ctx.config.total_label == Some(true) {
+                let labels = total_labels(&stacked, val_field, &cat_scale, &val_scale, is_horizontal, ctx);
+                root.add_mark(Mark::new(MarkType::Text, labels).as_data());
+            }
+            if !ctx.data_only {
+                root.add_group(generate_legend(&ColorScale::Categorical(unique_colors), color_f, ctx));
+            }
+            return Ok(root);
         } else {
             // Grouped bars (no stacking)
             let values = extract_numbers(data, val_field);
-            let max_value = values.iter().cloned().fold(0.0_f64, f64::max);
-
-            let (cat_scale, val_scale) = if is_horizontal {
-                let cat_scale = BandScale::new(unique_categories.clone(), (0.0, plot_area.height)).padding(0.2);
-                let val_scale = LinearScale::new((0.0, max_value), (0.0, plot_area.width)).nice().zero();
-                (cat_scale, val_scale)
-            } else {
-                let cat_scale = BandScale::new(unique_categories.clone(), (0.0, plot_area.width)).padding(0.2);
-                let val_scale = LinearScale::new((0.0, max_value), (plot_area.height, 0.0)).nice().zero();
-                (cat_scale, val_scale)
-            };
+            let cat_length = if is_horizontal { plot_area.height } else { plot_area.width };
+            let cat_scale = band_scale(unique_categories.clone(), (0.0, cat_length), ctx);
+            let val_scale = value_scale(&values, val_channel, is_horizontal, ctx);
 
-            let color_values: Vec<String> = extract_categories(data, color_f);
-            let unique_colors: Vec<String> = {
-                let mut seen = std::collections::HashSet::new();
-                color_values
-                    .iter()
-                    .filter(|c| seen.insert((*c).clone()))
-                    .cloned()
-                    .collect()
-            };
+            let unique_colors = color_categories(encoding, data);
 
-            let group_bandwidth = cat_scale.bandwidth();
-            let bar_width = group_bandwidth / unique_colors.len() as f64;
+            // Series are laid out side by side within each category's band
+            let offset_scale = offset_scale(unique_colors.clone(), cat_scale.bandwidth(), ctx);
+            let bar_width = offset_scale.bandwidth();
 
             for row in data.iter() {
-                let cat = row.get(cat_field).and_then(|v| match v {
-                    Value::String(s) => Some(s.clone()),
-                    Value::Number(n) => Some(n.to_string()),
-                    _ => None,
-                });
-                let val = row.get(val_field).and_then(|v| v.as_f64());
-                let color_val = row.get(color_f).and_then(|v| match v {
-                    Value::String(s) => Some(s.clone()),
-                    Value::Number(n) => Some(n.to_string()),
-                    _ => None,
-                });
+                let cat = get_path(row, cat_field).map(value_to_string);
+                let val = get_path(row, val_field).and_then(value_to_f64);
+                let color_val = get_path(row, color_f).map(value_to_string);
 
                 if let (Some(cat), Some(val), Some(cv)) = (cat, val, color_val) {
                     let color_idx = unique_colors.iter().position(|c| c == &cv).unwrap_or(0);
-                    let color = Color::from_hex(COLORS[color_idx % COLORS.len()]).unwrap();
+                    let fill = ctx.category_fill(&cv, color_idx);
 
+                    let offset = offset_scale.scale(&cv).unwrap_or(0.0);
                     if is_horizontal {
-                        let y = cat_scale.scale(&cat).unwrap_or(0.0) + color_idx as f64 * bar_width;
+                        let y = cat_scale.scale(&cat).unwrap_or(0.0) + offset;
                         let width = val_scale.scale(val);
                         bar_items.push(
                             MarkItem::new(Geometry::Rect {
                                 x: 0.0,
                                 y,
                                 width,
-                                height: bar_width * 0.9,
+                                height: bar_width,
                                 corner_radius: 0.0,
                             })
-                            .with_fill(color)
+                            .with_fill(fill.clone())
                             .with_datum(row.clone()),
                         );
                     } else {
-                        let x = cat_scale.scale(&cat).unwrap_or(0.0) + color_idx as f64 * bar_width;
+                        let x = cat_scale.scale(&cat).unwrap_or(0.0) + offset;
                         let bar_height = plot_area.height - val_scale.scale(val);
                         bar_items.push(
                             MarkItem::new(Geometry::Rect {
                                 x,
                                 y: val_scale.scale(val),
-                                width: bar_width * 0.9,
+                                width: bar_width,
                                 height: bar_height,
                                 corner_radius: 0.0,
                             })
-                            .with_fill(color)
+                            .with_fill(fill.clone())
                             .with_datum(row.clone()),
                         );
                     }
                 }
             }
 
-            return build_bar_group(bar_items, &cat_scale, &val_scale, encoding, plot_area, is_horizontal);
+            apply_min_length(&mut bar_items, &val_scale, ctx, is_horizontal);
+            let mut root = build_bar_group(bar_items, &cat_scale, &val_scale, encoding, ctx, is_horizontal)?;
+            ctx.scales.borrow_mut().offset = Some(offset_scale);
+            if !ctx.data_only {
+                root.add_group(generate_legend(&ColorScale::Categorical(unique_colors), color_f, ctx));
+            }
+            return Ok(root);
         }
     }
 
-    // Simple bars (no color encoding)
+    // Simple bars (no color series)
     let values = extract_numbers(data, val_field);
-    let max_value = values.iter().cloned().fold(0.0_f64, f64::max);
+    let cat_length = if is_horizontal { plot_area.height } else { plot_area.width };
+    let cat_scale = band_scale(unique_categories.clone(), (0.0, cat_length), ctx);
+    let val_scale = value_scale(&values, val_channel, is_horizontal, ctx);
 
-    let (cat_scale, val_scale) = if is_horizontal {
-        let cat_scale = BandScale::new(unique_categories.clone(), (0.0, plot_area.height)).padding(0.2);
-        let val_scale = LinearScale::new((0.0, max_value), (0.0, plot_area.width)).nice().zero();
-        (cat_scale, val_scale)
-    } else {
-        let cat_scale = BandScale::new(unique_categories.clone(), (0.0, plot_area.width)).padding(0.2);
-        let val_scale = LinearScale::new((0.0, max_value), (plot_area.height, 0.0)).nice().zero();
-        (cat_scale, val_scale)
-    };
-
-    let default_color = Color::from_hex(COLORS[0]).unwrap();
+    let default_color = ctx.series_color(0);
     let bandwidth = cat_scale.bandwidth();
 
     for row in data.iter() {
-        let cat = row.get(cat_field).and_then(|v| match v {
-            Value::String(s) => Some(s.clone()),
-            Value::Number(n) => Some(n.to_string()),
-            _ => None,
-        });
-        let val = row.get(val_field).and_then(|v| v.as_f64());
+        let cat = get_path(row, cat_field).map(value_to_string);
+        let val = get_path(row, val_field).and_then(value_to_f64);
 
         if let (Some(cat), Some(val)) = (cat, val) {
+            let color = if let Some(scale) = &value_color {
+                scale.fill(&Value::from(val), ctx).unwrap_or(Fill::Solid(default_color))
+            } else if ctx.config.color_by_category == Some(true) {
+                let index = unique_categories.iter().position(|c| *c == cat).unwrap_or(0);
+                Fill::Solid(ctx.series_color(index))
+            } else {
+                Fill::Solid(default_color)
+            };
             if is_horizontal {
                 let y = cat_scale.scale(&cat).unwrap_or(0.0);
                 let width = val_scale.scale(val);
@@ -272,7 +270,7 @@ pub fn compile_bar(
                         height: bandwidth,
                         corner_radius: 0.0,
                     })
-                    .with_fill(default_color)
+                    .with_fill(color)
                     .with_datum(row.clone()),
                 );
             } else {
@@ -286,72 +284,729 @@ pub fn compile_bar(
                         height: bar_height,
                         corner_radius: 0.0,
                     })
-                    .with_fill(default_color)
+                    .with_fill(color)
                     .with_datum(row.clone()),
                 );
             }
         }
     }
 
-    build_bar_group(bar_items, &cat_scale, &val_scale, encoding, plot_area, is_horizontal)
+    apply_min_length(&mut bar_items, &val_scale, ctx, is_horizontal);
+    let mut root = build_bar_group(bar_items, &cat_scale, &val_scale, encoding, ctx, is_horizontal)?;
+    if let Some(scale) = &value_color {
+        if !ctx.data_only {
+            root.add_group(generate_legend(scale, val_field, ctx));
+        }
+    }
+    Ok(root)
+}
+
+/// Apply `mark.minBarLength` to unstacked bars. Stacked segments are left as they are,
+/// since growing one would push it over its neighbour.
+fn apply_min_length(bar_items: &mut [MarkItem], val_scale: &ValueScale, ctx: &CompileContext, is_horizontal: bool) {
+    if let Some(min_length) = ctx.config.min_bar_length {
+        let zero = val_scale.scale(0.0);
+        for item in bar_items {
+            enforce_min_length(&mut item.geometry, min_length, zero, is_horizontal);
+        }
+    }
+}
+
+/// Grow a nonzero bar shorter than `min_length` pixels away from the zero line at `zero`;
+/// zero-length bars stay empty
+fn enforce_min_length(geometry: &mut Geometry, min_length: f64, zero: f64, is_horizontal: bool) {
+    let Geometry::Rect { x, y, width, height, .. } = geometry else {
+        return;
+    };
+    if is_horizontal {
+        if *width > 0.0 && *width < min_length {
+            // Bars left of zero grow further left
+            if *x + *width / 2.0 < zero {
+                *x -= min_length - *width;
+            }
+            *width = min_length;
+        }
+    } else if *height > 0.0 && *height < min_length {
+        // Bars above zero (smaller y) grow upward
+        if *y + *height / 2.0 <= zero {
+            *y -= min_length - *height;
+        }
+        *height = min_length;
+    }
+}
+
+/// Round each edge of a bar to a whole pixel on the canvas, the group being offset by
+/// `(dx, dy)`. Edges are rounded rather than the width, so bars that share an edge before
+/// rounding still share it after.
+fn round_edges(geometry: &mut Geometry, dx: f64, dy: f64) {
+    let Geometry::Rect { x, y, width, height, .. } = geometry else {
+        return;
+    };
+    let round = |edge: f64, offset: f64| (edge + offset).round() - offset;
+    let (left, right) = (round(*x, dx), round(*x + *width, dx));
+    let (top, bottom) = (round(*y, dy), round(*y + *height, dy));
+    (*x, *width) = (left, right - left);
+    (*y, *height) = (top, bottom - top);
+}
+
+/// Outline separating stacked segments: `mark.stackStroke` as a color, `"none"` for no
+/// outline, or by default a thin line in the background color (white unless themed)
+fn stack_separator(ctx: &CompileContext) -> Option<Stroke> {
+    let color = match ctx.config.stack_stroke.as_deref() {
+        Some("none") => return None,
+        Some(hex) => Color::from_hex(hex),
+        None => None,
+    };
+    let color = color.unwrap_or_else(|| ctx.theme.background.unwrap_or(Color::rgb(255, 255, 255)));
+    Some(Stroke::solid(color, 1.0))
+}
+
+/// One label per stack with the sum of its rows' values, just beyond the end of the stack
+fn total_labels(
+    stacked: &[StackedValue],
+    val_field: &str,
+    cat_scale: &BandScale,
+    val_scale: &ValueScale,
+    is_horizontal: bool,
+    ctx: &CompileContext,
+) -> Vec<MarkItem> {
+    // (category, stack end, total) in first-seen order
+    let mut stacks: Vec<(&str, f64, f64)> = Vec::new();
+    for sv in stacked {
+        let value = get_path(&sv.row, val_field).and_then(value_to_f64).unwrap_or(0.0);
+        match stacks.iter_mut().find(|(category, ..)| *category == sv.category) {
+            Some((_, end, total)) => {
+                *end = end.max(sv.y1);
+                *total += value;
+            }
+            None => stacks.push((&sv.category, sv.y1, value)),
+        }
+    }
+
+    stacks
+        .into_iter()
+        .filter_map(|(category, end, total)| {
+            let center = cat_scale.scale_center(category)?;
+            let end = val_scale.scale(end);
+            let (x, y, anchor, baseline) = if is_horizontal {
+                (end + TOTAL_LABEL_GAP, center, TextAnchor::Start, TextBaseline::Middle)
+            } else {
+                (center, end - TOTAL_LABEL_GAP, TextAnchor::Middle, TextBaseline::Bottom)
+            };
+            Some(
+                MarkItem::new(Geometry::Text {
+                    x,
+                    y,
+                    text: format_number_with(total, &ctx.locale),
+                    font: ctx.theme.label_font(),
+                    anchor,
+                    baseline,
+                    angle: 0.0,
+                })
+                .with_fill(ctx.theme.axis_color),
+            )
+        })
+        .collect()
+}
+
+/// Value scale for unstacked bars: from zero to the largest value, or on a log scale from
+/// the power of ten below the smallest positive value, since log(0) is undefined. Bars grow
+/// from the start of the scale, so on a log scale each keeps a finite length above that
+/// floor; values at or below zero get none.
+fn value_scale(values: &[f64], channel: &ChannelDef, is_horizontal: bool, ctx: &CompileContext) -> ValueScale {
+    let plot_area = &ctx.plot_area;
+    let range = if is_horizontal { (0.0, plot_area.width) } else { (plot_area.height, 0.0) };
+    let max_value = values.iter().cloned().fold(0.0_f64, f64::max);
+
+    if channel.scale_type() == ScaleType::Log {
+        let min_positive = values.iter().cloned().filter(|v| *v > 0.0).fold(f64::INFINITY, f64::min);
+        let (floor, max_value) = if min_positive.is_finite() {
+            (10_f64.powf(min_positive.log10().ceil() - 1.0), max_value)
+        } else {
+            (1.0, 10.0)
+        };
+        let scale = LogScale::new((floor, max_value), range);
+        return ValueScale::Log(if channel.nice() { scale.nice() } else { scale });
+    }
+
+    let scale = LinearScale::new((0.0, max_value), range).nice_if(channel.nice()).zero();
+    ValueScale::Linear(if is_horizontal { scale } else { ctx.resolve_y(scale) })
+}
+
+/// Scale placing each series' bar within a category band of width `bandwidth`, padded
+/// between bars per `mark.groupPadding`, with no padding at the band's edges
+fn offset_scale(series: Vec<String>, bandwidth: f64, ctx: &CompileContext) -> BandScale {
+    let padding = ctx.config.group_padding.unwrap_or(DEFAULT_GROUP_PADDING);
+    BandScale::new(series, (0.0, bandwidth)).padding_inner(padding).padding_outer(0.0)
+}
+
+/// Category scale for bars, padded per `mark.barPadding` / `paddingInner` / `paddingOuter`
+fn band_scale(categories: Vec<String>, range: (f64, f64), ctx: &CompileContext) -> BandScale {
+    let config = ctx.config;
+    let inner = config.padding_inner.or(config.bar_padding).unwrap_or(DEFAULT_BAR_PADDING);
+    let outer = config.padding_outer.or(config.bar_padding).unwrap_or(DEFAULT_BAR_PADDING);
+    BandScale::new(categories, range)
+        .padding_inner(inner)
+        .padding_outer(outer)
 }
 
 fn build_bar_group(
-    bar_items: Vec<MarkItem>,
+    mut bar_items: Vec<MarkItem>,
     cat_scale: &BandScale,
-    val_scale: &LinearScale,
+    val_scale: &ValueScale,
     encoding: &Encoding,
-    plot_area: &PlotArea,
+    ctx: &CompileContext,
     is_horizontal: bool,
 ) -> Result<Group, CompileError> {
+    let plot_area = &ctx.plot_area;
     let mut root = Group::new().with_transform(Transform::translate(plot_area.x, plot_area.y));
 
-    // Add bar marks
-    root.add_mark(Mark {
-        mark_type: MarkType::Rect,
-        items: bar_items,
-    });
+    if ctx.config.round == Some(true) {
+        for item in &mut bar_items {
+            round_edges(&mut item.geometry, plot_area.x, plot_area.y);
+        }
+    }
+
+    let x_axis = encoding.x.as_ref().and_then(|c| c.axis());
+    let y_axis = encoding.y.as_ref().and_then(|c| c.axis());
 
     // Generate axes
-    let x_axis_ticks = if is_horizontal {
-        val_scale.ticks(5)
+    let val_ticks = val_scale.ticks(if is_horizontal { x_axis } else { y_axis }, &ctx.locale);
+    let (x_axis_ticks, y_axis_ticks) = if is_horizontal {
+        (val_ticks, cat_scale.ticks())
     } else {
-        cat_scale.ticks()
+        (cat_scale.ticks(), val_ticks)
     };
-    let y_axis_ticks = if is_horizontal {
-        cat_scale.ticks()
+
+    let (cat_position, val_position) = (
+        PositionScale::Band(cat_scale.clone()),
+        val_scale.to_position(),
+    );
+    if is_horizontal {
+        ctx.record_scales(val_position, cat_position);
     } else {
-        val_scale
-            .ticks(5)
-            .into_iter()
-            .map(|t| crate::scale::Tick {
-                value: val_scale.scale(t.value),
-                label: t.label,
-            })
-            .collect()
-    };
+        ctx.record_scales(cat_position, val_position);
+    }
 
-    // Get axis titles
-    let x_title = encoding
-        .x
-        .as_ref()
-        .and_then(|c| c.axis())
-        .and_then(|a| a.title.as_deref());
-    let y_title = encoding
-        .y
-        .as_ref()
-        .and_then(|c| c.axis())
-        .and_then(|a| a.title.as_deref());
+    // Gridlines draw behind the bars
+    if !ctx.data_only {
+        let grids = [
+            generate_grid(AxisOrient::Bottom, &x_axis_ticks, plot_area, x_axis, ctx.theme),
+            generate_grid(AxisOrient::Left, &y_axis_ticks, plot_area, y_axis, ctx.theme),
+        ];
+        for grid in grids.into_iter().flatten() {
+            root.add_mark(grid);
+        }
+        let val_orient = if is_horizontal { AxisOrient::Bottom } else { AxisOrient::Left };
+        if let ValueScale::Linear(scale) = val_scale {
+            if let Some(zero_line) = generate_zero_line(val_orient, scale, plot_area, ctx.theme) {
+                root.add_mark(zero_line);
+            }
+        }
+    }
+
+    // Add bar marks
+    let mut bars = Mark::new(MarkType::Rect, bar_items).as_data();
+    if let Some(template) = &ctx.config.tooltip {
+        bars = bars.with_tooltip(template.clone());
+    }
+    if ctx.config.shadow == Some(true) {
+        bars = bars.with_shadow();
+    }
+    root.add_mark(bars);
+
+    if ctx.data_only {
+        return Ok(root);
+    }
 
     // Add x-axis
-    for mark in generate_axis(AxisOrient::Bottom, &x_axis_ticks, plot_area, x_title) {
-        root.add_mark(mark);
+    if !encoding.x.as_ref().is_some_and(|c| c.axis_disabled()) {
+        for mark in generate_axis(AxisOrient::Bottom, &x_axis_ticks, plot_area, x_axis, ctx.theme) {
+            root.add_mark(mark);
+        }
     }
 
     // Add y-axis
-    for mark in generate_axis(AxisOrient::Left, &y_axis_ticks, plot_area, y_title) {
-        root.add_mark(mark);
+    if !encoding.y.as_ref().is_some_and(|c| c.axis_disabled()) {
+        for mark in generate_axis(AxisOrient::Left, &y_axis_ticks, plot_area, y_axis, ctx.theme) {
+            root.add_mark(mark);
+        }
     }
 
     Ok(root)
 }
+
+#[cfg(test)]
+mod tests {
+    use serde_json::{json, Value};
+
+    use crate::ir::{Color, Fill, Geometry, Scene, TextAnchor};
+
+    fn bar_rects(scene: &Scene) -> Vec<(f64, f64)> {
+        scene
+            .mark_items()
+            .filter_map(|item| match item.geometry {
+                Geometry::Rect { x, width, .. } => Some((x, width)),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_zero_bar_padding_makes_bars_touch() {
+        let scene = crate::chart(
+            r#"{
+                "width": 320, "padding": { "top": 0, "right": 0, "bottom": 0, "left": 20 },
+                "data": { "values": [{ "c": "A", "v": 1 }, { "c": "B", "v": 2 }, { "c": "C", "v": 3 }] },
+                "mark": { "type": "bar", "barPadding": 0 },
+                "encoding": { "x": { "field": "c" }, "y": { "field": "v" } }
+            }"#,
+        )
+        .unwrap();
+
+        assert_eq!(bar_rects(&scene), vec![(0.0, 100.0), (100.0, 100.0), (200.0, 100.0)]);
+    }
+
+    #[test]
+    fn test_stacked_segments_get_separator_strokes() {
+        let spec = r#"{
+            "data": { "values": [
+                { "q": "Q1", "region": "North", "sales": 10 },
+                { "q": "Q1", "region": "South", "sales": 20 }
+            ] },
+            "mark": "bar",
+            "encoding": {
+                "x": { "field": "q" },
+                "y": { "field": "sales" },
+                "color": { "field": "region" }
+            },
+            "stack": true
+        }"#;
+        let segment_strokes = |patch: Value| -> Vec<Option<Color>> {
+            crate::chart_with(spec, patch)
+                .mark_items()
+                .filter(|item| item.datum.is_some())
+                .map(|item| item.stroke.as_ref().map(|s| s.color))
+                .collect()
+        };
+
+        let white = Some(Color::rgb(255, 255, 255));
+        assert_eq!(segment_strokes(json!({})), vec![white, white]);
+        let black = Some(Color::rgb(0, 0, 0));
+        let custom = segment_strokes(json!({ "mark": { "type": "bar", "stackStroke": "#000000" } }));
+        assert_eq!(custom, vec![black, black]);
+        let none = segment_strokes(json!({ "mark": { "type": "bar", "stackStroke": "none" } }));
+        assert_eq!(none, vec![None, None]);
+    }
+
+    #[test]
+    fn test_numeric_strings_size_bars_like_numbers() {
+        let spec = r#"{
+            "data": { "values": [
+                { "c": "A", "s": "x", "v": 10 },
+                { "c": "A", "s": "y", "v": 40 },
+                { "c": "B", "s": "x", "v": 50 }
+            ] },
+            "mark": "bar",
+            "encoding": { "x": { "field": "c" }, "y": { "field": "v" } }
+        }"#;
+        let bars = |patch: Value| -> Vec<Geometry> {
+            crate::chart_with(spec, patch)
+                .mark_items()
+                .filter(|item| item.datum.is_some())
+                .map(|item| item.geometry.clone())
+                .collect()
+        };
+        let string_row = json!({ "data": { "values": [
+            { "c": "A", "s": "x", "v": 10 },
+            { "c": "A", "s": "y", "v": "40" },
+            { "c": "B", "s": "x", "v": 50 }
+        ] } });
+        let color = json!({ "encoding": { "color": { "field": "s" } } });
+
+        assert_eq!(bars(string_row.clone()).len(), 3);
+        assert_eq!(bars(string_row.clone()), bars(json!({})));
+        let mut grouped = string_row.clone();
+        grouped["encoding"] = color["encoding"].clone();
+        assert_eq!(bars(grouped.clone()), bars(color.clone()));
+        let mut stacked = grouped;
+        stacked["stack"] = json!(true);
+        // Stacked segments come out in no fixed order
+        let sorted = |mut bars: Vec<Geometry>| {
+            bars.sort_by(|a, b| format!("{a:?}").cmp(&format!("{b:?}")));
+            bars
+        };
+        assert_eq!(sorted(bars(stacked)), sorted(bars(json!({ "encoding": color["encoding"], "stack": true }))));
+    }
+
+    #[test]
+    fn test_min_bar_length_keeps_tiny_bars_visible() {
+        let spec = r#"{
+            "padding": { "top": 0, "right": 0, "bottom": 0, "left": 0 },
+            "data": { "values": [{ "c": "A", "v": 0 }, { "c": "B", "v": 0.1 }, { "c": "C", "v": 1000 }] },
+            "mark": { "type": "bar", "minBarLength": 3 },
+            "encoding": { "x": { "field": "c" }, "y": { "field": "v" } }
+        }"#;
+        let bars = |patch: Value| -> Vec<(f64, f64, f64, f64)> {
+            crate::chart_with(spec, patch)
+                .mark_items()
+                .filter(|item| item.datum.is_some())
+                .map(|item| match item.geometry {
+                    Geometry::Rect { x, y, width, height, .. } => (x, y, width, height),
+                    _ => unreachable!(),
+                })
+                .collect()
+        };
+
+        let vertical = bars(json!({}));
+        assert_eq!(vertical[0].3, 0.0);
+        assert_eq!((vertical[1].1, vertical[1].3), (397.0, 3.0));
+        let horizontal = bars(json!({ "encoding": { "x": { "field": "v" }, "y": { "field": "c" } } }));
+        assert_eq!(horizontal[0].2, 0.0);
+        assert_eq!((horizontal[1].0, horizontal[1].2), (0.0, 3.0));
+    }
+
+    #[test]
+    fn test_min_bar_length_leaves_stacked_segments_touching() {
+        let scene = crate::chart(
+            r#"{
+                "data": { "values": [
+                    { "c": "A", "s": "x", "v": 0.1 },
+                    { "c": "A", "s": "y", "v": 1000 }
+                ] },
+                "stack": true,
+                "mark": { "type": "bar", "minBarLength": 3 },
+                "encoding": { "x": { "field": "c" }, "y": { "field": "v" }, "color": { "field": "s" } }
+            }"#,
+        )
+        .unwrap();
+
+        let spans: Vec<(f64, f64)> = scene
+            .mark_items()
+            .filter(|item| item.datum.is_some())
+            .map(|item| match item.geometry {
+                Geometry::Rect { y, height, .. } => (y, y + height),
+                _ => unreachable!(),
+            })
+            .collect();
+        // The tiny bottom segment keeps its height, so it ends where the next one starts
+        assert_eq!(spans.len(), 2);
+        assert!(spans[0].1 - spans[0].0 < 1.0);
+        assert_eq!(spans[0].0, spans[1].1);
+    }
+
+    #[test]
+    fn test_axis_false_or_null_removes_only_that_axis() {
+        let spec = r#"{
+            "data": { "values": [{ "c": "A", "v": 10 }] },
+            "mark": "bar",
+            "encoding": { "x": { "field": "c" }, "y": { "field": "v" } }
+        }"#;
+        let left_labels = |patch: Value| {
+            let scene = crate::chart_with(spec, patch);
+            let count = |anchor: TextAnchor| {
+                scene
+                    .mark_items()
+                    .filter(|item| matches!(&item.geometry, Geometry::Text { anchor: a, .. } if *a == anchor))
+                    .count()
+            };
+            (count(TextAnchor::End), count(TextAnchor::Middle))
+        };
+
+        let (with_axis, x_labels) = left_labels(json!({}));
+        assert!(with_axis > 0 && x_labels > 0);
+        assert_eq!(left_labels(json!({ "encoding": { "y": { "axis": false } } })), (0, x_labels));
+        assert_eq!(left_labels(json!({ "encoding": { "y": { "axis": null } } })), (0, x_labels));
+    }
+
+    #[test]
+    fn test_total_label_sits_above_each_stack() {
+        let scene = crate::chart(
+            r#"{
+                "data": { "values": [
+                    { "c": "A", "s": "x", "v": 10 }, { "c": "A", "s": "y", "v": 5 },
+                    { "c": "B", "s": "x", "v": 3 }, { "c": "B", "s": "y", "v": 4.5 }
+                ] },
+                "mark": { "type": "bar", "totalLabel": true },
+                "stack": true,
+                "encoding": { "x": { "field": "c" }, "y": { "field": "v" }, "color": { "field": "s" } }
+            }"#,
+        )
+        .unwrap();
+
+        let stack_top = |category: &str| {
+            scene
+                .mark_items()
+                .filter(|item| item.datum.as_ref().is_some_and(|d| d["c"] == category))
+                .filter_map(|item| match item.geometry {
+                    Geometry::Rect { y, .. } => Some(y),
+                    _ => None,
+                })
+                .fold(f64::INFINITY, f64::min)
+        };
+        let label_y = |label: &str| {
+            scene.mark_items().find_map(|item| match &item.geometry {
+                Geometry::Text { text, y, .. } if text == label => Some(*y),
+                _ => None,
+            })
+        };
+        assert_eq!(label_y("15"), Some(stack_top("A") - 4.0));
+        assert_eq!(label_y("7.50"), Some(stack_top("B") - 4.0));
+    }
+
+    #[test]
+    fn test_axis_values_place_ticks_exactly() {
+        let scene = crate::chart(
+            r#"{
+                "data": { "values": [{ "c": "A", "v": 20 }, { "c": "B", "v": 95 }] },
+                "mark": "bar",
+                "encoding": {
+                    "x": { "field": "c" },
+                    "y": { "field": "v", "axis": { "values": [0, 32, 100, 212] } }
+                }
+            }"#,
+        )
+        .unwrap();
+
+        let y_labels: Vec<_> = scene
+            .mark_items()
+            .filter_map(|item| match &item.geometry {
+                Geometry::Text { text, anchor: TextAnchor::End, .. } => Some(text.as_str()),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(y_labels, vec!["0", "32", "100"]);
+    }
+
+    #[test]
+    fn test_round_snaps_bar_edges_to_shared_whole_pixels() {
+        let scene = crate::chart(
+            r#"{
+                "width": 333.3,
+                "padding": { "top": 10, "right": 10, "bottom": 20, "left": 30.5 },
+                "data": { "values": [
+                    { "k": "A", "s": "a", "v": 1.3 }, { "k": "A", "s": "b", "v": 2.9 },
+                    { "k": "B", "s": "a", "v": 2.2 }, { "k": "B", "s": "b", "v": 0.7 },
+                    { "k": "C", "s": "a", "v": 1.9 }, { "k": "C", "s": "b", "v": 1.1 }
+                ] },
+                "mark": { "type": "bar", "round": true, "barPadding": 0 },
+                "stack": true,
+                "encoding": { "x": { "field": "k" }, "y": { "field": "v" }, "color": { "field": "s" } }
+            }"#,
+        )
+        .unwrap()
+        .flatten();
+
+        let mut rects: Vec<(f64, f64, f64, f64)> = scene
+            .mark_items()
+            .filter(|item| item.datum.is_some())
+            .filter_map(|item| match item.geometry {
+                Geometry::Rect { x, y, width, height, .. } => Some((x, y, x + width, y + height)),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(rects.len(), 6);
+        for (left, top, right, bottom) in &rects {
+            for edge in [left, top, right, bottom] {
+                assert_eq!(edge.fract(), 0.0, "{rects:?}");
+            }
+        }
+
+        // Neighbouring columns meet exactly, as do the segments of each stack
+        rects.sort_by(|a, b| a.0.total_cmp(&b.0).then(a.1.total_cmp(&b.1)));
+        for pair in rects.chunks(2) {
+            assert_eq!(pair[0].3, pair[1].1);
+        }
+        assert_eq!(rects[1].2, rects[2].0);
+        assert_eq!(rects[3].2, rects[4].0);
+    }
+
+    #[test]
+    fn test_grouped_bars_fill_their_band_through_the_offset_scale() {
+        let spec = crate::parse_spec(
+            r#"{
+                "data": { "values": [
+                    { "k": "A", "s": "a", "v": 1 }, { "k": "A", "s": "b", "v": 2 },
+                    { "k": "B", "s": "a", "v": 3 }, { "k": "B", "s": "b", "v": 4 }
+                ] },
+                "mark": { "type": "bar", "groupPadding": 0.5 },
+                "encoding": { "x": { "field": "k" }, "y": { "field": "v" }, "color": { "field": "s" } }
+            }"#,
+        )
+        .unwrap();
+        let (scene, scales) = crate::compile::compile_with_scales(&spec).unwrap();
+        let offset = scales.offset.unwrap();
+        let Some(crate::compile::PositionScale::Band(band)) = scales.x else {
+            panic!("categorical x expected");
+        };
+
+        let bars: Vec<(f64, f64)> = scene
+            .mark_items()
+            .filter(|item| item.datum.is_some())
+            .filter_map(|item| match item.geometry {
+                Geometry::Rect { x, width, .. } => Some((x, width)),
+                _ => None,
+            })
+            .collect();
+        let band_start = band.scale("A").unwrap();
+        assert_eq!(bars[0], (band_start, offset.bandwidth()));
+        assert!((bars[1].0 - bars[0].0 - offset.step()).abs() < 1e-9);
+        assert!((bars[1].0 + bars[1].1 - (band_start + band.bandwidth())).abs() < 1e-9);
+        assert!((offset.bandwidth() - offset.step() / 2.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_color_by_category_gives_simple_bars_palette_colors() {
+        let spec = r#"{
+            "data": { "values": [{ "c": "A", "v": 1 }, { "c": "B", "v": 2 }, { "c": "A", "v": 3 }] },
+            "mark": "bar",
+            "encoding": { "x": { "field": "c" }, "y": { "field": "v" } }
+        }"#;
+        let fills = |patch: Value| -> Vec<String> {
+            crate::chart_with(spec, patch)
+                .mark_items()
+                .filter(|item| item.datum.is_some())
+                .filter_map(|item| item.fill.as_ref().map(|fill| format!("{fill:?}")))
+                .collect()
+        };
+
+        let plain = fills(json!({}));
+        assert!(plain.iter().all(|fill| *fill == plain[0]));
+
+        let colored = fills(json!({ "mark": { "type": "bar", "colorByCategory": true } }));
+        assert_eq!(colored[0], plain[0]);
+        assert_ne!(colored[1], colored[0]);
+        assert_eq!(colored[2], colored[0]);
+    }
+
+    #[test]
+    fn test_nice_false_ends_the_value_axis_at_the_data() {
+        let y_domain = |y: &str| {
+            let spec = crate::parse_spec(&format!(
+                r#"{{
+                    "data": {{ "values": [{{ "c": "A", "v": 42 }}, {{ "c": "B", "v": 91 }}] }},
+                    "mark": "bar",
+                    "encoding": {{ "x": {{ "field": "c" }}, "y": {y} }}
+                }}"#
+            ))
+            .unwrap();
+            match crate::compile::compile_with_scales(&spec).unwrap().1.y {
+                Some(crate::compile::PositionScale::Linear(scale)) => scale.domain(),
+                _ => panic!("linear y expected"),
+            }
+        };
+
+        assert_eq!(y_domain(r#"{ "field": "v" }"#), (0.0, 100.0));
+        assert_eq!(y_domain(r#"{ "field": "v", "nice": false }"#), (0.0, 91.0));
+    }
+
+    #[test]
+    fn test_color_on_the_value_field_grades_bars_sequentially() {
+        let scene = crate::chart(
+            r#"{
+                "data": { "values": [{ "c": "A", "v": 10 }, { "c": "B", "v": 90 }, { "c": "C", "v": 50 }] },
+                "mark": "bar",
+                "encoding": { "x": { "field": "c" }, "y": { "field": "v" }, "color": { "field": "v" } }
+            }"#,
+        )
+        .unwrap();
+
+        let fills: Vec<Option<&Fill>> = scene
+            .mark_items()
+            .filter(|item| item.datum.is_some())
+            .map(|item| item.fill.as_ref())
+            .collect();
+        let (low, high) = crate::theme::Theme::default().sequential_range;
+        assert_eq!(
+            fills,
+            [Some(&Fill::Solid(low)), Some(&Fill::Solid(high)), Some(&Fill::Solid(low.lerp(&high, 0.5)))]
+        );
+        assert!(scene
+            .mark_items()
+            .any(|item| matches!(item.fill, Some(Fill::LinearGradient { .. }))));
+    }
+
+    #[test]
+    fn test_categories_that_print_the_same_share_a_band() {
+        let scene = crate::chart(
+            r#"{
+                "data": { "values": [{ "k": 1, "v": 10 }, { "k": "1", "v": 20 }, { "k": true, "v": 5 }] },
+                "mark": "bar",
+                "encoding": { "x": { "field": "k", "type": "nominal" }, "y": { "field": "v" } }
+            }"#,
+        )
+        .unwrap();
+
+        let xs: Vec<f64> = scene
+            .mark_items()
+            .filter(|item| item.datum.is_some())
+            .filter_map(|item| match item.geometry {
+                Geometry::Rect { x, .. } => Some(x),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(xs.len(), 3);
+        assert_eq!(xs[0], xs[1]);
+        assert!(xs[2] > xs[0]);
+    }
+
+    #[test]
+    fn test_log_value_scale_anchors_bars_at_a_positive_floor() {
+        let (scene, scales) = crate::compile::compile_with_scales(
+            &crate::parse_spec(
+                r#"{
+                    "height": 260, "padding": { "top": 10, "right": 10, "bottom": 50, "left": 50 },
+                    "data": { "values": [{ "c": "A", "v": 10 }, { "c": "B", "v": 100 }, { "c": "C", "v": 5000 }] },
+                    "mark": "bar",
+                    "encoding": { "x": { "field": "c" }, "y": { "field": "v", "scaleType": "log" } }
+                }"#,
+            )
+            .unwrap(),
+        )
+        .unwrap();
+
+        let Some(crate::compile::PositionScale::Log(scale)) = &scales.y else {
+            panic!("expected a log y scale");
+        };
+        assert_eq!(scale.domain(), (1.0, 10_000.0));
+
+        // 200px of plot height over four decades
+        let heights: Vec<f64> = scene
+            .mark_items()
+            .filter(|item| item.datum.is_some())
+            .filter_map(|item| match item.geometry {
+                Geometry::Rect { height, .. } => Some(height),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(heights.len(), 3);
+        assert!((heights[0] - 50.0).abs() < 1e-9);
+        assert!((heights[1] - 100.0).abs() < 1e-9);
+        assert!((heights[2] - 5000_f64.log10() * 50.0).abs() < 1e-9);
+
+        let labels: Vec<&str> = scene
+            .mark_items()
+            .filter_map(|item| match &item.geometry {
+                Geometry::Text { text, .. } if text.ends_with('0') || text.ends_with('K') || text == "1" => {
+                    Some(text.as_str())
+                }
+                _ => None,
+            })
+            .collect();
+        assert_eq!(labels, ["1", "10", "100", "1K", "10K"]);
+    }
+
+    #[test]
+    fn test_log_scale_on_stacked_bars_is_an_error() {
+        let spec = r#"{
+            "data": { "values": [{ "c": "A", "s": "x", "v": 10 }, { "c": "A", "s": "y", "v": 100 }] },
+            "mark": "bar",
+            "encoding": { "x": { "field": "c" }, "y": { "field": "v", "scaleType": "log" }, "color": { "field": "s" } }
+            STACK
+        }"#;
+        let result = crate::chart(&spec.replace("STACK", r#", "stack": true"#));
+        assert_eq!(result.unwrap_err().to_string(), "Invalid encoding: log scales aren't supported on stacked bars");
+        // Side by side, the bars keep their log scale
+        assert!(crate::chart(&spec.replace("STACK", "")).is_ok());
+    }
+}
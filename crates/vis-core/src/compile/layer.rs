@@ -0,0 +1,186 @@
+use std::borrow::Cow;
+use std::cell::RefCell;
+
+use serde_json::Value;
+
+use crate::ir::Group;
+use crate::spec::{DataSpec, LayerSpec, TransformSpec};
+use crate::transform::apply_transforms;
+
+use super::{compile_mark, CompileContext, CompileError, PositionScale, ScaleSet};
+
+/// Compile each layer into its own group, appended in z order. The chart-level
+/// `transforms` run on each layer's data, including layers with their own data.
+///
+/// With `shared_y`, a first pass compiles every layer to collect its quantitative y domain,
+/// and the layers are then drawn against the union of those domains.
+pub fn compile_layers(
+    layers: &[LayerSpec],
+    data: &DataSpec,
+    transforms: &[TransformSpec],
+    shared_y: bool,
+    ctx: &CompileContext,
+) -> Result<Group, CompileError> {
+    let layers = sort_layers(layers)
+        .into_iter()
+        .map(|layer| {
+            let values = layer
+                .data
+                .as_ref()
+                .unwrap_or(data)
+                .values()
+                .ok_or_else(|| CompileError::InvalidData("inline data required".to_string()))?;
+            Ok((layer, apply_transforms(values, transforms)))
+        })
+        .collect::<Result<Vec<_>, CompileError>>()?;
+
+    let ctx = CompileContext {
+        y_domain: if shared_y { union_y_domain(&layers, ctx)? } else { None },
+        ..*ctx
+    };
+
+    let mut root = Group::new();
+    for (layer, values) in &layers {
+        let layer_ctx = CompileContext {
+            config: layer.mark.config().unwrap_or(ctx.config),
+            ..ctx
+        };
+        let mut group = compile_mark(layer.mark.mark_type(), &layer.encoding, values, &layer_ctx)?;
+        if let Some(opacity) = layer.opacity {
+            group = group.with_opacity(opacity);
+        }
+        root.add_group(group);
+    }
+
+    Ok(root)
+}
+
+/// Union of the quantitative y domains the layers get when compiled on their own
+fn union_y_domain(
+    layers: &[(&LayerSpec, Cow<'_, [Value]>)],
+    ctx: &CompileContext,
+) -> Result<Option<(f64, f64)>, CompileError> {
+    let mut union: Option<(f64, f64)> = None;
+    for (layer, values) in layers {
+        let scales = RefCell::new(ScaleSet::default());
+        let layer_ctx = CompileContext {
+            config: layer.mark.config().unwrap_or(ctx.config),
+            scales: &scales,
+            ..*ctx
+        };
+        compile_mark(layer.mark.mark_type(), &layer.encoding, values, &layer_ctx)?;
+        if let Some(PositionScale::Linear(scale)) = scales.into_inner().y {
+            let (lo, hi) = scale.domain();
+            union = Some(match union {
+                Some((min, max)) => (min.min(lo), max.max(hi)),
+                None => (lo, hi),
+            });
+        }
+    }
+    Ok(union)
+}
+
+/// Order layers for drawing: lower `z` first, layers without `z` count as 0.
+/// The sort is stable, so ties keep spec order.
+pub fn sort_layers(layers: &[LayerSpec]) -> Vec<&LayerSpec> {
+    let mut sorted: Vec<&LayerSpec> = layers.iter().collect();
+    sorted.sort_by_key(|layer| layer.z.unwrap_or(0));
+    sorted
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ir::{Geometry, MarkType, SceneNode};
+
+    fn first_mark_type(node: &SceneNode) -> Option<MarkType> {
+        match node {
+            SceneNode::Group(g) => g.children.iter().find_map(|c| match c {
+                SceneNode::Mark(m) => Some(m.mark_type),
+                SceneNode::Group(_) => None,
+            }),
+            SceneNode::Mark(m) => Some(m.mark_type),
+        }
+    }
+
+    #[test]
+    fn test_layers_sorted_by_z() {
+        let scene = crate::chart(
+            r#"{
+                "data": { "values": [
+                    { "x": "A", "y": 1 },
+                    { "x": "B", "y": 3 }
+                ] },
+                "layer": [
+                    { "mark": "line", "z": 1, "encoding": { "x": { "field": "x" }, "y": { "field": "y" } } },
+                    { "mark": "bar", "encoding": { "x": { "field": "x" }, "y": { "field": "y" } } }
+                ]
+            }"#,
+        )
+        .unwrap();
+
+        let order: Vec<_> = scene.root.children.iter().filter_map(first_mark_type).collect();
+        assert_eq!(order, vec![MarkType::Rect, MarkType::Line]);
+    }
+
+    #[test]
+    fn test_layer_opacity_applies_to_its_group() {
+        let scene = crate::chart(
+            r#"{
+                "data": { "values": [{ "x": "A", "y": 1 }] },
+                "layer": [
+                    { "mark": "bar", "opacity": 0.3, "encoding": { "x": { "field": "x" }, "y": { "field": "y" } } },
+                    { "mark": "line", "encoding": { "x": { "field": "x" }, "y": { "field": "y" } } }
+                ]
+            }"#,
+        )
+        .unwrap();
+
+        let opacities: Vec<f64> = scene
+            .root
+            .children
+            .iter()
+            .filter_map(|node| match node {
+                SceneNode::Group(group) => Some(group.opacity),
+                SceneNode::Mark(_) => None,
+            })
+            .collect();
+        assert_eq!(opacities, vec![0.3, 1.0]);
+    }
+
+    #[test]
+    fn test_shared_y_puts_layers_on_one_scale() {
+        let spec = |resolve: &str| {
+            format!(
+                r#"{{
+                    "data": {{ "values": [{{ "x": "A", "y": 10 }}] }},
+                    "layer": [
+                        {{ "mark": "point", "encoding": {{ "x": {{ "field": "x" }}, "y": {{ "field": "y" }} }} }},
+                        {{
+                            "mark": "point",
+                            "data": {{ "values": [{{ "x": "A", "y": 100 }}] }},
+                            "encoding": {{ "x": {{ "field": "x" }}, "y": {{ "field": "y" }} }}
+                        }}
+                    ],
+                    "resolve": {{ "scale": {{ "y": "{resolve}" }} }}
+                }}"#
+            )
+        };
+        let point_ys = |json: &str| -> Vec<f64> {
+            crate::chart(json)
+                .unwrap()
+                .mark_items()
+                .filter_map(|item| match item.geometry {
+                    Geometry::Symbol { y, .. } => Some(y),
+                    _ => None,
+                })
+                .collect()
+        };
+
+        let independent = point_ys(&spec("independent"));
+        assert_eq!(independent[0], independent[1]);
+
+        // On a shared 0..100 scale, 10 sits far below 100
+        let shared = point_ys(&spec("shared"));
+        assert!(shared[0] > shared[1] + 100.0);
+    }
+}
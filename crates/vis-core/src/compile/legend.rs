@@ -0,0 +1,567 @@
+use serde_json::Value;
+
+use super::{channel_categories, channel_type, data_extent, limit_label, CompileContext, PlotArea};
+use crate::ir::{
+    Fill, Font, GradientStop, Geometry, Group, Mark, MarkItem, MarkType, Padding, Stroke, TextAnchor,
+    TextBaseline, Transform,
+};
+use crate::scale::{
+    get_path, value_to_f64, value_to_string, LinearScale, LogScale, NumberLocale, SequentialScale,
+    Tick,
+};
+use crate::spec::{ChannelDef, ColorScaleType, DataType, Encoding, LegendConfig, LegendOrient};
+use crate::spec::MarkType as SpecMarkType;
+use crate::stats;
+use crate::theme::Theme;
+
+/// Gap between the plot area and the legend
+const LEGEND_OFFSET: f64 = 20.0;
+
+/// Distance from a swatch or gradient bar's left edge to its labels
+const LABEL_OFFSET: f64 = SWATCH_SIZE + 8.0;
+
+/// Swatch edge length (categorical) and gradient bar width (continuous)
+const SWATCH_SIZE: f64 = 12.0;
+
+/// Vertical distance between categorical entries
+const ENTRY_HEIGHT: f64 = 20.0;
+
+/// Length of the continuous gradient bar
+const GRADIENT_LENGTH: f64 = 150.0;
+
+/// Space between the legend title and its first entry
+const TITLE_HEIGHT: f64 = 20.0;
+
+/// Gap between a label and the next column's swatch
+const COLUMN_GAP: f64 = 12.0;
+
+/// Where the color legend goes, decided along with the plot area
+#[derive(Debug, Clone, Copy, Default)]
+pub struct LegendLayout {
+    pub orient: LegendOrient,
+    pub columns: Option<usize>,
+    pub label_limit: Option<f64>,
+    /// Top-left corner of the legend relative to the plot area's origin
+    pub origin: (f64, f64),
+}
+
+impl LegendLayout {
+    fn horizontal(&self) -> bool {
+        matches!(self.orient, LegendOrient::Top | LegendOrient::Bottom)
+    }
+
+    /// Entries per row for `entries` categories
+    fn columns(&self, entries: usize) -> usize {
+        let default = if self.horizontal() { entries } else { 1 };
+        self.columns.unwrap_or(default).max(1)
+    }
+}
+
+/// How a mark maps its color field to fills
+#[derive(Debug, Clone, PartialEq)]
+pub enum ColorScale {
+    /// Distinct values in `scaleDomain` or first-seen order, colored by series index
+    Categorical(Vec<String>),
+    /// Numeric values interpolated along the theme's sequential ramp
+    Sequential(SequentialScale),
+}
+
+impl ColorScale {
+    /// Sequential when the field is quantitative, categorical otherwise
+    pub fn from_channel(channel: &ChannelDef, data: &[Value], field: &str, theme: &Theme) -> Self {
+        match channel_type(channel, data, field) {
+            DataType::Quantitative if channel.color_scale_type() == ColorScaleType::Log => {
+                // The ramp starts at the smallest positive value; the rest share the low color
+                let positive: Vec<f64> = data
+                    .iter()
+                    .filter_map(|row| get_path(row, field).and_then(value_to_f64))
+                    .filter(|value| *value > 0.0)
+                    .collect();
+                let domain = stats::min(&positive).zip(stats::max(&positive)).unwrap_or((1.0, 10.0));
+                ColorScale::Sequential(SequentialScale::new(domain, theme.sequential_range).log())
+            }
+            DataType::Quantitative => {
+                let domain = data_extent(data, field).unwrap_or((0.0, 1.0));
+                ColorScale::Sequential(SequentialScale::new(domain, theme.sequential_range))
+            }
+            DataType::Nominal | DataType::Ordinal | DataType::Temporal => {
+                ColorScale::Categorical(channel_categories(channel, data, field))
+            }
+        }
+    }
+
+    /// Fill for a field value, or `None` if the value can't be placed on the scale
+    pub fn fill(&self, value: &Value, ctx: &CompileContext) -> Option<Fill> {
+        match self {
+            ColorScale::Categorical(categories) => {
+                let value = value_to_string(value);
+                let index = categories.iter().position(|c| *c == value)?;
+                Some(ctx.category_fill(&value, index))
+            }
+            ColorScale::Sequential(scale) => value_to_f64(value).map(|v| Fill::Solid(scale.color(v))),
+        }
+    }
+}
+
+/// Room the legend for `encoding`'s color channel takes on its side of the plot: a width
+/// for left and right legends, a height for top and bottom ones
+pub fn legend_extent(
+    encoding: &Encoding,
+    data: &[Value],
+    config: &LegendConfig,
+    theme: &Theme,
+    locale: &NumberLocale,
+) -> f64 {
+    let layout = LegendLayout {
+        orient: config.orient.unwrap_or_default(),
+        columns: config.columns,
+        label_limit: config.label_limit,
+        ..LegendLayout::default()
+    };
+    let scale = encoding
+        .color
+        .as_ref()
+        .and_then(|channel| Some(ColorScale::from_channel(channel, data, channel.field()?, theme)));
+    let categories = match &scale {
+        Some(ColorScale::Categorical(categories)) => categories.as_slice(),
+        _ => &[],
+    };
+    let columns = layout.columns(categories.len());
+
+    if !layout.horizontal() {
+        let labels = match &scale {
+            Some(ColorScale::Sequential(scale)) => gradient_ticks(scale, (0.0, GRADIENT_LENGTH), locale)
+                .into_iter()
+                .map(|tick| tick.label)
+                .collect(),
+            _ => categories.to_vec(),
+        };
+        let mut width = legend_width(&labels, theme.font_size);
+        if let Some(limit) = layout.label_limit {
+            width = width.min(LEGEND_OFFSET + LABEL_OFFSET + limit);
+        }
+        let title = encoding.color.as_ref().and_then(|c| c.field()).unwrap_or_default();
+        let title_width = LEGEND_OFFSET + theme.label_font().text_width(title);
+        return width.max(title_width) + (columns - 1) as f64 * column_width(categories, layout.label_limit, theme);
+    }
+    // A gradient takes two rows: the bar and its tick labels
+    let rows = match scale {
+        Some(ColorScale::Categorical(_)) => categories.len().div_ceil(columns).max(1),
+        _ => 2,
+    };
+    TITLE_HEIGHT + rows as f64 * ENTRY_HEIGHT
+}
+
+/// Shrink `plot_area` by `extent` on the legend's side and place the legend in that space.
+/// Left, top and bottom legends sit beyond the padding so they stay clear of the axes.
+pub fn reserve_legend(
+    plot_area: &mut PlotArea,
+    padding: &Padding,
+    config: &LegendConfig,
+    extent: f64,
+) -> LegendLayout {
+    let orient = config.orient.unwrap_or_default();
+    let origin = match orient {
+        LegendOrient::Right => {
+            plot_area.width -= extent;
+            (plot_area.width + LEGEND_OFFSET, 0.0)
+        }
+        LegendOrient::Left => {
+            plot_area.x += extent;
+            plot_area.width -= extent;
+            (LEGEND_OFFSET - extent - padding.left, 0.0)
+        }
+        LegendOrient::Top => {
+            plot_area.y += extent;
+            plot_area.height -= extent;
+            (0.0, -extent)
+        }
+        LegendOrient::Bottom => {
+            plot_area.height -= extent;
+            (0.0, plot_area.height + padding.bottom)
+        }
+    };
+    LegendLayout {
+        orient,
+        columns: config.columns,
+        label_limit: config.label_limit,
+        origin,
+    }
+}
+
+/// Whether the mark draws a legend: any color field, except on arcs and bump charts, which
+/// label their wedges and lines directly
+pub fn has_legend(mark_type: SpecMarkType, encoding: &Encoding) -> bool {
+    !matches!(mark_type, SpecMarkType::Arc | SpecMarkType::Bump)
+        && encoding.color.as_ref().and_then(|c| c.field()).is_some()
+}
+
+/// Generate a legend in the space [`reserve_legend`] set aside: a swatch per category,
+/// wrapped into rows of `columns`, or a gradient bar with value ticks for sequential scales
+pub fn generate_legend(scale: &ColorScale, title: &str, ctx: &CompileContext) -> Group {
+    let theme = ctx.theme;
+    let layout = &ctx.legend;
+    let (x, y) = layout.origin;
+    let mut legend = Group::new()
+        .with_transform(Transform::translate(x, y))
+        .with_name(Group::legend_name(title));
+
+    legend.add_mark(Mark::new(
+        MarkType::Text,
+        vec![MarkItem::new(Geometry::Text {
+            x: 0.0,
+            y: 0.0,
+            text: title.to_string(),
+            font: theme.label_font(),
+            anchor: TextAnchor::Start,
+            baseline: TextBaseline::Top,
+            angle: 0.0,
+        })
+        .with_fill(theme.title_color)],
+    ));
+
+    match scale {
+        ColorScale::Categorical(categories) => {
+            // One named group per entry so a scene post-pass can hide it
+            let columns = layout.columns(categories.len());
+            let width = column_width(categories, layout.label_limit, theme);
+            for (i, category) in categories.iter().enumerate() {
+                let x = (i % columns) as f64 * width;
+                let y = TITLE_HEIGHT + (i / columns) as f64 * ENTRY_HEIGHT;
+                let mut entry = Group::new()
+                    .with_transform(Transform::translate(x, y))
+                    .with_name(category.as_str());
+                entry.add_mark(Mark::new(
+                    MarkType::Rect,
+                    vec![MarkItem::new(Geometry::Rect {
+                        x: 0.0,
+                        y: 0.0,
+                        width: SWATCH_SIZE,
+                        height: SWATCH_SIZE,
+                        corner_radius: 0.0,
+                    })
+                    .with_fill(ctx.category_fill(category, i))],
+                ));
+                entry.add_mark(Mark::new(
+                    MarkType::Text,
+                    vec![limit_label(
+                        legend_label(category, LABEL_OFFSET, SWATCH_SIZE / 2.0, theme),
+                        layout.label_limit,
+                    )],
+                ));
+                legend.add_group(entry);
+            }
+        }
+        ColorScale::Sequential(scale) if layout.horizontal() => {
+            // Low values on the left, matching the direction of an x axis
+            let (low, high) = scale.range();
+            let gradient = Fill::linear_gradient(
+                vec![GradientStop::new(0.0, low), GradientStop::new(1.0, high)],
+                0.0,
+            );
+            legend.add_mark(Mark::new(
+                MarkType::Rect,
+                vec![MarkItem::new(Geometry::Rect {
+                    x: 0.0,
+                    y: TITLE_HEIGHT,
+                    width: GRADIENT_LENGTH,
+                    height: SWATCH_SIZE,
+                    corner_radius: 0.0,
+                })
+                .with_fill(gradient)],
+            ));
+
+            let bar_bottom = TITLE_HEIGHT + SWATCH_SIZE;
+            let mut ticks = Vec::new();
+            let mut labels = Vec::new();
+            for tick in gradient_ticks(scale, (0.0, GRADIENT_LENGTH), &ctx.locale) {
+                let x = tick.value;
+                ticks.push(
+                    MarkItem::new(Geometry::Rule {
+                        x1: x,
+                        y1: bar_bottom,
+                        x2: x,
+                        y2: bar_bottom + 4.0,
+                    })
+                    .with_stroke(Stroke::solid(theme.axis_color, 1.0)),
+                );
+                labels.push(
+                    MarkItem::new(Geometry::Text {
+                        x,
+                        y: bar_bottom + 6.0,
+                        text: tick.label,
+                        font: theme.label_font(),
+                        anchor: TextAnchor::Middle,
+                        baseline: TextBaseline::Top,
+                        angle: 0.0,
+                    })
+                    .with_fill(theme.axis_color),
+                );
+            }
+            legend.add_mark(Mark::new(MarkType::Rule, ticks));
+            legend.add_mark(Mark::new(MarkType::Text, labels));
+        }
+        ColorScale::Sequential(scale) => {
+            // High values at the top, matching the direction of a y axis
+            let (low, high) = scale.range();
+            let gradient = Fill::linear_gradient(
+                vec![GradientStop::new(0.0, high), GradientStop::new(1.0, low)],
+                90.0,
+            );
+            legend.add_mark(Mark::new(
+                MarkType::Rect,
+                vec![MarkItem::new(Geometry::Rect {
+                    x: 0.0,
+                    y: TITLE_HEIGHT,
+                    width: SWATCH_SIZE,
+                    height: GRADIENT_LENGTH,
+                    corner_radius: 0.0,
+                })
+                .with_fill(gradient)],
+            ));
+
+            let range = (TITLE_HEIGHT + GRADIENT_LENGTH, TITLE_HEIGHT);
+            let mut ticks = Vec::new();
+            let mut labels = Vec::new();
+            for tick in gradient_ticks(scale, range, &ctx.locale) {
+                let y = tick.value;
+                ticks.push(
+                    MarkItem::new(Geometry::Rule {
+                        x1: SWATCH_SIZE,
+                        y1: y,
+                        x2: SWATCH_SIZE + 4.0,
+                        y2: y,
+                    })
+                    .with_stroke(Stroke::solid(theme.axis_color, 1.0)),
+                );
+                labels.push(legend_label(&tick.label, LABEL_OFFSET, y, theme));
+            }
+            legend.add_mark(Mark::new(MarkType::Rule, ticks));
+            legend.add_mark(Mark::new(MarkType::Text, labels));
+        }
+    }
+
+    legend
+}
+
+/// Ticks along a gradient bar spanning `range`, positioned in pixels: powers of ten for
+/// log scales, evenly spaced values otherwise
+fn gradient_ticks(scale: &SequentialScale, range: (f64, f64), locale: &NumberLocale) -> Vec<Tick> {
+    if scale.is_log() {
+        let position = LogScale::new(scale.domain(), range);
+        return position
+            .ticks()
+            .into_iter()
+            .map(|t| Tick { value: position.scale(t.value), label: t.label })
+            .collect();
+    }
+    let position = LinearScale::new(scale.domain(), range);
+    position
+        .ticks_with(5, locale)
+        .into_iter()
+        .map(|t| Tick { value: position.scale(t.value), label: t.label })
+        .collect()
+}
+
+/// Width a legend needs to the right of the plot for one column of `entries` labeled at
+/// `font_size`: the gap from the plot, a swatch and the widest label, estimated from the
+/// font's average glyph width
+pub fn legend_width(entries: &[String], font_size: f64) -> f64 {
+    let font = Font {
+        size: font_size,
+        ..Font::default()
+    };
+    let label_width = entries.iter().map(|entry| font.text_width(entry)).fold(0.0, f64::max);
+    LEGEND_OFFSET + LABEL_OFFSET + label_width
+}
+
+/// Distance between the swatches of neighbouring columns: enough for the longest label,
+/// up to `label_limit`
+fn column_width(categories: &[String], label_limit: Option<f64>, theme: &Theme) -> f64 {
+    let width = legend_width(categories, theme.font_size) - LEGEND_OFFSET;
+    let width = label_limit.map_or(width, |limit| width.min(LABEL_OFFSET + limit));
+    width + COLUMN_GAP
+}
+
+fn legend_label(text: &str, x: f64, y: f64, theme: &Theme) -> MarkItem {
+    MarkItem::new(Geometry::Text {
+        x,
+        y,
+        text: text.to_string(),
+        font: theme.label_font(),
+        anchor: TextAnchor::Start,
+        baseline: TextBaseline::Middle,
+        angle: 0.0,
+    })
+    .with_fill(theme.axis_color)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ir::{Color, Fill, Geometry};
+
+    #[test]
+    fn test_quantitative_color_gets_gradient_legend() {
+        let scene = crate::chart(
+            r#"{
+                "data": { "values": [
+                    { "x": 1, "y": 1, "temp": 10 },
+                    { "x": 2, "y": 2, "temp": 30 }
+                ] },
+                "mark": "point",
+                "encoding": {
+                    "x": { "field": "x" },
+                    "y": { "field": "y" },
+                    "color": { "field": "temp" }
+                }
+            }"#,
+        )
+        .unwrap();
+
+        let gradients = scene
+            .mark_items()
+            .filter(|item| matches!(item.fill, Some(Fill::LinearGradient { .. })))
+            .count();
+        assert_eq!(gradients, 1);
+
+        let labels: Vec<_> = scene
+            .mark_items()
+            .filter_map(|item| match &item.geometry {
+                Geometry::Text { text, .. } => Some(text.as_str()),
+                _ => None,
+            })
+            .collect();
+        assert!(labels.contains(&"temp"));
+        assert!(labels.contains(&"30"));
+    }
+
+    #[test]
+    fn test_color_map_pins_categories_and_cycles_the_rest() {
+        let scene = crate::chart(
+            r##"{
+                "data": { "values": [
+                    { "k": "a", "v": 1 }, { "k": "b", "v": 2 }, { "k": "c", "v": 3 }
+                ] },
+                "mark": "bar",
+                "encoding": {
+                    "x": { "field": "k" },
+                    "y": { "field": "v" },
+                    "color": { "field": "k", "colorMap": { "b": "#ff0000" } }
+                }
+            }"##,
+        )
+        .unwrap();
+
+        let theme = crate::theme::Theme::default();
+        let bar_fills: Vec<_> = scene
+            .mark_items()
+            .filter(|item| matches!(item.geometry, Geometry::Rect { width, .. } if width > 12.0))
+            .filter_map(|item| item.fill.clone())
+            .collect();
+        assert_eq!(
+            bar_fills,
+            vec![
+                Fill::Solid(theme.palette_color(0)),
+                Fill::Solid(Color::rgb(255, 0, 0)),
+                Fill::Solid(theme.palette_color(2)),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_bottom_legend_wraps_entries_below_the_axis() {
+        let scene = crate::chart(
+            r#"{
+                "height": 400, "padding": { "top": 20, "right": 20, "bottom": 40, "left": 50 },
+                "data": { "values": [
+                    { "k": "a", "v": 1 }, { "k": "b", "v": 2 }, { "k": "c", "v": 3 }
+                ] },
+                "mark": "bar",
+                "legend": { "orient": "bottom", "columns": 2 },
+                "encoding": { "x": { "field": "k" }, "y": { "field": "v" }, "color": { "field": "k" } }
+            }"#,
+        )
+        .unwrap()
+        .flatten();
+
+        // Two rows of entries take 60px below the 40px of bottom padding
+        let mut entries: Vec<(f64, f64)> = scene
+            .mark_items()
+            .filter_map(|item| match &item.geometry {
+                Geometry::Rect { x, y, width, .. } if *width == 12.0 => Some((*x, *y)),
+                _ => None,
+            })
+            .collect();
+        entries.sort_by(|a, b| a.1.total_cmp(&b.1).then(a.0.total_cmp(&b.0)));
+        assert_eq!(entries.len(), 3);
+        assert_eq!(entries[0].1, 360.0);
+        assert_eq!(entries[1].1, 360.0);
+        assert!(entries[1].0 > entries[0].0);
+        assert_eq!(entries[2], (entries[0].0, 380.0));
+    }
+
+    #[test]
+    fn test_log_color_scale_interpolates_by_order_of_magnitude() {
+        let scene = crate::chart(
+            r#"{
+                "data": { "values": [
+                    { "x": 1, "y": 1, "pop": 1 }, { "x": 2, "y": 2, "pop": 100 },
+                    { "x": 3, "y": 3, "pop": 10000 }, { "x": 4, "y": 4, "pop": 0 }
+                ] },
+                "mark": "point",
+                "encoding": {
+                    "x": { "field": "x" },
+                    "y": { "field": "y" },
+                    "color": { "field": "pop", "colorScaleType": "log" }
+                }
+            }"#,
+        )
+        .unwrap();
+
+        let (low, high) = crate::theme::Theme::default().sequential_range;
+        let point_fills: Vec<_> = scene
+            .mark_items()
+            .filter(|item| matches!(item.geometry, Geometry::Symbol { .. }))
+            .filter_map(|item| item.fill.clone())
+            .collect();
+        assert_eq!(
+            point_fills,
+            vec![Fill::Solid(low), Fill::Solid(low.lerp(&high, 0.5)), Fill::Solid(high), Fill::Solid(low)]
+        );
+
+        let labels: Vec<_> = scene
+            .mark_items()
+            .filter_map(|item| match &item.geometry {
+                Geometry::Text { text, .. } => Some(text.as_str()),
+                _ => None,
+            })
+            .collect();
+        assert!(labels.contains(&"100") && labels.contains(&"10K"));
+    }
+
+    #[test]
+    fn test_plot_area_reserves_the_legend_width_for_its_labels() {
+        let plot_width = |a: &str, b: &str| {
+            let spec = crate::parse_spec(&format!(
+                r#"{{
+                    "width": 600,
+                    "padding": {{ "top": 10, "right": 10, "bottom": 10, "left": 10 }},
+                    "data": {{ "values": [{{ "k": "{a}", "v": 1 }}, {{ "k": "{b}", "v": 2 }}] }},
+                    "mark": "bar",
+                    "encoding": {{ "x": {{ "field": "k" }}, "y": {{ "field": "v" }}, "color": {{ "field": "k" }} }}
+                }}"#
+            ))
+            .unwrap();
+            crate::compile::compile_with_scales(&spec).unwrap().1.plot_area.width
+        };
+
+        let theme = crate::theme::Theme::default();
+        let entries = ["North America".to_string(), "Europe".to_string()];
+        let width = super::legend_width(&entries, theme.font_size);
+        let text_width = theme.label_font().text_width("North America");
+        assert_eq!(width, super::LEGEND_OFFSET + super::LABEL_OFFSET + text_width);
+
+        assert_eq!(plot_width("North America", "Europe"), 600.0 - 20.0 - width);
+        assert!(plot_width("A", "B") > plot_width("North America", "Europe"));
+    }
+}
@@ -2,20 +2,158 @@ use serde_json::Value;
 use std::collections::HashMap;
 
 use super::stack::{compute_stack, max_stacked_value, min_stacked_value};
-use super::{extract_categories, extract_numbers, generate_axis, infer_data_type, CompileError, PlotArea};
-use crate::ir::{Color, Geometry, Group, Mark, MarkItem, MarkType, Point, Stroke, Transform};
-use crate::scale::{BandScale, LinearScale};
-use crate::spec::{AxisOrient, DataType, Encoding, StackConfig, StackMode};
+use super::position::{value_ticks, PositionScale};
+use super::{
+    channel_type, color_categories, distinct_categories, extract_numbers, generate_axis,
+    generate_grid, generate_legend, generate_zero_line, ColorScale, CompileContext, CompileError,
+};
+use crate::ir::{Geometry, Group, Mark, MarkItem, MarkType, Point, Stroke, TextAnchor, TextBaseline, Transform};
+use crate::scale::{get_path, value_to_f64, value_to_string, LinearScale};
+use crate::spec::{
+    AxisOrient, DataType, Encoding, Interpolate, LineAlign, MarkConfig, StackConfig, StackMode,
+};
+
+/// Default fill opacity of area marks
+const AREA_OPACITY: f64 = 0.7;
+
+/// Default fill opacity beneath line marks with `mark.fill: true`, light enough that the
+/// line stays the focus
+const FILLED_LINE_OPACITY: f64 = 0.3;
+
+/// Straight pieces each segment of a `catmull-rom` curve is drawn with
+const CURVE_SAMPLES: usize = 8;
+
+/// Default line width, unless `mark.strokeWidth` is set
+const LINE_WIDTH: f64 = 2.0;
+
+/// Default line width of bump charts, thick enough to follow a series through crossings
+const BUMP_LINE_WIDTH: f64 = 4.0;
+
+/// Gap between the end of a bump chart's line and its label
+const END_LABEL_GAP: f64 = 6.0;
+
+/// Compile line chart encoding to scene graph; with `mark.fill: true` each line also gets
+/// an area beneath it, as an area mark would
+pub fn compile_line(
+    encoding: &Encoding,
+    data: &[Value],
+    ctx: &CompileContext,
+) -> Result<Group, CompileError> {
+    let filled = ctx.config.fill.as_ref().is_some_and(|fill| fill.fills_line());
+    let fill_opacity = filled.then(|| ctx.config.fill_opacity.unwrap_or(FILLED_LINE_OPACITY));
+    compile_series(encoding, data, ctx, fill_opacity, None)
+}
 
-use super::bar::COLORS;
+/// Compile area chart encoding to scene graph: unstacked areas fill down to their baseline
+/// (`y2`, else `mark.baseline`, else zero), stacked ones to the series below
+pub fn compile_area(
+    encoding: &Encoding,
+    data: &[Value],
+    ctx: &CompileContext,
+) -> Result<Group, CompileError> {
+    compile_series(encoding, data, ctx, Some(ctx.config.fill_opacity.unwrap_or(AREA_OPACITY)), None)
+}
 
-/// Compile line chart encoding to scene graph
-pub fn compile_line(
+/// Compile a bump chart: each `color` series' rank by its `y` value at every `x`, 1 for the
+/// largest, drawn as thick lines with rank 1 at the top and each series' name at its end
+pub fn compile_bump(
     encoding: &Encoding,
     data: &[Value],
-    plot_area: &PlotArea,
-    stack_config: Option<&StackConfig>,
+    ctx: &CompileContext,
 ) -> Result<Group, CompileError> {
+    let x_field = encoding
+        .x
+        .as_ref()
+        .and_then(|c| c.field())
+        .ok_or_else(|| CompileError::MissingField("encoding.x".to_string()))?;
+    let y_field = encoding
+        .y
+        .as_ref()
+        .and_then(|c| c.field())
+        .ok_or_else(|| CompileError::MissingField("encoding.y".to_string()))?;
+    let color_field = encoding
+        .color
+        .as_ref()
+        .and_then(|c| c.field())
+        .ok_or_else(|| CompileError::MissingField("encoding.color".to_string()))?;
+
+    let ranked = rank_rows(data, x_field, y_field, color_field);
+    let ranks = ranked
+        .iter()
+        .filter_map(|row| get_path(row, y_field).and_then(value_to_f64))
+        .fold(1.0, f64::max);
+
+    // The end labels sit to the right of the lines
+    let font = ctx.theme.label_font();
+    let label_width = color_categories(encoding, data)
+        .iter()
+        .map(|series| font.text_width(series))
+        .fold(0.0, f64::max);
+    let mut plot_area = ctx.plot_area;
+    plot_area.width = (plot_area.width - label_width - END_LABEL_GAP).max(0.0);
+
+    let config = MarkConfig {
+        stroke_width: Some(ctx.config.stroke_width.unwrap_or(BUMP_LINE_WIDTH)),
+        ..ctx.config.clone()
+    };
+    let ctx = CompileContext {
+        plot_area,
+        config: &config,
+        stack: None,
+        ..*ctx
+    };
+    // Half a rank of room above the first and below the last
+    let rank_scale = LinearScale::new((0.5, ranks + 0.5), (0.0, plot_area.height));
+    compile_series(encoding, &ranked, &ctx, None, Some(rank_scale))
+}
+
+/// Copies of the rows with `y_field` replaced by the row's rank among the rows sharing its
+/// `x_field` value, 1 for the largest; ties keep data order. Rows missing any of the fields
+/// are dropped.
+fn rank_rows(data: &[Value], x_field: &str, y_field: &str, series_field: &str) -> Vec<Value> {
+    let mut by_x: Vec<(String, Vec<(f64, &Value)>)> = Vec::new();
+    for row in data {
+        let (Some(x), Some(value)) = (extract_string(row, x_field), get_path(row, y_field).and_then(value_to_f64))
+        else {
+            continue;
+        };
+        if get_path(row, series_field).is_none() {
+            continue;
+        }
+        match by_x.iter_mut().find(|(key, _)| *key == x) {
+            Some((_, rows)) => rows.push((value, row)),
+            None => by_x.push((x, vec![(value, row)])),
+        }
+    }
+
+    let mut ranked = Vec::new();
+    for (_, mut rows) in by_x {
+        rows.sort_by(|a, b| b.0.total_cmp(&a.0));
+        for (rank, (_, row)) in rows.into_iter().enumerate() {
+            let mut row = row.clone();
+            if let Value::Object(fields) = &mut row {
+                fields.insert(y_field.to_string(), Value::from(rank + 1));
+            }
+            ranked.push(row);
+        }
+    }
+    ranked
+}
+
+/// Lines, plus areas beneath them drawn at `fill_opacity` when it's set. A `rank_scale`
+/// places the series by rank for bump charts, which label each line's end rather than
+/// drawing a legend.
+fn compile_series(
+    encoding: &Encoding,
+    data: &[Value],
+    ctx: &CompileContext,
+    fill_opacity: Option<f64>,
+    rank_scale: Option<LinearScale>,
+) -> Result<Group, CompileError> {
+    let plot_area = &ctx.plot_area;
+    let area = fill_opacity.is_some();
+    let fill_opacity = fill_opacity.unwrap_or(AREA_OPACITY);
+
     // Get x and y channels
     let x_channel = encoding
         .x
@@ -33,19 +171,46 @@ pub fn compile_line(
         .field()
         .ok_or_else(|| CompileError::InvalidEncoding("y must have a field".to_string()))?;
 
-    // Infer data types
-    let x_type = x_channel.data_type().unwrap_or_else(|| infer_data_type(data, x_field));
-
-    // Extract unique x categories for band scale
-    let categories = extract_categories(data, x_field);
-    let unique_categories: Vec<String> = {
-        let mut seen = std::collections::HashSet::new();
-        categories
-            .iter()
-            .filter(|c| seen.insert((*c).clone()))
-            .cloned()
-            .collect()
+    // Determine orientation: a quantitative x with a categorical y runs the lines top to
+    // bottom, as bars turn horizontal. The series are built with the category as x and
+    // the value as y, then transposed, so from here on x and y mean category and value.
+    let is_horizontal = matches!(channel_type(x_channel, data, x_field), DataType::Quantitative)
+        && matches!(channel_type(y_channel, data, y_field), DataType::Nominal | DataType::Ordinal);
+    let (x_channel, y_channel, x_field, y_field) = if is_horizontal {
+        (y_channel, x_channel, y_field, x_field)
+    } else {
+        (x_channel, y_channel, x_field, y_field)
+    };
+    let (x_length, val_range) = if is_horizontal {
+        (plot_area.height, (0.0, plot_area.width))
+    } else {
+        (plot_area.width, (plot_area.height, 0.0))
     };
+    // Layers share a y domain only when the value runs along y
+    let resolve = |scale: LinearScale| if is_horizontal { scale } else { ctx.resolve_y(scale) };
+
+    // Categorical x uses band centers; quantitative x gets a linear axis
+    let mut x_scale = PositionScale::new(x_channel, data, x_field, (0.0, x_length), 0.0);
+    if ctx.config.align == Some(LineAlign::Edge) {
+        x_scale = x_scale.align_edges();
+    }
+
+    // Extract unique x values in first-seen order
+    let unique_categories = distinct_categories(data, x_field);
+
+    // Every x value left to right, so series can tell where they skip one
+    let mut x_keys = unique_categories.clone();
+    x_keys.sort_by(|a, b| {
+        let x_a = x_scale.position_of_key(a).unwrap_or(0.0);
+        let x_b = x_scale.position_of_key(b).unwrap_or(0.0);
+        x_a.total_cmp(&x_b)
+    });
+    let connect_nulls = ctx.config.connect_nulls != Some(false);
+    let interpolate = ctx.config.interpolate.unwrap_or(Interpolate::Linear);
+    let tension = ctx.config.tension.unwrap_or(0.0).clamp(0.0, 1.0);
+    let line_width = ctx.config.stroke_width.unwrap_or(LINE_WIDTH);
+    let y2_field = if is_horizontal { &encoding.x2 } else { &encoding.y2 };
+    let y2_field = y2_field.as_ref().and_then(|c| c.field());
 
     // Check for color encoding (multiple series)
     let color_field = encoding
@@ -56,10 +221,7 @@ pub fn compile_line(
 
     // Determine if we should stack
     let should_stack = color_field.is_some()
-        && stack_config.map_or(false, |sc| !matches!(sc, StackConfig::Enabled(false)));
-
-    // Create x scale (band for categorical, linear for quantitative)
-    let _x_is_categorical = matches!(x_type, DataType::Nominal | DataType::Ordinal);
+        && ctx.stack.is_some_and(|sc| !matches!(sc, StackConfig::Enabled(false)));
 
     let mut line_items = Vec::new();
     let mut area_items = Vec::new();
@@ -67,7 +229,7 @@ pub fn compile_line(
     if let Some(ref color_f) = color_field {
         if should_stack {
             // Stacked lines/areas
-            let stack_cfg = stack_config.cloned().unwrap_or(StackConfig::Enabled(true));
+            let stack_cfg = ctx.stack.cloned().unwrap_or(StackConfig::Enabled(true));
             let stacked = compute_stack(data, x_field, y_field, color_f, &stack_cfg);
 
             let max_val = max_stacked_value(&stacked);
@@ -79,19 +241,10 @@ pub fn compile_line(
                 _ => (0.0, max_val),
             };
 
-            let cat_scale = BandScale::new(unique_categories.clone(), (0.0, plot_area.width)).padding(0.0);
-            let val_scale = LinearScale::new((domain_min, domain_max), (plot_area.height, 0.0)).nice();
+            let val_scale = resolve(LinearScale::new((domain_min, domain_max), val_range).nice_if(y_channel.nice()));
 
             // Get unique series
-            let color_values: Vec<String> = extract_categories(data, color_f);
-            let unique_colors: Vec<String> = {
-                let mut seen = std::collections::HashSet::new();
-                color_values
-                    .iter()
-                    .filter(|c| seen.insert((*c).clone()))
-                    .cloned()
-                    .collect()
-            };
+            let unique_colors = color_categories(encoding, data);
 
             // Group stacked values by series, preserving category order
             let mut by_series: HashMap<String, Vec<(String, f64, f64)>> = HashMap::new();
@@ -105,14 +258,15 @@ pub fn compile_line(
             // Create area fills for stacked lines
             for (series, values) in &by_series {
                 let color_idx = unique_colors.iter().position(|c| c == series).unwrap_or(0);
-                let color = Color::from_hex(COLORS[color_idx % COLORS.len()]).unwrap();
+                let color = ctx.category_color(series, color_idx);
+                let fill = ctx.category_fill(series, color_idx);
 
-                // Sort by category order
+                // Sort left to right
                 let mut sorted_values: Vec<_> = values.clone();
                 sorted_values.sort_by(|a, b| {
-                    let idx_a = unique_categories.iter().position(|c| c == &a.0).unwrap_or(0);
-                    let idx_b = unique_categories.iter().position(|c| c == &b.0).unwrap_or(0);
-                    idx_a.cmp(&idx_b)
+                    let x_a = x_scale.position_of_key(&a.0).unwrap_or(0.0);
+                    let x_b = x_scale.position_of_key(&b.0).unwrap_or(0.0);
+                    x_a.total_cmp(&x_b)
                 });
 
                 // Build points for top line and baseline
@@ -120,10 +274,12 @@ pub fn compile_line(
                 let mut baseline_points = Vec::new();
 
                 for (cat, y0, y1) in &sorted_values {
-                    let x = cat_scale.scale(cat).unwrap_or(0.0) + cat_scale.bandwidth() / 2.0;
+                    let x = x_scale.position_of_key(cat).unwrap_or(0.0);
                     top_points.push(Point::new(x, val_scale.scale(*y1)));
                     baseline_points.push(Point::new(x, val_scale.scale(*y0)));
                 }
+                let top_points = interpolate_points(top_points, interpolate, tension);
+                let baseline_points = interpolate_points(baseline_points, interpolate, tension);
 
                 // Create area
                 area_items.push(
@@ -131,183 +287,718 @@ pub fn compile_line(
                         points: top_points.clone(),
                         baseline: baseline_points,
                     })
-                    .with_fill(color)
-                    .with_opacity(0.7),
+                    .with_fill(fill)
+                    .with_fill_opacity(fill_opacity)
+                    .with_datum(series_datum(color_f, series)),
                 );
 
                 // Create line on top
                 line_items.push(
                     MarkItem::new(Geometry::Line { points: top_points })
-                        .with_stroke(Stroke::solid(color, 2.0)),
+                        .with_stroke(Stroke::solid(color, line_width))
+                        .with_datum(series_datum(color_f, series)),
                 );
             }
 
-            return build_line_group(line_items, area_items, &cat_scale, &val_scale, encoding, plot_area);
+            let mut root = build_line_group(line_items, area_items, &x_scale, &val_scale, encoding, ctx, is_horizontal)?;
+            if ctx.config.sparkline != Some(true) && !ctx.data_only {
+                root.add_group(generate_legend(&ColorScale::Categorical(unique_colors), color_f, ctx));
+            }
+            return Ok(root);
         } else {
             // Multiple lines (no stacking)
-            let values = extract_numbers(data, y_field);
-            let max_value = values.iter().cloned().fold(0.0_f64, f64::max);
-
-            let cat_scale = BandScale::new(unique_categories.clone(), (0.0, plot_area.width)).padding(0.0);
-            let val_scale = LinearScale::new((0.0, max_value), (plot_area.height, 0.0)).nice().zero();
+            let val_scale = match &rank_scale {
+                Some(scale) => scale.clone(),
+                None => resolve(series_scale(data, y_field, y2_field, area, y_channel.nice(), val_range, ctx)),
+            };
+            let mut end_labels = Vec::new();
 
             // Get unique series
-            let color_values: Vec<String> = extract_categories(data, color_f);
-            let unique_colors: Vec<String> = {
-                let mut seen = std::collections::HashSet::new();
-                color_values
-                    .iter()
-                    .filter(|c| seen.insert((*c).clone()))
-                    .cloned()
-                    .collect()
-            };
+            let unique_colors = color_categories(encoding, data);
 
             // Group data by series
-            let mut by_series: HashMap<String, Vec<(String, f64)>> = HashMap::new();
+            let mut by_series: HashMap<String, Vec<(String, f64, f64)>> = HashMap::new();
             for row in data {
                 let cat = extract_string(row, x_field);
                 let series = extract_string(row, color_f);
-                let val = row.get(y_field).and_then(|v| v.as_f64());
+                let val = get_path(row, y_field).and_then(value_to_f64);
 
                 if let (Some(cat), Some(series), Some(val)) = (cat, series, val) {
-                    by_series.entry(series).or_default().push((cat, val));
+                    let base = row_baseline(row, y2_field, ctx);
+                    by_series.entry(series).or_default().push((cat, val, base));
                 }
             }
 
             for (series, values) in &by_series {
                 let color_idx = unique_colors.iter().position(|c| c == series).unwrap_or(0);
-                let color = Color::from_hex(COLORS[color_idx % COLORS.len()]).unwrap();
+                let color = ctx.category_color(series, color_idx);
+                let fill = ctx.category_fill(series, color_idx);
 
-                // Sort by category order
+                // Sort left to right
                 let mut sorted_values: Vec<_> = values.clone();
                 sorted_values.sort_by(|a, b| {
-                    let idx_a = unique_categories.iter().position(|c| c == &a.0).unwrap_or(0);
-                    let idx_b = unique_categories.iter().position(|c| c == &b.0).unwrap_or(0);
-                    idx_a.cmp(&idx_b)
+                    let x_a = x_scale.position_of_key(&a.0).unwrap_or(0.0);
+                    let x_b = x_scale.position_of_key(&b.0).unwrap_or(0.0);
+                    x_a.total_cmp(&x_b)
                 });
 
-                let points: Vec<Point> = sorted_values
-                    .iter()
-                    .map(|(cat, val)| {
-                        let x = cat_scale.scale(cat).unwrap_or(0.0) + cat_scale.bandwidth() / 2.0;
-                        let y = val_scale.scale(*val);
-                        Point::new(x, y)
-                    })
-                    .collect();
+                let mut points = Vec::new();
+                let mut baseline_points = Vec::new();
+                for (cat, val, base) in &sorted_values {
+                    let x = x_scale.position_of_key(cat).unwrap_or(0.0);
+                    points.push((cat.as_str(), Point::new(x, val_scale.scale(*val))));
+                    baseline_points.push((cat.as_str(), Point::new(x, val_scale.scale(*base))));
+                }
 
-                line_items.push(
-                    MarkItem::new(Geometry::Line { points })
-                        .with_stroke(Stroke::solid(color, 2.0)),
-                );
+                if let (Some(_), Some((_, end))) = (&rank_scale, points.last()) {
+                    end_labels.push(
+                        MarkItem::new(Geometry::Text {
+                            x: end.x + END_LABEL_GAP,
+                            y: end.y,
+                            text: series.clone(),
+                            font: ctx.theme.label_font(),
+                            anchor: TextAnchor::Start,
+                            baseline: TextBaseline::Middle,
+                            angle: 0.0,
+                        })
+                        .with_fill(color),
+                    );
+                }
+
+                let segments = line_segments(points, &x_keys, connect_nulls);
+                let baselines = line_segments(baseline_points, &x_keys, connect_nulls);
+                for (points, baseline) in segments.into_iter().zip(baselines) {
+                    let points = interpolate_points(points, interpolate, tension);
+                    if area {
+                        area_items.push(
+                            MarkItem::new(Geometry::Area {
+                                points: points.clone(),
+                                baseline: interpolate_points(baseline, interpolate, tension),
+                            })
+                            .with_fill(fill.clone())
+                            .with_fill_opacity(fill_opacity)
+                            .with_datum(series_datum(color_f, series)),
+                        );
+                    }
+                    line_items.push(
+                        MarkItem::new(Geometry::Line { points })
+                            .with_stroke(Stroke::solid(color, line_width))
+                            .with_datum(series_datum(color_f, series)),
+                    );
+                }
             }
 
-            return build_line_group(line_items, area_items, &cat_scale, &val_scale, encoding, plot_area);
+            let mut root = build_line_group(line_items, area_items, &x_scale, &val_scale, encoding, ctx, is_horizontal)?;
+            if rank_scale.is_some() {
+                root.add_mark(Mark::new(MarkType::Text, end_labels).as_data());
+            } else if ctx.config.sparkline != Some(true) && !ctx.data_only {
+                root.add_group(generate_legend(&ColorScale::Categorical(unique_colors), color_f, ctx));
+            }
+            return Ok(root);
         }
     }
 
     // Simple line (single series)
-    let values = extract_numbers(data, y_field);
-    let max_value = values.iter().cloned().fold(0.0_f64, f64::max);
-
-    let cat_scale = BandScale::new(unique_categories.clone(), (0.0, plot_area.width)).padding(0.0);
-    let val_scale = LinearScale::new((0.0, max_value), (plot_area.height, 0.0)).nice().zero();
+    let val_scale = resolve(series_scale(data, y_field, y2_field, area, y_channel.nice(), val_range, ctx));
 
-    let default_color = Color::from_hex(COLORS[0]).unwrap();
+    let default_color = ctx.series_color(0);
 
     // Build points in category order
-    let mut points_map: HashMap<String, f64> = HashMap::new();
+    let mut points_map: HashMap<String, (f64, f64)> = HashMap::new();
     for row in data {
         let cat = extract_string(row, x_field);
-        let val = row.get(y_field).and_then(|v| v.as_f64());
+        let val = get_path(row, y_field).and_then(value_to_f64);
 
         if let (Some(cat), Some(val)) = (cat, val) {
-            points_map.insert(cat, val);
+            points_map.insert(cat, (val, row_baseline(row, y2_field, ctx)));
         }
     }
 
-    let points: Vec<Point> = unique_categories
+    let mut keyed: Vec<(&str, Point, Point)> = unique_categories
         .iter()
         .filter_map(|cat| {
-            points_map.get(cat).map(|val| {
-                let x = cat_scale.scale(cat).unwrap_or(0.0) + cat_scale.bandwidth() / 2.0;
-                let y = val_scale.scale(*val);
-                Point::new(x, y)
+            points_map.get(cat).map(|(val, base)| {
+                let x = x_scale.position_of_key(cat).unwrap_or(0.0);
+                let top = Point::new(x, val_scale.scale(*val));
+                let bottom = Point::new(x, val_scale.scale(*base));
+                (cat.as_str(), top, bottom)
             })
         })
         .collect();
+    keyed.sort_by(|a, b| a.1.x.total_cmp(&b.1.x));
+
+    let points = keyed.iter().map(|(cat, top, _)| (*cat, *top)).collect();
+    let baseline_points = keyed.iter().map(|(cat, _, bottom)| (*cat, *bottom)).collect();
+    let segments = line_segments(points, &x_keys, connect_nulls);
+    let baselines = line_segments(baseline_points, &x_keys, connect_nulls);
+    for (points, baseline) in segments.into_iter().zip(baselines) {
+        let points = interpolate_points(points, interpolate, tension);
+        if area {
+            area_items.push(
+                MarkItem::new(Geometry::Area {
+                    points: points.clone(),
+                    baseline: interpolate_points(baseline, interpolate, tension),
+                })
+                .with_fill(ctx.series_fill(0))
+                .with_fill_opacity(fill_opacity),
+            );
+        }
+        line_items.push(
+            MarkItem::new(Geometry::Line { points })
+                .with_stroke(Stroke::solid(default_color, line_width)),
+        );
+    }
+
+    build_line_group(line_items, area_items, &x_scale, &val_scale, encoding, ctx, is_horizontal)
+}
+
+/// Value scale for unstacked series over `range`, from zero to the largest value; areas also
+/// take in their baselines so a baseline below zero or above the data stays on the chart
+fn series_scale(
+    data: &[Value],
+    y_field: &str,
+    y2_field: Option<&str>,
+    area: bool,
+    nice: bool,
+    range: (f64, f64),
+    ctx: &CompileContext,
+) -> LinearScale {
+    let values = extract_numbers(data, y_field);
+    let mut min_value = 0.0_f64;
+    let mut max_value = values.iter().cloned().fold(0.0_f64, f64::max);
+    if area {
+        for base in data.iter().map(|row| row_baseline(row, y2_field, ctx)) {
+            min_value = min_value.min(base);
+            max_value = max_value.max(base);
+        }
+    }
+    LinearScale::new((min_value, max_value), range).nice_if(nice).zero()
+}
+
+/// Value an area row fills down to: its `y2` value, else `mark.baseline`, else zero
+fn row_baseline(row: &Value, y2_field: Option<&str>, ctx: &CompileContext) -> f64 {
+    y2_field
+        .and_then(|field| get_path(row, field))
+        .and_then(value_to_f64)
+        .or(ctx.config.baseline)
+        .unwrap_or(0.0)
+}
+
+/// Split a series' points (sorted left to right, keyed by x value) into runs without gaps:
+/// a new run starts whenever the series skips one of `x_keys`. With `connect` set the
+/// points stay a single line.
+fn line_segments(points: Vec<(&str, Point)>, x_keys: &[String], connect: bool) -> Vec<Vec<Point>> {
+    if connect {
+        return vec![points.into_iter().map(|(_, point)| point).collect()];
+    }
+
+    let mut segments: Vec<Vec<Point>> = Vec::new();
+    let mut last_index = None;
+    for (key, point) in points {
+        let index = x_keys.iter().position(|k| k == key);
+        let continues = match (last_index, index) {
+            (Some(last), Some(index)) => index <= last + 1,
+            _ => false,
+        };
+        match segments.last_mut() {
+            Some(segment) if continues => segment.push(point),
+            _ => segments.push(vec![point]),
+        }
+        last_index = index;
+    }
+    segments
+}
+
+/// Insert the corner points of a step interpolation, or the samples of a curve, between
+/// each pair of points; linear interpolation leaves the points as they are
+fn interpolate_points(points: Vec<Point>, interpolate: Interpolate, tension: f64) -> Vec<Point> {
+    if interpolate == Interpolate::Linear || points.len() < 2 {
+        return points;
+    }
+    if interpolate == Interpolate::CatmullRom {
+        return cardinal_spline(&points, tension);
+    }
 
-    line_items.push(
-        MarkItem::new(Geometry::Line { points })
-            .with_stroke(Stroke::solid(default_color, 2.0)),
-    );
+    let mut stepped = Vec::with_capacity(points.len() * 2 - 1);
+    stepped.push(points[0]);
+    for pair in points.windows(2) {
+        let (from, to) = (pair[0], pair[1]);
+        stepped.push(match interpolate {
+            Interpolate::StepBefore => Point::new(from.x, to.y),
+            _ => Point::new(to.x, from.y),
+        });
+        stepped.push(to);
+    }
+    stepped
+}
+
+/// Cardinal spline through `points`, sampled into [`CURVE_SAMPLES`] straight pieces per
+/// segment. Each point's tangent is `(1 - tension)` times half the chord between its
+/// neighbours (the end points reuse themselves), so tension 0 gives the standard
+/// Catmull-Rom curve and 1 straight lines.
+fn cardinal_spline(points: &[Point], tension: f64) -> Vec<Point> {
+    let scale = (1.0 - tension) / 2.0;
+    let tangent = |i: usize| {
+        let (before, after) = (points[i.saturating_sub(1)], points[(i + 1).min(points.len() - 1)]);
+        ((after.x - before.x) * scale, (after.y - before.y) * scale)
+    };
 
-    build_line_group(line_items, area_items, &cat_scale, &val_scale, encoding, plot_area)
+    let mut curve = Vec::with_capacity((points.len() - 1) * CURVE_SAMPLES + 1);
+    curve.push(points[0]);
+    for i in 0..points.len() - 1 {
+        let (from, to) = (points[i], points[i + 1]);
+        let (m0, m1) = (tangent(i), tangent(i + 1));
+        for step in 1..=CURVE_SAMPLES {
+            // Cubic Hermite basis
+            let t = step as f64 / CURVE_SAMPLES as f64;
+            let (t2, t3) = (t * t, t * t * t);
+            let h00 = 2.0 * t3 - 3.0 * t2 + 1.0;
+            let h10 = t3 - 2.0 * t2 + t;
+            let h01 = -2.0 * t3 + 3.0 * t2;
+            let h11 = t3 - t2;
+            curve.push(Point::new(
+                h00 * from.x + h10 * m0.0 + h01 * to.x + h11 * m1.0,
+                h00 * from.y + h10 * m0.1 + h01 * to.y + h11 * m1.1,
+            ));
+        }
+    }
+    curve
 }
 
+/// Datum for a whole series line or area: just its color field value
+fn series_datum(color_field: &str, series: &str) -> Value {
+    let mut datum = serde_json::Map::new();
+    datum.insert(color_field.to_string(), Value::String(series.to_string()));
+    Value::Object(datum)
+}
+
+/// Lay out the series built with the category as x: transposed when `is_horizontal`, then
+/// drawn with their gridlines and axes
 fn build_line_group(
-    line_items: Vec<MarkItem>,
-    area_items: Vec<MarkItem>,
-    cat_scale: &BandScale,
+    mut line_items: Vec<MarkItem>,
+    mut area_items: Vec<MarkItem>,
+    cat_scale: &PositionScale,
     val_scale: &LinearScale,
     encoding: &Encoding,
-    plot_area: &PlotArea,
+    ctx: &CompileContext,
+    is_horizontal: bool,
 ) -> Result<Group, CompileError> {
+    let plot_area = &ctx.plot_area;
     let mut root = Group::new().with_transform(Transform::translate(plot_area.x, plot_area.y));
 
+    // Sparklines and data-only compiles draw the data alone
+    let sparkline = ctx.config.sparkline == Some(true) || ctx.data_only;
+
+    let x_axis = encoding.x.as_ref().and_then(|c| c.axis());
+    let y_axis = encoding.y.as_ref().and_then(|c| c.axis());
+
+    // Generate axes
+    let (cat_position, val_position) = (cat_scale.clone(), PositionScale::Linear(val_scale.clone()));
+    let (x_axis_ticks, y_axis_ticks) = if is_horizontal {
+        for item in line_items.iter_mut().chain(&mut area_items) {
+            transpose(&mut item.geometry);
+        }
+        ctx.record_scales(val_position, cat_position);
+        (value_ticks(val_scale, x_axis, &ctx.locale), cat_scale.ticks(y_axis, &ctx.locale))
+    } else {
+        ctx.record_scales(cat_position, val_position);
+        (cat_scale.ticks(x_axis, &ctx.locale), value_ticks(val_scale, y_axis, &ctx.locale))
+    };
+    let val_orient = if is_horizontal { AxisOrient::Bottom } else { AxisOrient::Left };
+
+    // Gridlines draw behind the data
+    if !sparkline {
+        let grids = [
+            generate_grid(AxisOrient::Bottom, &x_axis_ticks, plot_area, x_axis, ctx.theme),
+            generate_grid(AxisOrient::Left, &y_axis_ticks, plot_area, y_axis, ctx.theme),
+        ];
+        for grid in grids.into_iter().flatten() {
+            root.add_mark(grid);
+        }
+        if let Some(zero_line) = generate_zero_line(val_orient, val_scale, plot_area, ctx.theme) {
+            root.add_mark(zero_line);
+        }
+    }
+
+    let shadowed = |mark: Mark| if ctx.config.shadow == Some(true) { mark.with_shadow() } else { mark };
+
     // Add area marks first (behind lines)
     if !area_items.is_empty() {
-        root.add_mark(Mark {
-            mark_type: MarkType::Area,
-            items: area_items,
-        });
+        root.add_mark(shadowed(Mark::new(MarkType::Area, area_items).as_data()));
     }
 
     // Add line marks
-    root.add_mark(Mark {
-        mark_type: MarkType::Line,
-        items: line_items,
-    });
-
-    // Generate axes
-    let x_axis_ticks = cat_scale.ticks();
-    let y_axis_ticks: Vec<crate::scale::Tick> = val_scale
-        .ticks(5)
-        .into_iter()
-        .map(|t| crate::scale::Tick {
-            value: val_scale.scale(t.value),
-            label: t.label,
-        })
-        .collect();
+    root.add_mark(shadowed(Mark::new(MarkType::Line, line_items).as_data()));
 
-    // Get axis titles
-    let x_title = encoding
-        .x
-        .as_ref()
-        .and_then(|c| c.axis())
-        .and_then(|a| a.title.as_deref());
-    let y_title = encoding
-        .y
-        .as_ref()
-        .and_then(|c| c.axis())
-        .and_then(|a| a.title.as_deref());
+    if sparkline {
+        return Ok(root);
+    }
 
     // Add x-axis
-    for mark in generate_axis(AxisOrient::Bottom, &x_axis_ticks, plot_area, x_title) {
-        root.add_mark(mark);
+    if !encoding.x.as_ref().is_some_and(|c| c.axis_disabled()) {
+        for mark in generate_axis(AxisOrient::Bottom, &x_axis_ticks, plot_area, x_axis, ctx.theme) {
+            root.add_mark(mark);
+        }
     }
 
     // Add y-axis
-    for mark in generate_axis(AxisOrient::Left, &y_axis_ticks, plot_area, y_title) {
-        root.add_mark(mark);
+    if !encoding.y.as_ref().is_some_and(|c| c.axis_disabled()) {
+        for mark in generate_axis(AxisOrient::Left, &y_axis_ticks, plot_area, y_axis, ctx.theme) {
+            root.add_mark(mark);
+        }
     }
 
     Ok(root)
 }
 
+/// Swap the x and y of a line or area's points
+fn transpose(geometry: &mut Geometry) {
+    let swap = |point: &mut Point| *point = Point::new(point.y, point.x);
+    match geometry {
+        Geometry::Line { points } => points.iter_mut().for_each(swap),
+        Geometry::Area { points, baseline } => points.iter_mut().chain(baseline).for_each(swap),
+        _ => {}
+    }
+}
+
 fn extract_string(row: &Value, field: &str) -> Option<String> {
-    row.get(field).map(|v| match v {
-        Value::String(s) => s.clone(),
-        Value::Number(n) => n.to_string(),
-        _ => v.to_string(),
-    })
+    get_path(row, field).map(value_to_string)
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::{json, Value};
+
+    use super::CURVE_SAMPLES;
+    use crate::ir::Geometry;
+
+    #[test]
+    fn test_explicit_quantitative_x_spaces_points_by_value() {
+        let scene = crate::chart(
+            r#"{
+                "width": 320, "padding": { "top": 0, "right": 0, "bottom": 0, "left": 20 },
+                "data": { "values": [
+                    { "t": 0, "v": 1 }, { "t": 10, "v": 2 }, { "t": 0, "v": 3 }, { "t": 30, "v": 4 }
+                ] },
+                "mark": "line",
+                "encoding": {
+                    "x": { "field": "t", "type": "quantitative" },
+                    "y": { "field": "v" }
+                }
+            }"#,
+        )
+        .unwrap();
+
+        let xs: Vec<f64> = scene
+            .mark_items()
+            .find_map(|item| match &item.geometry {
+                Geometry::Line { points } => Some(points.iter().map(|p| p.x).collect()),
+                _ => None,
+            })
+            .unwrap();
+        assert_eq!(xs, vec![0.0, 100.0, 300.0]);
+    }
+
+    #[test]
+    fn test_sparkline_draws_only_the_line_across_the_canvas() {
+        let scene = crate::chart(
+            r#"{
+                "width": 100, "height": 20,
+                "data": { "values": [{ "d": "a", "v": 1 }, { "d": "b", "v": 3 }] },
+                "mark": { "type": "line", "sparkline": true },
+                "encoding": { "x": { "field": "d" }, "y": { "field": "v" } }
+            }"#,
+        )
+        .unwrap();
+
+        let geometries: Vec<_> = scene.mark_items().map(|item| &item.geometry).collect();
+        let [Geometry::Line { points }] = geometries.as_slice() else {
+            panic!("expected a single line, got {geometries:?}");
+        };
+        assert_eq!((points[0].x, points[1].x), (25.0, 75.0));
+        assert_eq!(points[1].y, 0.0);
+    }
+
+    #[test]
+    fn test_edge_align_puts_end_points_on_plot_edges() {
+        let scene = crate::chart(
+            r#"{
+                "width": 320, "padding": { "top": 0, "right": 0, "bottom": 0, "left": 20 },
+                "data": { "values": [{ "m": "Jan", "v": 1 }, { "m": "Feb", "v": 2 }, { "m": "Mar", "v": 3 }] },
+                "mark": { "type": "line", "align": "edge" },
+                "encoding": { "x": { "field": "m" }, "y": { "field": "v" } }
+            }"#,
+        )
+        .unwrap();
+
+        let xs: Vec<f64> = scene
+            .mark_items()
+            .find_map(|item| match &item.geometry {
+                Geometry::Line { points } => Some(points.iter().map(|p| p.x).collect()),
+                _ => None,
+            })
+            .unwrap();
+        assert_eq!(xs, vec![0.0, 150.0, 300.0]);
+    }
+
+    #[test]
+    fn test_connect_nulls_false_breaks_line_at_missing_x() {
+        let spec = |connect: bool| {
+            format!(
+                r#"{{
+                    "data": {{ "values": [
+                        {{ "m": "Jan", "s": "a", "v": 1 }}, {{ "m": "Feb", "s": "b", "v": 2 }},
+                        {{ "m": "Mar", "s": "a", "v": 3 }}, {{ "m": "Apr", "s": "a", "v": 4 }}
+                    ] }},
+                    "mark": {{ "type": "line", "connectNulls": {connect} }},
+                    "encoding": {{ "x": {{ "field": "m" }}, "y": {{ "field": "v" }}, "color": {{ "field": "s" }} }}
+                }}"#
+            )
+        };
+        let series_a_lines = |connect: bool| -> Vec<usize> {
+            let scene = crate::chart(&spec(connect)).unwrap();
+            let mut lengths: Vec<usize> = scene
+                .mark_items()
+                .filter(|item| item.datum.as_ref().is_some_and(|d| d["s"] == "a"))
+                .filter_map(|item| match &item.geometry {
+                    Geometry::Line { points } => Some(points.len()),
+                    _ => None,
+                })
+                .collect();
+            lengths.sort();
+            lengths
+        };
+
+        assert_eq!(series_a_lines(true), vec![3]);
+        assert_eq!(series_a_lines(false), vec![1, 2]);
+    }
+
+    #[test]
+    fn test_step_after_holds_values_on_line_and_area_baseline() {
+        let scene = crate::chart(
+            r#"{
+                "width": 320, "height": 120, "padding": { "top": 0, "right": 0, "bottom": 0, "left": 20 },
+                "data": { "values": [
+                    { "t": "a", "s": "up", "v": 1 }, { "t": "b", "s": "up", "v": 0 },
+                    { "t": "a", "s": "down", "v": 0 }, { "t": "b", "s": "down", "v": 1 }
+                ] },
+                "mark": { "type": "line", "interpolate": "step-after" },
+                "stack": true,
+                "encoding": { "x": { "field": "t" }, "y": { "field": "v" }, "color": { "field": "s" } }
+            }"#,
+        )
+        .unwrap();
+
+        let mut areas = 0;
+        for item in scene.mark_items() {
+            if let Geometry::Area { points, baseline } = &item.geometry {
+                areas += 1;
+                assert_eq!(points.len(), 3);
+                assert_eq!(baseline.len(), 3);
+                // The corner sits at the next x but keeps the previous value
+                assert_eq!((points[1].x, points[1].y), (points[2].x, points[0].y));
+                assert_eq!((baseline[1].x, baseline[1].y), (baseline[2].x, baseline[0].y));
+            }
+        }
+        assert_eq!(areas, 2);
+        let lines = scene
+            .mark_items()
+            .filter(|item| matches!(&item.geometry, Geometry::Line { points } if points.len() == 3))
+            .count();
+        assert_eq!(lines, 2);
+    }
+
+    #[test]
+    fn test_area_fills_to_y2_or_constant_baseline() {
+        let spec = r#"{
+            "height": 120, "padding": { "top": 10, "right": 0, "bottom": 10, "left": 0 },
+            "data": { "values": [{ "t": "a", "v": 100, "lo": 20 }, { "t": "b", "v": 80, "lo": 40 }] },
+            "mark": "area",
+            "encoding": { "x": { "field": "t" }, "y": { "field": "v" } }
+        }"#;
+        let baseline = |patch: Value| -> Vec<f64> {
+            let scene = crate::chart_with(spec, patch);
+            let baseline = scene.mark_items().find_map(|item| match &item.geometry {
+                Geometry::Area { baseline, .. } => Some(baseline.iter().map(|p| p.y).collect()),
+                _ => None,
+            });
+            baseline.unwrap()
+        };
+
+        // 100px tall plot over a 0..100 domain
+        assert_eq!(baseline(json!({})), vec![100.0, 100.0]);
+        assert_eq!(baseline(json!({ "mark": { "type": "area", "baseline": 50 } })), vec![50.0, 50.0]);
+        assert_eq!(baseline(json!({ "encoding": { "y2": { "field": "lo" } } })), vec![80.0, 60.0]);
+    }
+
+    #[test]
+    fn test_zero_line_marks_a_domain_crossing_zero() {
+        let spec = r#"{
+            "height": 200, "padding": { "top": 0, "right": 0, "bottom": 0, "left": 0 },
+            "data": { "values": [{ "x": 1, "y": 5 }, { "x": 2, "y": 20 }] },
+            "mark": "line",
+            "encoding": { "x": { "field": "x" }, "y": { "field": "y" } }
+        }"#;
+        let zero_lines = |patch: Value| -> Vec<f64> {
+            crate::chart_with(spec, patch)
+                .mark_items()
+                .filter_map(|item| match item.geometry {
+                    Geometry::Rule { x1: 0.0, y1, x2, y2 } if x2 > 0.0 && y1 == y2 && y1 < 200.0 => Some(y1),
+                    _ => None,
+                })
+                .collect()
+        };
+
+        // The -10 baseline stretches the domain to [-10, 20], so zero sits a third of the way up
+        let area = zero_lines(json!({ "mark": { "type": "area", "baseline": -10 } }));
+        assert_eq!(area, vec![200.0 * 2.0 / 3.0]);
+        assert!(zero_lines(json!({})).is_empty());
+    }
+
+    #[test]
+    fn test_filled_line_adds_a_faint_area_per_series() {
+        let spec = r#"{
+            "data": { "values": [
+                { "x": 1, "y": 5, "s": "a" }, { "x": 2, "y": 20, "s": "a" },
+                { "x": 1, "y": 8, "s": "b" }, { "x": 2, "y": 4, "s": "b" }
+            ] },
+            "mark": "line",
+            "encoding": { "x": { "field": "x" }, "y": { "field": "y" } }
+        }"#;
+        let area_opacities = |patch: Value| {
+            let scene = crate::chart_with(spec, patch);
+            let lines = scene.mark_items().filter(|item| matches!(item.geometry, Geometry::Line { .. })).count();
+            let opacities: Vec<Option<f64>> = scene
+                .mark_items()
+                .filter(|item| matches!(item.geometry, Geometry::Area { .. }))
+                .map(|item| item.fill_opacity)
+                .collect();
+            (lines, opacities)
+        };
+
+        assert_eq!(area_opacities(json!({})), (1, vec![]));
+        assert_eq!(area_opacities(json!({ "mark": { "type": "line", "fill": true } })), (1, vec![Some(0.3)]));
+        let series = json!({
+            "mark": { "type": "line", "fill": true, "fillOpacity": 0.5 },
+            "encoding": { "color": { "field": "s" } }
+        });
+        assert_eq!(area_opacities(series), (2, vec![Some(0.5), Some(0.5)]));
+    }
+
+    #[test]
+    fn test_catmull_rom_passes_through_points_and_tension_straightens_it() {
+        let spec = r#"{
+            "data": { "values": [{ "x": 0, "y": 0 }, { "x": 1, "y": 10 }, { "x": 2, "y": 0 }] },
+            "mark": "line",
+            "encoding": { "x": { "field": "x" }, "y": { "field": "y" } }
+        }"#;
+        let line = |patch: Value| {
+            let scene = crate::chart_with(spec, patch);
+            let points = scene.mark_items().find_map(|item| match &item.geometry {
+                Geometry::Line { points } => Some(points.clone()),
+                _ => None,
+            });
+            points.unwrap()
+        };
+
+        let linear = line(json!({}));
+        let curve = line(json!({ "mark": { "type": "line", "interpolate": "catmull-rom" } }));
+        assert_eq!(curve.len(), 2 * CURVE_SAMPLES + 1);
+        for (i, point) in linear.iter().enumerate() {
+            assert_eq!(curve[i * CURVE_SAMPLES], *point);
+        }
+        // Rounding the peak, the curve rises above the straight path to it
+        let midway = curve[CURVE_SAMPLES / 2];
+        let straight_y = (linear[0].y + linear[1].y) / 2.0;
+        assert!(midway.y < straight_y);
+
+        let tight = line(json!({ "mark": { "type": "line", "interpolate": "catmull-rom", "tension": 1 } }));
+        assert!((tight[CURVE_SAMPLES / 2].y - straight_y).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_categorical_y_with_quantitative_x_runs_lines_down_the_chart() {
+        let (scene, scales) = crate::compile::compile_with_scales(
+            &crate::parse_spec(
+                r#"{
+                    "width": 300, "height": 200, "padding": { "top": 0, "right": 0, "bottom": 0, "left": 0 },
+                    "data": { "values": [
+                        { "stage": "Plan", "done": 10 }, { "stage": "Build", "done": 60 }, { "stage": "Ship", "done": 90 }
+                    ] },
+                    "mark": "line",
+                    "encoding": { "x": { "field": "done" }, "y": { "field": "stage" } }
+                }"#,
+            )
+            .unwrap(),
+        )
+        .unwrap();
+
+        let points = scene
+            .mark_items()
+            .find_map(|item| match &item.geometry {
+                Geometry::Line { points } if points.len() == 3 => Some(points.clone()),
+                _ => None,
+            })
+            .unwrap();
+        assert!(points.windows(2).all(|pair| pair[0].y < pair[1].y && pair[0].x < pair[1].x));
+        assert_eq!(scales.scale_x(&serde_json::json!(60)), Some(points[1].x));
+        assert_eq!(scales.scale_y(&serde_json::json!("Build")), Some(points[1].y));
+    }
+
+    #[test]
+    fn test_bump_ranks_series_per_x_with_first_on_top_and_labels_the_ends() {
+        let (scene, scales) = crate::compile::compile_with_scales(
+            &crate::parse_spec(
+                r#"{
+                    "data": { "values": [
+                        { "year": "2023", "team": "A", "wins": 10 }, { "year": "2024", "team": "A", "wins": 30 },
+                        { "year": "2023", "team": "B", "wins": 20 }, { "year": "2024", "team": "B", "wins": 10 },
+                        { "year": "2023", "team": "C", "wins": 5 }, { "year": "2024", "team": "C", "wins": 40 }
+                    ] },
+                    "mark": "bump",
+                    "encoding": { "x": { "field": "year" }, "y": { "field": "wins" }, "color": { "field": "team" } }
+                }"#,
+            )
+            .unwrap(),
+        )
+        .unwrap();
+
+        let rank_y = |rank: i32| scales.y.as_ref().unwrap().position(&serde_json::json!(rank)).unwrap();
+        assert!(rank_y(1) < rank_y(3));
+
+        let mut lines: Vec<(String, Vec<f64>, f64)> = scene
+            .mark_items()
+            .filter_map(|item| match &item.geometry {
+                Geometry::Line { points } => Some((
+                    item.datum.as_ref()?["team"].as_str()?.to_string(),
+                    points.iter().map(|p| p.y).collect(),
+                    item.stroke.as_ref()?.width,
+                )),
+                _ => None,
+            })
+            .collect();
+        lines.sort_by(|a, b| a.0.cmp(&b.0));
+        assert_eq!(
+            lines,
+            [
+                ("A".to_string(), vec![rank_y(2), rank_y(2)], 4.0),
+                ("B".to_string(), vec![rank_y(1), rank_y(3)], 4.0),
+                ("C".to_string(), vec![rank_y(3), rank_y(1)], 4.0),
+            ]
+        );
+
+        let mut labels: Vec<(&str, f64)> = scene
+            .mark_items()
+            .filter_map(|item| match &item.geometry {
+                Geometry::Text { text, y, .. } if ["A", "B", "C"].contains(&text.as_str()) => Some((text.as_str(), *y)),
+                _ => None,
+            })
+            .collect();
+        labels.sort_by(|a, b| a.0.cmp(b.0));
+        assert_eq!(labels, [("A", rank_y(2)), ("B", rank_y(3)), ("C", rank_y(1))]);
+        // The labels stand in for a legend
+        assert!(!scene
+            .mark_items()
+            .any(|item| matches!(&item.geometry, Geometry::Text { text, .. } if text == "team")));
+    }
 }
@@ -0,0 +1,157 @@
+use serde_json::Value;
+
+use super::{CompileContext, CompileError};
+use crate::ir::{Font, FontWeight, Geometry, Group, Mark, MarkItem, MarkType, TextAnchor, TextBaseline, Transform};
+use crate::scale::{format_number_with, get_path, value_to_f64};
+use crate::spec::Encoding;
+use crate::stats;
+use crate::template::format_template;
+
+/// Share of the plot height the number's font takes, before shrinking to fit the width
+const NUMBER_HEIGHT: f64 = 0.4;
+
+/// Widest the number may be, as a share of the plot width
+const NUMBER_WIDTH: f64 = 0.9;
+
+/// Gap between the number and the caption above or the comparison below it
+const GAP: f64 = 8.0;
+
+/// Compile a metric ("big number") mark: the `text` field reduced to one value (by its
+/// `aggregate`, summed otherwise) drawn large in the middle of the plot area, with
+/// `mark.label` as a caption above it and the change from `mark.comparison` below it
+pub fn compile_metric(
+    encoding: &Encoding,
+    data: &[Value],
+    ctx: &CompileContext,
+) -> Result<Group, CompileError> {
+    let plot_area = &ctx.plot_area;
+    let theme = ctx.theme;
+
+    let text_channel = encoding
+        .text
+        .as_ref()
+        .ok_or_else(|| CompileError::MissingField("encoding.text".to_string()))?;
+    let field = text_channel
+        .field()
+        .ok_or_else(|| CompileError::InvalidEncoding("text must have a field".to_string()))?;
+
+    // An aggregated channel has already reduced the data to one row; otherwise sum the rows
+    let values: Vec<f64> = data
+        .iter()
+        .filter_map(|row| get_path(row, field).and_then(value_to_f64))
+        .collect();
+    let value = stats::sum(&values);
+    let datum = data.first().cloned().unwrap_or(Value::Null);
+
+    let text = format_number_with(value, &ctx.locale);
+    let mut font = Font {
+        size: plot_area.height * NUMBER_HEIGHT,
+        weight: FontWeight::Bold,
+        ..theme.title_font()
+    };
+    let width = font.text_width(&text);
+    if width > plot_area.width * NUMBER_WIDTH {
+        font.size *= plot_area.width * NUMBER_WIDTH / width;
+    }
+
+    let (cx, cy) = (plot_area.width / 2.0, plot_area.height / 2.0);
+    let half_height = font.size / 2.0;
+    let mut texts = vec![MarkItem::new(Geometry::Text {
+        x: cx,
+        y: cy,
+        text,
+        font,
+        anchor: TextAnchor::Middle,
+        baseline: TextBaseline::Middle,
+        angle: 0.0,
+    })
+    .with_fill(theme.title_color)
+    .with_datum(datum.clone())];
+
+    if let Some(template) = &ctx.config.label {
+        texts.push(
+            MarkItem::new(Geometry::Text {
+                x: cx,
+                y: cy - half_height - GAP,
+                text: format_template(template, &datum),
+                font: theme.title_font(),
+                anchor: TextAnchor::Middle,
+                baseline: TextBaseline::Bottom,
+                angle: 0.0,
+            })
+            .with_fill(theme.axis_color),
+        );
+    }
+
+    if let Some(comparison) = ctx.config.comparison {
+        texts.push(
+            MarkItem::new(Geometry::Text {
+                x: cx,
+                y: cy + half_height + GAP,
+                text: change_text(value, comparison, ctx),
+                font: theme.title_font(),
+                anchor: TextAnchor::Middle,
+                baseline: TextBaseline::Top,
+                angle: 0.0,
+            })
+            .with_fill(theme.axis_color),
+        );
+    }
+
+    let mut root = Group::new().with_transform(Transform::translate(plot_area.x, plot_area.y));
+    let mut mark = Mark::new(MarkType::Text, texts).as_data();
+    if let Some(template) = &ctx.config.tooltip {
+        mark = mark.with_tooltip(template.clone());
+    }
+    if ctx.config.shadow == Some(true) {
+        mark = mark.with_shadow();
+    }
+    root.add_mark(mark);
+    Ok(root)
+}
+
+/// Signed change from `comparison`, with the relative change when `comparison` isn't zero,
+/// e.g. `"▲ 20 (+25.0%)"`
+fn change_text(value: f64, comparison: f64, ctx: &CompileContext) -> String {
+    let delta = value - comparison;
+    let arrow = match delta.partial_cmp(&0.0) {
+        Some(std::cmp::Ordering::Greater) => "▲ ",
+        Some(std::cmp::Ordering::Less) => "▼ ",
+        _ => "",
+    };
+    let mut text = format!("{arrow}{}", format_number_with(delta.abs(), &ctx.locale));
+    if comparison != 0.0 {
+        let percent = delta / comparison.abs() * 100.0;
+        let sign = if percent < 0.0 { '-' } else { '+' };
+        let percent = ctx.locale.apply(&format!("{:.1}", percent.abs()));
+        text.push_str(&format!(" ({sign}{percent}%)"));
+    }
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ir::Geometry;
+
+    #[test]
+    fn test_metric_shows_aggregate_with_caption_and_change() {
+        let scene = crate::chart(
+            r#"{
+                "data": { "values": [{ "region": "N", "sales": 60 }, { "region": "S", "sales": 40 }] },
+                "mark": { "type": "metric", "label": "Total sales", "comparison": 80 },
+                "encoding": { "text": { "field": "sales", "aggregate": "sum" } }
+            }"#,
+        )
+        .unwrap();
+
+        let texts: Vec<(&str, f64)> = scene
+            .mark_items()
+            .filter_map(|item| match &item.geometry {
+                Geometry::Text { text, font, .. } => Some((text.as_str(), font.size)),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(texts.iter().map(|(text, _)| *text).collect::<Vec<_>>(), ["100", "Total sales", "▲ 20 (+25.0%)"]);
+        assert!(texts[0].1 > 3.0 * texts[1].1);
+    }
+}
@@ -1,15 +1,44 @@
+mod aggregate;
+mod arc;
 mod bar;
+mod layer;
+mod legend;
 mod line;
+mod metric;
+mod point;
+mod position;
 mod stack;
+mod update;
+
+use std::borrow::Cow;
+use std::cell::RefCell;
+use std::collections::BTreeMap;
 
 use serde_json::Value;
 use thiserror::Error;
 
-use crate::ir::{Color, Group, Mark, Scene};
-use crate::spec::{AxisOrient, ChartSpec, DataType, Encoding, MarkType, StackConfig};
+use crate::ir::{
+    Color, Fill, Font, Geometry, Group, Mark, MarkItem, Scene, TextAnchor, TextBaseline, PATTERNS,
+};
+use crate::scale::{get_path, value_to_f64, value_to_string, BandScale, LinearScale, NumberLocale};
+use crate::spec::{
+    AxisConfig, AxisOrient, ChannelDef, ChartSpec, DataType, Encoding, InvalidMode, MarkConfig,
+    MarkType, ResolveMode, StackConfig, TransformSpec, ViewSpec, WatermarkPosition, WatermarkSpec,
+};
+use crate::theme::Theme;
+use crate::transform::apply_transforms;
 
+pub use aggregate::aggregate_rows;
+pub use arc::compile_arc;
 pub use bar::{compile_bar, COLORS};
-pub use line::compile_line;
+pub use layer::{compile_layers, sort_layers};
+pub use legend::{generate_legend, legend_width, ColorScale, LegendLayout};
+pub use line::{compile_area, compile_bump, compile_line};
+pub use metric::compile_metric;
+pub use point::compile_point;
+pub use position::PositionScale;
+pub use crate::spec::{channel_type, infer_data_type};
+pub use update::{CompiledChart, SceneUpdate};
 
 #[derive(Debug, Error)]
 pub enum CompileError {
@@ -25,48 +54,206 @@ pub enum CompileError {
 
 /// Compile a chart specification to a scene graph
 pub fn compile(spec: &ChartSpec) -> Result<Scene, CompileError> {
+    compile_with_theme(spec, &Theme::default())
+}
+
+/// Compile a chart specification to a scene graph using the given theme
+pub fn compile_with_theme(spec: &ChartSpec, theme: &Theme) -> Result<Scene, CompileError> {
+    compile_scene(spec, theme, &RefCell::default(), false)
+}
+
+/// Compile a chart specification, also returning the position scales used so screen
+/// coordinates can be inverted back to data (e.g. on pointer events)
+pub fn compile_with_scales(spec: &ChartSpec) -> Result<(Scene, ScaleSet), CompileError> {
+    let scales = RefCell::default();
+    let scene = compile_scene(spec, &Theme::default(), &scales, false)?;
+    Ok((scene, scales.into_inner()))
+}
+
+/// Compile `spec`, recording its scales into `scales`. With `data_only` the compilers skip
+/// gridlines, axes and legends, leaving the data marks over the same plot area.
+fn compile_scene(
+    spec: &ChartSpec,
+    theme: &Theme,
+    scales: &RefCell<ScaleSet>,
+    data_only: bool,
+) -> Result<Scene, CompileError> {
     let mut scene = Scene::new(spec.width, spec.height);
+    scene.rtl = spec.rtl == Some(true);
 
     // Set background
+    scene.background = theme.background;
     if let Some(bg) = &spec.background {
         if let Some(color) = Color::from_hex(bg) {
             scene.background = Some(color);
         }
     }
 
+    // Sparklines and full-bleed views ignore padding and fill the canvas
+    let fullbleed = spec.view.as_ref().is_some_and(ViewSpec::is_fullbleed);
+    let sparkline = spec.mark.as_ref().is_some_and(|mark| {
+        mark.mark_type() == MarkType::Line
+            && mark.config().is_some_and(|config| config.sparkline == Some(true))
+    });
+
     // Calculate plot area
-    let plot_area = PlotArea {
-        x: spec.padding.left,
-        y: spec.padding.top,
-        width: spec.width - spec.padding.left - spec.padding.right,
-        height: spec.height - spec.padding.top - spec.padding.bottom,
+    let mut plot_area = if sparkline || fullbleed {
+        PlotArea {
+            x: 0.0,
+            y: 0.0,
+            width: spec.width,
+            height: spec.height,
+        }
+    } else {
+        PlotArea {
+            x: spec.padding.left,
+            y: spec.padding.top,
+            width: spec.width - spec.padding.left - spec.padding.right,
+            height: spec.height - spec.padding.top - spec.padding.bottom,
+        }
+    };
+
+    let transforms = spec.transform.as_deref().unwrap_or_default();
+
+    // Reserve a margin on the legend's side, sized for the first mark that draws one
+    let legend_config = spec.legend.unwrap_or_default();
+    let legend_source = legend_source(spec, transforms).filter(|_| !sparkline && !fullbleed);
+    let legend_extent = match &legend_source {
+        Some((encoding, data)) => {
+            legend::legend_extent(encoding, data, &legend_config, theme, &spec.locale.unwrap_or_default())
+        }
+        None => 0.0,
+    };
+    let legend = legend::reserve_legend(&mut plot_area, &spec.padding, &legend_config, legend_extent);
+
+    let default_config = MarkConfig::default();
+    let ctx = CompileContext {
+        plot_area,
+        config: &default_config,
+        stack: spec.stack.as_ref(),
+        theme,
+        scales,
+        y_domain: None,
+        color_map: None,
+        legend,
+        locale: spec.locale.unwrap_or_default(),
+        data_only: data_only || fullbleed,
     };
 
     // Handle single mark vs layers
-    if let Some(mark_spec) = &spec.mark {
+    let root = if let Some(mark_spec) = &spec.mark {
         let encoding = spec.encoding.as_ref().ok_or_else(|| {
             CompileError::MissingField("encoding".to_string())
         })?;
 
-        let data = spec
+        let values = spec
             .data
             .values()
             .ok_or_else(|| CompileError::InvalidData("inline data required".to_string()))?;
+        let data = &apply_transforms(values, transforms);
 
-        let compiled = compile_mark(mark_spec.mark_type(), encoding, data, &plot_area, spec.stack.as_ref())?;
-        scene.root = compiled;
-    } else if let Some(_layers) = &spec.layer {
-        // TODO: Layer support in Phase 3
-        return Err(CompileError::UnsupportedMark(MarkType::Line));
+        let ctx = CompileContext {
+            config: mark_spec.config().unwrap_or(&default_config),
+            ..ctx
+        };
+
+        compile_mark(mark_spec.mark_type(), encoding, data, &ctx)?
+    } else if let Some(layers) = &spec.layer {
+        let shared_y = spec
+            .resolve
+            .as_ref()
+            .is_some_and(|resolve| resolve.scale.y == Some(ResolveMode::Shared));
+        compile_layers(layers, &spec.data, transforms, shared_y, &ctx)?
     } else {
         return Err(CompileError::MissingField("mark or layer".to_string()));
+    };
+
+    // The legend's colors, so a data update can tell when its entries change
+    scales.borrow_mut().color = legend_source.and_then(|(encoding, data)| {
+        let channel = encoding.color.as_ref()?;
+        Some(ColorScale::from_channel(channel, &data, channel.field()?, theme))
+    });
+
+    // The plot background goes under everything the marks drew, gridlines included
+    let view_fill = spec.view.as_ref().and_then(ViewSpec::fill).and_then(Color::from_hex);
+    let view_pattern = spec.view.as_ref().and_then(ViewSpec::pattern);
+    let background_fill = match (view_pattern, view_fill) {
+        (Some(pattern), background) => Some(Fill::Pattern {
+            pattern,
+            color: theme.grid_color,
+            background,
+        }),
+        (None, fill) => fill.map(Fill::Solid),
+    };
+    scene.root = match background_fill {
+        Some(fill) => {
+            let mut background = Group::new();
+            background.add_mark(Mark::new(
+                crate::ir::MarkType::Rect,
+                vec![MarkItem::new(Geometry::Rect {
+                    x: plot_area.x,
+                    y: plot_area.y,
+                    width: plot_area.width,
+                    height: plot_area.height,
+                    corner_radius: 0.0,
+                })
+                .with_fill(fill)],
+            ));
+            background.add_group(root);
+            background
+        }
+        None => root,
+    };
+
+    // The watermark sits in canvas coordinates, above everything else
+    if let Some(watermark) = &spec.watermark {
+        let mut root = Group::new();
+        root.add_group(scene.root);
+        root.add_mark(watermark_mark(watermark, spec.width, spec.height, theme));
+        scene.root = root;
     }
 
     Ok(scene)
 }
 
+/// Gap between a watermark and the edges of the canvas
+const WATERMARK_MARGIN: f64 = 4.0;
+
+/// Small text in a corner of the canvas
+fn watermark_mark(watermark: &WatermarkSpec, width: f64, height: f64, theme: &Theme) -> Mark {
+    let position = watermark.position();
+    let (x, anchor) = match position {
+        WatermarkPosition::TopLeft | WatermarkPosition::BottomLeft => (WATERMARK_MARGIN, TextAnchor::Start),
+        WatermarkPosition::TopRight | WatermarkPosition::BottomRight => {
+            (width - WATERMARK_MARGIN, TextAnchor::End)
+        }
+    };
+    let (y, baseline) = match position {
+        WatermarkPosition::TopLeft | WatermarkPosition::TopRight => (WATERMARK_MARGIN, TextBaseline::Top),
+        WatermarkPosition::BottomLeft | WatermarkPosition::BottomRight => {
+            (height - WATERMARK_MARGIN, TextBaseline::Bottom)
+        }
+    };
+    let font = Font {
+        size: theme.font_size * 0.9,
+        ..theme.label_font()
+    };
+    let item = MarkItem::new(Geometry::Text {
+        x,
+        y,
+        text: watermark.text().to_string(),
+        font,
+        anchor,
+        baseline,
+        angle: 0.0,
+    })
+    .with_fill(theme.axis_color)
+    .with_opacity(watermark.opacity());
+    Mark::new(crate::ir::MarkType::Text, vec![item])
+}
+
 /// Plot area dimensions
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
 pub struct PlotArea {
     pub x: f64,
     pub y: f64,
@@ -74,18 +261,143 @@ pub struct PlotArea {
     pub height: f64,
 }
 
+/// Inputs shared by the mark compilers
+#[derive(Debug, Clone, Copy)]
+pub struct CompileContext<'a> {
+    pub plot_area: PlotArea,
+    /// Configuration of the mark being compiled
+    pub config: &'a MarkConfig,
+    pub stack: Option<&'a StackConfig>,
+    pub theme: &'a Theme,
+    /// Position scales recorded by the mark compilers for [`compile_with_scales`]
+    pub scales: &'a RefCell<ScaleSet>,
+    /// y domain shared by all layers, replacing each mark's own (`resolve.scale.y: "shared"`)
+    pub y_domain: Option<(f64, f64)>,
+    /// Fixed colors for categories of the mark's color field
+    pub color_map: Option<&'a BTreeMap<String, String>>,
+    /// Placement of the color legend
+    pub legend: LegendLayout,
+    /// Separators for numbers in axis and value labels
+    pub locale: NumberLocale,
+    /// Draw the data marks alone, without gridlines, axes or legends: set for full-bleed
+    /// views and by [`CompiledChart::update_data`]
+    pub data_only: bool,
+}
+
+/// Position scales of a compiled chart, keyed by channel. Ranges are in pixels relative to
+/// `plot_area`'s origin; for layered charts each channel holds the last layer's scale.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ScaleSet {
+    pub plot_area: PlotArea,
+    pub x: Option<PositionScale>,
+    pub y: Option<PositionScale>,
+    /// Placement of grouped bars' series within a category band, starting at the band's
+    /// start
+    pub offset: Option<BandScale>,
+    /// Colors of the mark drawing the legend, if the chart has one
+    pub color: Option<ColorScale>,
+}
+
+impl ScaleSet {
+    /// Canvas x coordinate of a data value, plot area offset included, so overlays drawn
+    /// there line up with the chart; categories map to their band's center. `None` without
+    /// an x scale or for values the scale can't place.
+    pub fn scale_x(&self, value: &Value) -> Option<f64> {
+        Some(self.plot_area.x + self.x.as_ref()?.position(value)?)
+    }
+
+    /// Canvas y coordinate of a data value, as [`ScaleSet::scale_x`]
+    pub fn scale_y(&self, value: &Value) -> Option<f64> {
+        Some(self.plot_area.y + self.y.as_ref()?.position(value)?)
+    }
+}
+
+impl CompileContext<'_> {
+    /// `scale` for a quantitative y, or the same range over the shared y domain if one is set
+    pub fn resolve_y(&self, scale: LinearScale) -> LinearScale {
+        match self.y_domain {
+            Some(domain) => LinearScale::new(domain, scale.range()),
+            None => scale,
+        }
+    }
+
+    /// Record the x and y scales the current mark was drawn with
+    pub fn record_scales(&self, x: PositionScale, y: PositionScale) {
+        *self.scales.borrow_mut() = ScaleSet {
+            plot_area: self.plot_area,
+            x: Some(x),
+            y: Some(y),
+            ..ScaleSet::default()
+        };
+    }
+
+    /// Palette color for the series at `index`
+    pub fn series_color(&self, index: usize) -> Color {
+        self.theme.palette_color(index)
+    }
+
+    /// Color for a color-field category: its `colorMap` entry if it has one, otherwise the
+    /// palette color for its series `index`
+    pub fn category_color(&self, category: &str, index: usize) -> Color {
+        self.color_map
+            .and_then(|map| map.get(category))
+            .and_then(|hex| Color::from_hex(hex))
+            .unwrap_or_else(|| self.series_color(index))
+    }
+
+    /// Fill for a color-field category, as [`CompileContext::category_color`] but patterned
+    /// like [`CompileContext::series_fill`]
+    pub fn category_fill(&self, category: &str, index: usize) -> Fill {
+        self.color_fill(index, self.category_color(category, index))
+    }
+
+    /// Fill for the series at `index`: the palette color, or a pattern drawn in that
+    /// color when `mark.patterns` is set
+    pub fn series_fill(&self, index: usize) -> Fill {
+        self.color_fill(index, self.series_color(index))
+    }
+
+    fn color_fill(&self, index: usize, color: Color) -> Fill {
+        if self.config.patterns == Some(true) {
+            Fill::pattern(PATTERNS[index % PATTERNS.len()], color)
+        } else {
+            Fill::Solid(color)
+        }
+    }
+}
+
 /// Compile a single mark type
 fn compile_mark(
     mark_type: MarkType,
     encoding: &Encoding,
     data: &[Value],
-    plot_area: &PlotArea,
-    stack_config: Option<&StackConfig>,
+    ctx: &CompileContext,
 ) -> Result<Group, CompileError> {
+    if let Some(missing) = encoding.missing_field(data) {
+        return Err(CompileError::InvalidEncoding(format!("encoding.{} {missing}", missing.channel)));
+    }
+    let data = &filter_to_scale_domains(encoding, data);
+    let data = &match ctx.config.invalid {
+        Some(InvalidMode::Zero) => zero_invalid_values(encoding, data),
+        Some(InvalidMode::Filter) | None => Cow::Borrowed(&data[..]),
+    };
+    let data = &match aggregate_rows(encoding, data) {
+        Some(rows) => Cow::Owned(rows),
+        None => Cow::Borrowed(&data[..]),
+    };
+    let ctx = &CompileContext {
+        color_map: encoding.color.as_ref().and_then(|c| c.color_map()),
+        ..*ctx
+    };
     match mark_type {
-        MarkType::Bar => compile_bar(encoding, data, plot_area, stack_config),
-        MarkType::Line => compile_line(encoding, data, plot_area, stack_config),
-        MarkType::Point | MarkType::Area | MarkType::Rule | MarkType::Text | MarkType::Rect => {
+        MarkType::Bar => compile_bar(encoding, data, ctx),
+        MarkType::Line => compile_line(encoding, data, ctx),
+        MarkType::Point => compile_point(encoding, data, ctx),
+        MarkType::Arc => compile_arc(encoding, data, ctx),
+        MarkType::Area => compile_area(encoding, data, ctx),
+        MarkType::Metric => compile_metric(encoding, data, ctx),
+        MarkType::Bump => compile_bump(encoding, data, ctx),
+        MarkType::Rule | MarkType::Text | MarkType::Rect => {
             Err(CompileError::UnsupportedMark(mark_type))
         }
         MarkType::Boxplot | MarkType::Bullet | MarkType::Funnel => {
@@ -94,61 +406,265 @@ fn compile_mark(
     }
 }
 
-/// Infer data type from values
-pub fn infer_data_type(values: &[Value], field: &str) -> DataType {
-    for value in values {
-        if let Some(v) = value.get(field) {
-            match v {
-                Value::Number(_) => return DataType::Quantitative,
-                Value::String(s) => {
-                    // Check if it's a date-like string
-                    if s.contains('-') && s.len() >= 8 {
-                        return DataType::Temporal;
-                    }
-                    return DataType::Nominal;
-                }
-                _ => continue,
+/// Cut a text item to `limit` pixels with an ellipsis, keeping the full text as its title
+pub(crate) fn limit_label(mut item: MarkItem, limit: Option<f64>) -> MarkItem {
+    let Some(limit) = limit else {
+        return item;
+    };
+    if let Geometry::Text { text, font, .. } = &mut item.geometry {
+        let truncated = font.truncate(text, limit);
+        if truncated != *text {
+            item.title = Some(std::mem::replace(text, truncated));
+        }
+    }
+    item
+}
+
+/// Encoding and data of the first mark that draws a color legend
+fn legend_source<'a>(
+    spec: &'a ChartSpec,
+    transforms: &[TransformSpec],
+) -> Option<(&'a Encoding, Cow<'a, [Value]>)> {
+    let (encoding, data) = match (&spec.mark, &spec.encoding, &spec.layer) {
+        (Some(mark), Some(encoding), _) => {
+            legend::has_legend(mark.mark_type(), encoding).then_some((encoding, &spec.data))?
+        }
+        (None, _, Some(layers)) => layers
+            .iter()
+            .find(|layer| legend::has_legend(layer.mark.mark_type(), &layer.encoding))
+            .map(|layer| (&layer.encoding, layer.data.as_ref().unwrap_or(&spec.data)))?,
+        _ => return None,
+    };
+    Some((encoding, apply_transforms(data.values()?, transforms)))
+}
+
+/// Synthetic datum field holding a segment's share of its total, for tooltip and label templates
+pub const PERCENT_FIELD: &str = "__percent";
+
+/// Copy of `row` with its `share` (0..1) added as a `__percent` string such as `"33.3%"`
+fn with_percent(row: &Value, share: f64) -> Value {
+    let mut row = row.clone();
+    if let Value::Object(fields) = &mut row {
+        let percent = format!("{:.1}", share * 100.0);
+        let percent = percent.strip_suffix(".0").unwrap_or(&percent);
+        fields.insert(PERCENT_FIELD.to_string(), Value::String(format!("{percent}%")));
+    }
+    row
+}
+
+/// Drop rows whose x, y or color value falls outside that channel's explicit `scaleDomain`
+fn filter_to_scale_domains<'a>(encoding: &Encoding, data: &'a [Value]) -> Cow<'a, [Value]> {
+    let domains: Vec<(&str, &[String])> = [&encoding.x, &encoding.y, &encoding.color]
+        .into_iter()
+        .flatten()
+        .filter_map(|channel| Some((channel.field()?, channel.scale_domain()?)))
+        .collect();
+    if domains.is_empty() {
+        return Cow::Borrowed(data);
+    }
+
+    let in_domain = |row: &Value| {
+        domains.iter().all(|(field, domain)| {
+            get_path(row, field)
+                .is_some_and(|v| domain.contains(&crate::scale::value_to_string(v)))
+        })
+    };
+    Cow::Owned(data.iter().filter(|row| in_domain(row)).cloned().collect())
+}
+
+/// Copy of `data` with null, missing and non-numeric values of quantitative position, theta
+/// and size fields set to 0 (`mark.invalid: "zero"`)
+fn zero_invalid_values<'a>(encoding: &Encoding, data: &'a [Value]) -> Cow<'a, [Value]> {
+    let fields: Vec<&str> = [&encoding.x, &encoding.y, &encoding.theta, &encoding.size]
+        .into_iter()
+        .flatten()
+        .filter_map(|channel| {
+            let field = channel.field()?;
+            (channel_type(channel, data, field) == DataType::Quantitative).then_some(field)
+        })
+        .collect();
+    let is_invalid = |row: &Value, field: &str| get_path(row, field).and_then(value_to_f64).is_none();
+    if !data.iter().any(|row| fields.iter().any(|field| is_invalid(row, field))) {
+        return Cow::Borrowed(data);
+    }
+
+    let mut rows = data.to_vec();
+    for row in &mut rows {
+        for field in &fields {
+            if is_invalid(row, field) {
+                set_zero(row, field);
+            }
+        }
+    }
+    Cow::Owned(rows)
+}
+
+/// Set `field` (possibly a dotted path) to 0, adding it as a top-level key if absent
+fn set_zero(row: &mut Value, field: &str) {
+    let pointer = format!("/{}", field.replace('.', "/"));
+    let target = match row.get(field) {
+        Some(_) => row.get_mut(field),
+        None => row.pointer_mut(&pointer),
+    };
+    match target {
+        Some(value) => *value = Value::from(0),
+        None => {
+            if let Value::Object(fields) = row {
+                fields.insert(field.to_string(), Value::from(0));
             }
         }
     }
-    DataType::Nominal
 }
 
-/// Extract field values as strings (for categorical)
+/// Categories for a band scale: the channel's explicit `scaleDomain` in its given order,
+/// otherwise the distinct values of `field` in first-seen order
+pub fn channel_categories(channel: &ChannelDef, data: &[Value], field: &str) -> Vec<String> {
+    if let Some(domain) = channel.scale_domain() {
+        return domain.to_vec();
+    }
+    distinct_categories(data, field)
+}
+
+/// Series of the color channel in legend and palette order (see [`channel_categories`]);
+/// empty without a color field
+pub fn color_categories(encoding: &Encoding, data: &[Value]) -> Vec<String> {
+    encoding
+        .color
+        .as_ref()
+        .and_then(|channel| Some(channel_categories(channel, data, channel.field()?)))
+        .unwrap_or_default()
+}
+
+/// Extract field values as strings (for categorical), via [`value_to_string`]
+///
+/// A category is its string form, so values that print the same are one category: the
+/// number `1` and the string `"1"` share a band, as do `2020` and `2020.0`. Every compiler
+/// looks rows up by the same string, so all such rows land in that band.
 pub fn extract_categories(data: &[Value], field: &str) -> Vec<String> {
     data.iter()
-        .filter_map(|row| {
-            row.get(field).map(|v| match v {
-                Value::String(s) => s.clone(),
-                Value::Number(n) => n.to_string(),
-                _ => v.to_string(),
-            })
-        })
+        .filter_map(|row| get_path(row, field).map(value_to_string))
         .collect()
 }
 
-/// Extract field values as numbers
+/// Distinct values of `field` as strings, in first-seen order
+pub fn distinct_categories(data: &[Value], field: &str) -> Vec<String> {
+    let mut seen = std::collections::HashSet::new();
+    extract_categories(data, field)
+        .into_iter()
+        .filter(|c| seen.insert(c.clone()))
+        .collect()
+}
+
+/// Extract field values as numbers, numeric strings included
 pub fn extract_numbers(data: &[Value], field: &str) -> Vec<f64> {
     data.iter()
-        .filter_map(|row| row.get(field).and_then(|v| v.as_f64()))
+        .filter_map(|row| get_path(row, field).and_then(value_to_f64))
         .collect()
 }
 
-/// Generate axis marks
+/// Smallest and largest numeric value of `field`, or `None` if no row has one
+pub fn data_extent(data: &[Value], field: &str) -> Option<(f64, f64)> {
+    let values = extract_numbers(data, field);
+    let min = values.iter().cloned().fold(f64::INFINITY, f64::min);
+    let max = values.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
+    min.is_finite().then_some((min, max))
+}
+
+/// Dash pattern for gridlines, keeping them subordinate to the solid axis line
+const DEFAULT_GRID_DASH: [f64; 2] = [2.0, 2.0];
+
+/// Generate gridlines across the plot area at each tick, if the axis enables `grid`
+pub fn generate_grid(
+    orient: AxisOrient,
+    ticks: &[crate::scale::Tick],
+    plot_area: &PlotArea,
+    axis: Option<&AxisConfig>,
+    theme: &Theme,
+) -> Option<Mark> {
+    use crate::ir::{Geometry, MarkItem, MarkType as IrMarkType, Stroke};
+
+    let axis = axis.filter(|a| a.grid == Some(true))?;
+    let stroke = match axis.grid_dash.as_deref() {
+        Some([]) => Stroke::solid(theme.grid_color, 1.0),
+        Some(dash) => Stroke::dashed(theme.grid_color, 1.0, dash.to_vec()),
+        None => Stroke::dashed(theme.grid_color, 1.0, DEFAULT_GRID_DASH.to_vec()),
+    };
+
+    let items = ticks
+        .iter()
+        .map(|tick| {
+            let geometry = match orient {
+                AxisOrient::Bottom | AxisOrient::Top => Geometry::Rule {
+                    x1: tick.value,
+                    y1: 0.0,
+                    x2: tick.value,
+                    y2: plot_area.height,
+                },
+                AxisOrient::Left | AxisOrient::Right => Geometry::Rule {
+                    x1: 0.0,
+                    y1: tick.value,
+                    x2: plot_area.width,
+                    y2: tick.value,
+                },
+            };
+            MarkItem::new(geometry).with_stroke(stroke.clone())
+        })
+        .collect();
+
+    Some(Mark::new(IrMarkType::Rule, items))
+}
+
+/// Solid rule across the plot area at zero on a value axis whose domain runs from negative
+/// to positive, marking the divide between them; `None` when zero isn't inside the domain
+pub fn generate_zero_line(
+    orient: AxisOrient,
+    scale: &LinearScale,
+    plot_area: &PlotArea,
+    theme: &Theme,
+) -> Option<Mark> {
+    use crate::ir::{Geometry, MarkItem, MarkType as IrMarkType, Stroke};
+
+    let (min, max) = scale.domain();
+    if min.min(max) >= 0.0 || min.max(max) <= 0.0 {
+        return None;
+    }
+    let zero = scale.scale(0.0);
+    let geometry = match orient {
+        AxisOrient::Bottom | AxisOrient::Top => Geometry::Rule {
+            x1: zero,
+            y1: 0.0,
+            x2: zero,
+            y2: plot_area.height,
+        },
+        AxisOrient::Left | AxisOrient::Right => Geometry::Rule {
+            x1: 0.0,
+            y1: zero,
+            x2: plot_area.width,
+            y2: zero,
+        },
+    };
+    let item = MarkItem::new(geometry).with_stroke(Stroke::solid(theme.axis_color, 1.0));
+    Some(Mark::new(IrMarkType::Rule, vec![item]))
+}
+
+/// Default gap between the end of a tick and its label
+const DEFAULT_LABEL_PADDING: f64 = 10.0;
+
+/// Generate axis marks. Tick length defaults to the theme's, label padding to 10px.
 pub fn generate_axis(
     orient: AxisOrient,
     ticks: &[crate::scale::Tick],
     plot_area: &PlotArea,
-    title: Option<&str>,
+    axis: Option<&AxisConfig>,
+    theme: &Theme,
 ) -> Vec<Mark> {
-    use crate::ir::{
-        Font, Geometry, MarkItem, MarkType as IrMarkType, Stroke, TextAnchor, TextBaseline,
-    };
+    use crate::ir::{Geometry, MarkItem, MarkType as IrMarkType, Stroke, TextAnchor, TextBaseline};
 
     let mut marks = Vec::new();
-    let axis_color = Color::rgb(100, 100, 100);
-    let tick_length = 6.0;
-    let label_offset = 10.0;
+    let axis_color = theme.axis_color;
+    let title = axis.and_then(|a| a.title.as_deref());
+    let tick_length = axis.and_then(|a| a.tick_size).unwrap_or(theme.tick_length);
+    let label_offset = axis.and_then(|a| a.label_padding).unwrap_or(DEFAULT_LABEL_PADDING);
 
     // Axis line
     let line_item = match orient {
@@ -179,10 +695,7 @@ pub fn generate_axis(
     }
     .with_stroke(Stroke::solid(axis_color, 1.0));
 
-    marks.push(Mark {
-        mark_type: IrMarkType::Rule,
-        items: vec![line_item],
-    });
+    marks.push(Mark::new(IrMarkType::Rule, vec![line_item]));
 
     // Tick marks and labels
     let mut tick_items = Vec::new();
@@ -242,39 +755,36 @@ pub fn generate_axis(
             .with_stroke(Stroke::solid(axis_color, 1.0)),
         );
 
-        label_items.push(MarkItem::new(Geometry::Text {
+        let label = MarkItem::new(Geometry::Text {
             x: lx,
             y: ly,
             text: tick.label.clone(),
-            font: Font::default(),
+            font: theme.label_font(),
             anchor,
             baseline,
             angle: 0.0,
-        }).with_fill(axis_color));
+        }).with_fill(axis_color);
+        label_items.push(limit_label(label, axis.and_then(|a| a.label_limit)));
     }
 
-    marks.push(Mark {
-        mark_type: IrMarkType::Rule,
-        items: tick_items,
-    });
+    marks.push(Mark::new(IrMarkType::Rule, tick_items));
 
-    marks.push(Mark {
-        mark_type: IrMarkType::Text,
-        items: label_items,
-    });
+    marks.push(Mark::new(IrMarkType::Text, label_items));
 
     // Title
     if let Some(title_text) = title {
+        // Titles sit beyond the labels, so they move out with longer ticks or more padding
+        let label_extent = tick_length + label_offset;
         let (tx, ty, anchor, baseline, angle) = match orient {
             AxisOrient::Bottom => (
                 plot_area.width / 2.0,
-                plot_area.height + 35.0,
+                plot_area.height + label_extent + 19.0,
                 TextAnchor::Middle,
                 TextBaseline::Top,
                 0.0,
             ),
             AxisOrient::Left => (
-                -40.0,
+                -label_extent - 24.0,
                 plot_area.height / 2.0,
                 TextAnchor::Middle,
                 TextBaseline::Bottom,
@@ -282,13 +792,13 @@ pub fn generate_axis(
             ),
             AxisOrient::Top => (
                 plot_area.width / 2.0,
-                -35.0,
+                -label_extent - 19.0,
                 TextAnchor::Middle,
                 TextBaseline::Bottom,
                 0.0,
             ),
             AxisOrient::Right => (
-                plot_area.width + 40.0,
+                plot_area.width + label_extent + 24.0,
                 plot_area.height / 2.0,
                 TextAnchor::Middle,
                 TextBaseline::Top,
@@ -296,22 +806,466 @@ pub fn generate_axis(
             ),
         };
 
-        let mut font = Font::default();
-        font.size = 14.0;
+        // Long titles wrap to the length of the axis
+        let font = theme.title_font();
+        let axis_length = match orient {
+            AxisOrient::Bottom | AxisOrient::Top => plot_area.width,
+            AxisOrient::Left | AxisOrient::Right => plot_area.height,
+        };
+        let text = font.wrap(title_text, axis_length);
 
-        marks.push(Mark {
-            mark_type: IrMarkType::Text,
-            items: vec![MarkItem::new(Geometry::Text {
+        marks.push(Mark::new(
+            IrMarkType::Text,
+            vec![MarkItem::new(Geometry::Text {
                 x: tx,
                 y: ty,
-                text: title_text.to_string(),
+                text,
                 font,
                 anchor,
                 baseline,
                 angle,
-            }).with_fill(Color::rgb(50, 50, 50))],
-        });
+            })
+            .with_fill(theme.title_color)],
+        ));
     }
 
     marks
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ir::{Geometry, PatternKind};
+    use serde_json::json;
+
+    #[test]
+    fn test_repeated_integers_infer_ordinal() {
+        let years = json!([
+            { "year": 2020, "sales": 12 },
+            { "year": 2021, "sales": 15 },
+            { "year": 2020, "sales": 7 },
+            { "year": 2021, "sales": 9 }
+        ]);
+        let rows = years.as_array().unwrap();
+        assert_eq!(infer_data_type(rows, "year"), DataType::Ordinal);
+        assert_eq!(infer_data_type(rows, "sales"), DataType::Quantitative);
+    }
+
+    #[test]
+    fn test_grid_rules_are_dashed_by_default() {
+        let scene = crate::chart(
+            r#"{
+                "data": { "values": [{ "c": "A", "v": 10 }, { "c": "B", "v": 20 }] },
+                "mark": "bar",
+                "encoding": {
+                    "x": { "field": "c", "axis": { "grid": true, "gridDash": [] } },
+                    "y": { "field": "v", "axis": { "grid": true } }
+                }
+            }"#,
+        )
+        .unwrap();
+
+        let grid_dashes: Vec<_> = scene
+            .mark_items()
+            .filter_map(|item| item.stroke.as_ref())
+            .filter(|stroke| stroke.color == Theme::default().grid_color)
+            .map(|stroke| stroke.dash.clone())
+            .collect();
+        // Two solid category gridlines, then dashed value gridlines at 0, 5, ..., 20
+        assert_eq!(grid_dashes.len(), 7);
+        assert!(grid_dashes[..2].iter().all(Option::is_none));
+        assert!(grid_dashes[2..].iter().all(|dash| dash.as_deref() == Some(&[2.0, 2.0][..])));
+    }
+
+    #[test]
+    fn test_only_iso_dates_infer_temporal() {
+        for date in ["2024-01", "2024-01-15", "2024-01-15T08:30:00Z"] {
+            assert!(crate::spec::is_iso_date(date), "{date}");
+        }
+        for text in ["north-america", "product-1234", "2024-13-01", "2024-01-155"] {
+            assert!(!crate::spec::is_iso_date(text), "{text}");
+        }
+    }
+
+    #[test]
+    fn test_scale_domain_filters_and_orders_bands() {
+        let scene = crate::chart(
+            r#"{
+                "data": { "values": [{ "c": "A", "v": 1 }, { "c": "B", "v": 2 }, { "c": "C", "v": 3 }] },
+                "mark": "bar",
+                "encoding": {
+                    "x": { "field": "c", "scaleDomain": ["C", "A"] },
+                    "y": { "field": "v" }
+                }
+            }"#,
+        )
+        .unwrap();
+
+        let mut bars: Vec<(f64, String)> = scene
+            .mark_items()
+            .filter_map(|item| match (&item.geometry, &item.datum) {
+                (Geometry::Rect { x, .. }, Some(datum)) => Some((*x, datum["c"].to_string())),
+                _ => None,
+            })
+            .collect();
+        bars.sort_by(|a, b| a.0.total_cmp(&b.0));
+        let order: Vec<_> = bars.iter().map(|(_, c)| c.as_str()).collect();
+        assert_eq!(order, [r#""C""#, r#""A""#]);
+    }
+
+    #[test]
+    fn test_scale_domain_orders_numeric_and_color_categories() {
+        let data = json!([{ "size": 3 }, { "size": 1 }, { "size": 2 }]);
+        let size: ChannelDef = serde_json::from_value(json!({ "field": "size", "scaleDomain": ["1", "2", "3"] })).unwrap();
+        let rows = data.as_array().unwrap();
+        assert_eq!(channel_type(&size, rows, "size"), DataType::Ordinal);
+
+        let scene = crate::chart(
+            r#"{
+                "data": { "values": [{ "k": "a", "v": 1, "s": "L" }, { "k": "a", "v": 2, "s": "S" }, { "k": "a", "v": 3, "s": "M" }] },
+                "mark": "bar",
+                "encoding": {
+                    "x": { "field": "k" },
+                    "y": { "field": "v" },
+                    "color": { "field": "s", "scaleDomain": ["S", "M", "L"] }
+                }
+            }"#,
+        )
+        .unwrap();
+        let legend: Vec<_> = scene
+            .mark_items()
+            .filter_map(|item| match &item.geometry {
+                Geometry::Text { text, .. } if ["S", "M", "L"].contains(&text.as_str()) => Some(text.as_str()),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(legend, ["S", "M", "L"]);
+    }
+
+    #[test]
+    fn test_axis_tick_size_and_label_padding() {
+        let axis: AxisConfig = serde_json::from_value(json!({ "tickSize": 10, "labelPadding": 4 })).unwrap();
+        let plot_area = PlotArea { x: 0.0, y: 0.0, width: 100.0, height: 50.0 };
+        let ticks = [crate::scale::Tick { value: 20.0, label: "A".to_string() }];
+
+        let marks = generate_axis(AxisOrient::Bottom, &ticks, &plot_area, Some(&axis), &Theme::default());
+        let geometries: Vec<_> = marks.iter().flat_map(|m| &m.items).map(|i| &i.geometry).collect();
+        assert!(geometries.contains(&&Geometry::Rule { x1: 20.0, y1: 50.0, x2: 20.0, y2: 60.0 }));
+        assert!(geometries
+            .iter()
+            .any(|g| matches!(g, Geometry::Text { y, text, .. } if text == "A" && *y == 64.0)));
+    }
+
+    #[test]
+    fn test_compile_with_scales_returns_bar_scales() {
+        let spec = crate::parse_spec(
+            r#"{
+                "data": { "values": [{ "c": "A", "v": 10 }, { "c": "B", "v": 20 }] },
+                "mark": "bar",
+                "encoding": { "x": { "field": "c" }, "y": { "field": "v" } }
+            }"#,
+        )
+        .unwrap();
+
+        let (_, scales) = compile_with_scales(&spec).unwrap();
+        let Some(PositionScale::Band(x)) = &scales.x else {
+            panic!("expected a band x scale");
+        };
+        assert_eq!(x.domain(), ["A", "B"]);
+        let Some(PositionScale::Linear(y)) = &scales.y else {
+            panic!("expected a linear y scale");
+        };
+        assert_eq!(y.invert(scales.plot_area.height), 0.0);
+    }
+
+    #[test]
+    fn test_scale_set_maps_data_to_canvas_pixels() {
+        let spec = crate::parse_spec(
+            r#"{
+                "data": { "values": [{ "c": "A", "v": 10 }, { "c": "B", "v": 20 }] },
+                "mark": "bar",
+                "encoding": { "x": { "field": "c" }, "y": { "field": "v" } }
+            }"#,
+        )
+        .unwrap();
+
+        let (scene, scales) = compile_with_scales(&spec).unwrap();
+        let scene = scene.flatten();
+        let bar_b = scene
+            .mark_items()
+            .find_map(|item| match item.geometry {
+                Geometry::Rect { x, y, width, .. } if item.datum.as_ref().is_some_and(|d| d["c"] == "B") => {
+                    Some((x + width / 2.0, y))
+                }
+                _ => None,
+            })
+            .unwrap();
+        assert_eq!(scales.scale_x(&json!("B")), Some(bar_b.0));
+        assert_eq!(scales.scale_y(&json!(20)), Some(bar_b.1));
+        assert_eq!(scales.scale_x(&json!("Z")), None);
+    }
+
+    #[test]
+    fn test_dotted_fields_read_nested_objects() {
+        let spec = crate::parse_spec(
+            r#"{
+                "data": { "values": [
+                    { "geo": { "country": "US" }, "metric": { "value": 5 } },
+                    { "geo": { "country": "FR" }, "metric": { "value": 10 } }
+                ] },
+                "mark": "bar",
+                "encoding": { "x": { "field": "geo.country" }, "y": { "field": "metric.value" } }
+            }"#,
+        )
+        .unwrap();
+
+        let (scene, scales) = compile_with_scales(&spec).unwrap();
+        let Some(PositionScale::Band(x)) = &scales.x else {
+            panic!("expected a band x scale");
+        };
+        assert_eq!(x.domain(), ["US", "FR"]);
+        let heights: Vec<f64> = scene
+            .mark_items()
+            .filter(|item| item.datum.is_some())
+            .filter_map(|item| match item.geometry {
+                Geometry::Rect { height, .. } => Some(height),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(heights.len(), 2);
+        assert_eq!(heights[0] * 2.0, heights[1]);
+    }
+
+    #[test]
+    fn test_invalid_zero_keeps_rows_with_null_or_missing_values() {
+        let spec = r#"{
+            "data": { "values": [{ "c": "A", "v": 5 }, { "c": "B", "v": null }, { "c": "C" }] },
+            "mark": { "type": "bar" },
+            "encoding": { "x": { "field": "c" }, "y": { "field": "v", "type": "quantitative" } }
+        }"#;
+        let bars = |invalid: &str| -> Vec<(String, f64)> {
+            crate::chart_with(spec, json!({ "mark": { "invalid": invalid } }))
+                .mark_items()
+                .filter_map(|item| match (&item.geometry, &item.datum) {
+                    (Geometry::Rect { height, .. }, Some(datum)) => Some((datum["c"].to_string(), *height)),
+                    _ => None,
+                })
+                .collect()
+        };
+
+        assert_eq!(bars("filter").len(), 1);
+        let zeroed = bars("zero");
+        assert_eq!(zeroed.len(), 3);
+        assert_eq!((zeroed[1].1, zeroed[2].1), (0.0, 0.0));
+    }
+
+    #[test]
+    fn test_invalid_zero_draws_numeric_strings_as_numbers() {
+        let spec = r#"{
+            "data": { "values": [{ "c": "A", "v": 10 }, { "c": "B", "v": "40" }, { "c": "C", "v": null }] },
+            "mark": { "type": "bar", "invalid": "zero" },
+            "encoding": { "x": { "field": "c" }, "y": { "field": "v", "type": "quantitative" } }
+        }"#;
+        let heights = |patch: Value| -> Vec<f64> {
+            crate::chart_with(spec, patch)
+                .mark_items()
+                .filter(|item| item.datum.is_some())
+                .filter_map(|item| match item.geometry {
+                    Geometry::Rect { height, .. } => Some(height),
+                    _ => None,
+                })
+                .collect()
+        };
+
+        let numbers = json!({ "data": { "values": [{ "c": "A", "v": 10 }, { "c": "B", "v": 40 }, { "c": "C", "v": 0 }] } });
+        assert_eq!(heights(json!({})), heights(numbers));
+        assert_eq!(heights(json!({})).len(), 3);
+    }
+
+    #[test]
+    fn test_view_fill_draws_plot_background_first() {
+        let scene = crate::chart(
+            r##"{
+                "width": 200, "height": 100, "padding": { "top": 10, "right": 10, "bottom": 20, "left": 30 },
+                "view": { "fill": "#eeeeee" },
+                "data": { "values": [{ "c": "A", "v": 10 }] },
+                "mark": "bar",
+                "encoding": { "x": { "field": "c" }, "y": { "field": "v" } }
+            }"##,
+        )
+        .unwrap();
+
+        let first = scene.mark_items().next().unwrap();
+        assert!(matches!(
+            first.geometry,
+            Geometry::Rect { x: 30.0, y: 10.0, width: 160.0, height: 70.0, .. }
+        ));
+        assert_eq!(first.fill, Some(Fill::Solid(Color::rgb(0xee, 0xee, 0xee))));
+    }
+
+    #[test]
+    fn test_view_pattern_tiles_grid_over_view_fill() {
+        let scene = crate::chart(
+            r##"{
+                "view": { "fill": "#fafafa", "pattern": "dots" },
+                "data": { "values": [{ "c": "A", "v": 10 }] },
+                "mark": "bar",
+                "encoding": { "x": { "field": "c" }, "y": { "field": "v" } }
+            }"##,
+        )
+        .unwrap();
+
+        let first = scene.mark_items().next().unwrap();
+        assert_eq!(
+            first.fill,
+            Some(Fill::Pattern {
+                pattern: PatternKind::Dots,
+                color: Theme::default().grid_color,
+                background: Some(Color::rgb(0xfa, 0xfa, 0xfa)),
+            })
+        );
+    }
+
+    #[test]
+    fn test_misspelled_field_is_an_error_with_suggestions() {
+        let err = crate::chart(
+            r#"{
+                "data": { "values": [{ "category": "A", "value": 1 }] },
+                "mark": "bar",
+                "encoding": { "x": { "field": "catgory" }, "y": { "field": "value" } }
+            }"#,
+        )
+        .unwrap_err();
+
+        assert_eq!(
+            err.to_string(),
+            r#"Invalid encoding: encoding.x field "catgory" is not in the data; did you mean "category"?"#
+        );
+    }
+
+    #[test]
+    fn test_count_aggregates_skip_the_field_check() {
+        let spec = r#"{
+            "data": { "values": [{ "k": "A" }, { "k": "B" }, { "k": "A" }] },
+            "mark": "bar",
+            "encoding": { "x": { "field": "k" }, "y": { "aggregate": "count" } }
+        }"#;
+        for patch in [json!({}), json!({ "encoding": { "y": { "field": "*" } } })] {
+            let counts: Vec<Option<f64>> = crate::chart_with(spec, patch)
+                .mark_items()
+                .filter_map(|item| item.datum.as_ref().map(|datum| datum["*"].as_f64()))
+                .collect();
+            assert_eq!(counts, [Some(2.0), Some(1.0)]);
+        }
+    }
+
+    #[test]
+    fn test_axis_label_limit_truncates_and_keeps_full_title() {
+        let scene = crate::chart(
+            r#"{
+                "data": { "values": [{ "k": "Very Long Category", "v": 1 }, { "k": "Short", "v": 2 }] },
+                "mark": "bar",
+                "encoding": {
+                    "x": { "field": "k", "axis": { "labelLimit": 60 } },
+                    "y": { "field": "v" }
+                }
+            }"#,
+        )
+        .unwrap();
+
+        let labels: Vec<(&str, Option<&str>)> = scene
+            .mark_items()
+            .filter_map(|item| match &item.geometry {
+                Geometry::Text { text, .. } if text.starts_with("Very") || text == "Short" => {
+                    Some((text.as_str(), item.title.as_deref()))
+                }
+                _ => None,
+            })
+            .collect();
+        assert_eq!(labels, vec![("Very Lo…", Some("Very Long Category")), ("Short", None)]);
+    }
+
+    #[test]
+    fn test_data_extent_and_distinct_categories() {
+        let data = vec![
+            json!({ "k": "b", "v": 3 }),
+            json!({ "k": "a", "v": -1.5 }),
+            json!({ "k": "b", "v": "n/a" }),
+        ];
+        assert_eq!(data_extent(&data, "v"), Some((-1.5, 3.0)));
+        assert_eq!(data_extent(&data, "k"), None);
+        assert_eq!(distinct_categories(&data, "k"), ["b", "a"]);
+    }
+
+    #[test]
+    fn test_float_encoded_whole_numbers_categorize_without_decimal() {
+        let data = vec![json!({ "year": 2020.0 }), json!({ "year": 2.5 }), json!({ "year": 2021 })];
+        assert_eq!(extract_categories(&data, "year"), ["2020", "2.5", "2021"]);
+
+        let scene = crate::chart(
+            r#"{
+                "data": { "values": [{ "year": 2020.0, "v": 1 }, { "year": 2021.0, "v": 2 }] },
+                "mark": "bar",
+                "encoding": { "x": { "field": "year", "type": "ordinal" }, "y": { "field": "v" } }
+            }"#,
+        )
+        .unwrap();
+        assert!(scene
+            .mark_items()
+            .any(|item| matches!(&item.geometry, Geometry::Text { text, .. } if text == "2020")));
+    }
+
+    #[test]
+    fn test_watermark_sits_in_a_corner_of_the_canvas() {
+        let spec = r#"{
+            "width": 300,
+            "height": 200,
+            "data": { "values": [{ "c": "A", "v": 10 }] },
+            "mark": "bar",
+            "encoding": { "x": { "field": "c" }, "y": { "field": "v" } },
+            "watermark": WATERMARK
+        }"#;
+        let watermark = |json: &str| {
+            let scene = crate::chart(&spec.replace("WATERMARK", json)).unwrap();
+            let found = scene
+                .mark_items()
+                .find_map(|item| match &item.geometry {
+                    Geometry::Text { x, y, text, anchor, .. } if text == "Source: ACME" => {
+                        Some((*x, *y, *anchor, item.opacity))
+                    }
+                    _ => None,
+                });
+            found.unwrap()
+        };
+
+        assert_eq!(watermark(r#""Source: ACME""#), (296.0, 196.0, TextAnchor::End, 0.4));
+        assert_eq!(
+            watermark(r#"{ "text": "Source: ACME", "position": "top-left", "opacity": 0.8 }"#),
+            (4.0, 4.0, TextAnchor::Start, 0.8)
+        );
+    }
+
+    #[test]
+    fn test_fullbleed_view_fills_the_canvas_with_data_marks_only() {
+        let spec = crate::parse_spec(
+            r#"{
+                "width": 400,
+                "height": 200,
+                "view": "fullbleed",
+                "data": { "values": [
+                    { "c": "A", "s": "x", "v": 10 }, { "c": "A", "s": "y", "v": 5 },
+                    { "c": "B", "s": "x", "v": 20 }, { "c": "B", "s": "y", "v": 8 }
+                ] },
+                "mark": "bar",
+                "encoding": { "x": { "field": "c" }, "y": { "field": "v" }, "color": { "field": "s" } }
+            }"#,
+        )
+        .unwrap();
+        assert!(spec.validate().is_empty());
+
+        let (scene, scales) = compile_with_scales(&spec).unwrap();
+        assert_eq!(scales.plot_area, PlotArea { x: 0.0, y: 0.0, width: 400.0, height: 200.0 });
+        assert!(scene.mark_items().all(|item| item.datum.is_some()));
+        assert_eq!(scene.mark_items().count(), 4);
+    }
+}
@@ -0,0 +1,247 @@
+use serde_json::Value;
+
+use super::position::PositionScale;
+use super::{
+    extract_numbers, generate_axis, generate_grid, generate_legend, ColorScale,
+    CompileContext, CompileError,
+};
+use crate::ir::{Geometry, Group, Mark, MarkItem, MarkType, SymbolShape, Transform};
+use crate::scale::{get_path, value_to_f64, LinearScale};
+use crate::spec::{AxisOrient, Encoding, MarkSort};
+
+/// Symbol area used when there is no size encoding or `mark.size`
+const DEFAULT_SIZE: f64 = 64.0;
+
+/// Symbol area range for the size channel
+const SIZE_RANGE: (f64, f64) = (16.0, 400.0);
+
+/// Padding between categories when an axis is categorical
+const BAND_PADDING: f64 = 0.2;
+
+/// Fraction of the band that jittered points spread across
+const JITTER_SPREAD: f64 = 0.8;
+
+/// Compile point (scatter) encoding to scene graph
+pub fn compile_point(
+    encoding: &Encoding,
+    data: &[Value],
+    ctx: &CompileContext,
+) -> Result<Group, CompileError> {
+    let plot_area = &ctx.plot_area;
+
+    let x_channel = encoding
+        .x
+        .as_ref()
+        .ok_or_else(|| CompileError::MissingField("encoding.x".to_string()))?;
+    let y_channel = encoding
+        .y
+        .as_ref()
+        .ok_or_else(|| CompileError::MissingField("encoding.y".to_string()))?;
+
+    let x_field = x_channel
+        .field()
+        .ok_or_else(|| CompileError::InvalidEncoding("x must have a field".to_string()))?;
+    let y_field = y_channel
+        .field()
+        .ok_or_else(|| CompileError::InvalidEncoding("y must have a field".to_string()))?;
+
+    let x_scale = PositionScale::new(x_channel, data, x_field, (0.0, plot_area.width), BAND_PADDING);
+    let y_scale = match PositionScale::new(y_channel, data, y_field, (plot_area.height, 0.0), BAND_PADDING) {
+        PositionScale::Linear(scale) => PositionScale::Linear(ctx.resolve_y(scale)),
+        band => band,
+    };
+
+    // Size channel maps a quantitative field linearly onto symbol area
+    let size_field = encoding.size.as_ref().and_then(|c| c.field());
+    let size_scale = size_field.map(|field| {
+        let sizes = extract_numbers(data, field);
+        let min = sizes.iter().cloned().fold(f64::INFINITY, f64::min);
+        let max = sizes.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
+        LinearScale::new((min, max), SIZE_RANGE)
+    });
+
+    // Color channel assigns palette colors per series, or a sequential ramp for numbers
+    let color = encoding.color.as_ref().and_then(|channel| {
+        let field = channel.field()?;
+        Some((field, ColorScale::from_channel(channel, data, field, ctx.theme)))
+    });
+
+    // Jitter spreads points across a categorical x band instead of piling on its center
+    let jitter_width = match ctx.config.jitter {
+        Some(true) => x_scale.bandwidth().map(|w| w * JITTER_SPREAD),
+        _ => None,
+    };
+
+    let mut point_items = Vec::new();
+
+    for (i, row) in data.iter().enumerate() {
+        let x = get_path(row, x_field)
+            .and_then(|v| x_scale.position(v))
+            .map(|x| x + jitter_width.map_or(0.0, |w| w * jitter(i as u64)));
+        let y = get_path(row, y_field).and_then(|v| y_scale.position(v));
+        let (Some(x), Some(y)) = (x, y) else {
+            continue;
+        };
+
+        let size = match (size_field, &size_scale) {
+            (Some(field), Some(scale)) => match get_path(row, field).and_then(value_to_f64) {
+                Some(v) => scale.scale(v),
+                None => continue,
+            },
+            _ => ctx.config.size.unwrap_or(DEFAULT_SIZE),
+        };
+
+        let fill = color
+            .as_ref()
+            .and_then(|(field, scale)| scale.fill(get_path(row, field)?, ctx))
+            .unwrap_or_else(|| ctx.series_fill(0));
+
+        point_items.push(
+            MarkItem::new(Geometry::Symbol {
+                x,
+                y,
+                size,
+                shape: SymbolShape::Circle,
+            })
+            .with_fill(fill)
+            .with_opacity(0.7)
+            .with_datum(row.clone()),
+        );
+    }
+
+    if ctx.config.sort == Some(MarkSort::SizeDescending) {
+        // Larger symbols first so smaller ones draw on top; the sort is stable
+        point_items.sort_by(|a, b| symbol_size(b).total_cmp(&symbol_size(a)));
+    }
+
+    let mut root = Group::new().with_transform(Transform::translate(plot_area.x, plot_area.y));
+
+    let x_axis = x_channel.axis();
+    let y_axis = y_channel.axis();
+    let x_ticks = x_scale.ticks(x_axis, &ctx.locale);
+    let y_ticks = y_scale.ticks(y_axis, &ctx.locale);
+
+    // Gridlines draw behind the points
+    if !ctx.data_only {
+        let grids = [
+            generate_grid(AxisOrient::Bottom, &x_ticks, plot_area, x_axis, ctx.theme),
+            generate_grid(AxisOrient::Left, &y_ticks, plot_area, y_axis, ctx.theme),
+        ];
+        for grid in grids.into_iter().flatten() {
+            root.add_mark(grid);
+        }
+    }
+
+    let mut points = Mark::new(MarkType::Symbol, point_items).as_data();
+    if let Some(template) = &ctx.config.tooltip {
+        points = points.with_tooltip(template.clone());
+    }
+    if ctx.config.shadow == Some(true) {
+        points = points.with_shadow();
+    }
+    root.add_mark(points);
+
+    if ctx.data_only {
+        ctx.record_scales(x_scale, y_scale);
+        return Ok(root);
+    }
+
+    if !x_channel.axis_disabled() {
+        for mark in generate_axis(AxisOrient::Bottom, &x_ticks, plot_area, x_axis, ctx.theme) {
+            root.add_mark(mark);
+        }
+    }
+    if !y_channel.axis_disabled() {
+        for mark in generate_axis(AxisOrient::Left, &y_ticks, plot_area, y_axis, ctx.theme) {
+            root.add_mark(mark);
+        }
+    }
+
+    if let Some((field, scale)) = &color {
+        root.add_group(generate_legend(scale, field, ctx));
+    }
+
+    ctx.record_scales(x_scale, y_scale);
+    Ok(root)
+}
+
+/// Deterministic offset in [-0.5, 0.5) for the row at `seed` (splitmix64)
+fn jitter(seed: u64) -> f64 {
+    let mut z = seed.wrapping_add(0x9E37_79B9_7F4A_7C15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^= z >> 31;
+    (z >> 11) as f64 / (1u64 << 53) as f64 - 0.5
+}
+
+fn symbol_size(item: &MarkItem) -> f64 {
+    match item.geometry {
+        Geometry::Symbol { size, .. } => size,
+        _ => 0.0,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ir::{Geometry, SceneNode};
+
+    #[test]
+    fn test_size_descending_draws_large_points_first() {
+        let scene = crate::chart(
+            r#"{
+                "data": { "values": [
+                    { "x": 1, "y": 1, "pop": 5 },
+                    { "x": 2, "y": 2, "pop": 50 },
+                    { "x": 3, "y": 3, "pop": 20 }
+                ] },
+                "mark": { "type": "point", "sort": "sizeDescending" },
+                "encoding": {
+                    "x": { "field": "x" },
+                    "y": { "field": "y" },
+                    "size": { "field": "pop" }
+                }
+            }"#,
+        )
+        .unwrap();
+
+        let SceneNode::Mark(points) = &scene.root.children[0] else {
+            panic!("expected point mark first");
+        };
+        let pops: Vec<_> = points
+            .items
+            .iter()
+            .map(|item| {
+                assert!(matches!(item.geometry, Geometry::Symbol { .. }));
+                item.datum.as_ref().unwrap()["pop"].as_i64().unwrap()
+            })
+            .collect();
+        assert_eq!(pops, vec![50, 20, 5]);
+    }
+
+    #[test]
+    fn test_jitter_spreads_points_within_the_band() {
+        let spec = r#"{
+            "width": 220, "padding": { "top": 0, "right": 0, "bottom": 0, "left": 20 },
+            "data": { "values": [
+                { "c": "A", "v": 1 }, { "c": "A", "v": 1 }, { "c": "A", "v": 1 }
+            ] },
+            "mark": { "type": "point", "jitter": true },
+            "encoding": { "x": { "field": "c" }, "y": { "field": "v" } }
+        }"#;
+        let xs = |scene: &crate::Scene| -> Vec<f64> {
+            scene
+                .mark_items()
+                .filter_map(|item| match item.geometry {
+                    Geometry::Symbol { x, .. } => Some(x),
+                    _ => None,
+                })
+                .collect()
+        };
+
+        let first = xs(&crate::chart(spec).unwrap());
+        assert_eq!(first, xs(&crate::chart(spec).unwrap()));
+        assert!(first.windows(2).all(|w| w[0] != w[1]));
+        // Band of 200px centered at 100, spread over 80% of it
+        assert!(first.iter().all(|x| (20.0..180.0).contains(x)));
+    }
+}
@@ -0,0 +1,146 @@
+use serde_json::Value;
+
+use super::{channel_categories, channel_type, data_extent};
+use crate::scale::{value_to_f64, value_to_string, BandScale, LinearScale, LogScale, NumberLocale, Tick};
+use crate::spec::{AxisConfig, ChannelDef, DataType};
+
+/// Position scale for one axis: linear (or log) for quantitative data, band centers otherwise
+#[derive(Debug, Clone, PartialEq)]
+pub enum PositionScale {
+    Linear(LinearScale),
+    Log(LogScale),
+    Band(BandScale),
+}
+
+impl PositionScale {
+    /// `band_padding` applies only when the data is categorical
+    pub fn new(
+        channel: &ChannelDef,
+        data: &[Value],
+        field: &str,
+        range: (f64, f64),
+        band_padding: f64,
+    ) -> Self {
+        match channel_type(channel, data, field) {
+            DataType::Quantitative => {
+                let domain = data_extent(data, field).unwrap_or((0.0, 1.0));
+                PositionScale::Linear(LinearScale::new(domain, range).nice_if(channel.nice()))
+            }
+            DataType::Nominal | DataType::Ordinal | DataType::Temporal => {
+                let categories = channel_categories(channel, data, field);
+                PositionScale::Band(BandScale::new(categories, range).padding(band_padding))
+            }
+        }
+    }
+
+    /// For categorical scales, spread the band centers so the first and last categories sit
+    /// exactly on the ends of the range; a single category stays centered
+    pub fn align_edges(self) -> Self {
+        match self {
+            PositionScale::Band(scale) if scale.domain().len() > 1 => {
+                let (start, end) = scale.range();
+                let half_step = (end - start) / (scale.domain().len() - 1) as f64 / 2.0;
+                let range = (start - half_step, end + half_step);
+                PositionScale::Band(BandScale::new(scale.domain().to_vec(), range).padding(0.0))
+            }
+            scale => scale,
+        }
+    }
+
+    pub fn position(&self, value: &Value) -> Option<f64> {
+        match self {
+            PositionScale::Linear(scale) => value_to_f64(value).map(|v| scale.scale(v)),
+            PositionScale::Log(scale) => value_to_f64(value).map(|v| scale.scale(v)),
+            PositionScale::Band(scale) => scale.scale_center(&value_to_string(value)),
+        }
+    }
+
+    /// Position of a value already converted to its string key (as grouped rows are)
+    pub fn position_of_key(&self, key: &str) -> Option<f64> {
+        match self {
+            PositionScale::Linear(scale) => key.parse().ok().map(|v| scale.scale(v)),
+            PositionScale::Log(scale) => key.parse().ok().map(|v| scale.scale(v)),
+            PositionScale::Band(scale) => scale.scale_center(key),
+        }
+    }
+
+    /// Band width for categorical scales, `None` for continuous ones
+    pub fn bandwidth(&self) -> Option<f64> {
+        match self {
+            PositionScale::Linear(_) | PositionScale::Log(_) => None,
+            PositionScale::Band(scale) => Some(scale.bandwidth()),
+        }
+    }
+
+    /// Ticks positioned in range (pixel) coordinates, numbers labeled in `locale`
+    pub fn ticks(&self, axis: Option<&AxisConfig>, locale: &NumberLocale) -> Vec<Tick> {
+        match self {
+            PositionScale::Linear(scale) => value_ticks(scale, axis, locale),
+            PositionScale::Log(scale) => log_ticks(scale, axis, locale),
+            PositionScale::Band(scale) => scale.ticks(),
+        }
+    }
+}
+
+/// Scale along a bar's value axis: linear, or log with `scaleType: "log"`
+#[derive(Debug, Clone, PartialEq)]
+pub enum ValueScale {
+    Linear(LinearScale),
+    Log(LogScale),
+}
+
+impl ValueScale {
+    pub fn scale(&self, value: f64) -> f64 {
+        match self {
+            ValueScale::Linear(scale) => scale.scale(value),
+            ValueScale::Log(scale) => scale.scale(value),
+        }
+    }
+
+    /// Ticks positioned in range (pixel) coordinates, as [`PositionScale::ticks`]
+    pub fn ticks(&self, axis: Option<&AxisConfig>, locale: &NumberLocale) -> Vec<Tick> {
+        match self {
+            ValueScale::Linear(scale) => value_ticks(scale, axis, locale),
+            ValueScale::Log(scale) => log_ticks(scale, axis, locale),
+        }
+    }
+
+    pub fn to_position(&self) -> PositionScale {
+        match self {
+            ValueScale::Linear(scale) => PositionScale::Linear(scale.clone()),
+            ValueScale::Log(scale) => PositionScale::Log(scale.clone()),
+        }
+    }
+}
+
+/// Ticks of a log axis positioned in range (pixel) coordinates: at `axis.values` when
+/// given, otherwise one per power of ten
+fn log_ticks(scale: &LogScale, axis: Option<&AxisConfig>, locale: &NumberLocale) -> Vec<Tick> {
+    let ticks = match axis.and_then(|axis| axis.values.as_deref()) {
+        Some(values) => scale.ticks_at(values, locale),
+        None => scale.ticks_with(locale),
+    };
+    ticks
+        .into_iter()
+        .map(|t| Tick {
+            value: scale.scale(t.value),
+            label: t.label,
+        })
+        .collect()
+}
+
+/// Ticks of a linear axis positioned in range (pixel) coordinates: at `axis.values` when
+/// given, otherwise wherever the scale puts them
+pub fn value_ticks(scale: &LinearScale, axis: Option<&AxisConfig>, locale: &NumberLocale) -> Vec<Tick> {
+    let ticks = match axis.and_then(|axis| axis.values.as_deref()) {
+        Some(values) => scale.ticks_at(values, locale),
+        None => scale.ticks_with(5, locale),
+    };
+    ticks
+        .into_iter()
+        .map(|t| Tick {
+            value: scale.scale(t.value),
+            label: t.label,
+        })
+        .collect()
+}
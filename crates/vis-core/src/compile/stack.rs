@@ -1,6 +1,7 @@
 use serde_json::Value;
 use std::collections::HashMap;
 
+use crate::scale::{get_path, value_to_f64, value_to_string};
 use crate::spec::{StackConfig, StackMode};
 
 /// Result of stacking computation for a single data point
@@ -16,6 +17,8 @@ pub struct StackedValue {
     pub category: String,
     /// Color/series value
     pub series: String,
+    /// Fraction of the category total this value makes up
+    pub share: f64,
 }
 
 /// Compute stacked values from data
@@ -41,7 +44,7 @@ pub fn compute_stack(
     for row in data {
         let category = extract_string(row, category_field).unwrap_or_default();
         let series = extract_string(row, series_field).unwrap_or_default();
-        let value = row.get(value_field).and_then(|v| v.as_f64()).unwrap_or(0.0);
+        let value = get_path(row, value_field).and_then(value_to_f64).unwrap_or(0.0);
 
         by_category
             .entry(category)
@@ -59,6 +62,7 @@ pub fn compute_stack(
         let mut cumulative = 0.0;
 
         for (series, value, row) in items {
+            let share = if total == 0.0 { 0.0 } else { value / total };
             let (y0, y1) = match mode {
                 StackMode::Zero => {
                     let y0 = cumulative;
@@ -91,6 +95,7 @@ pub fn compute_stack(
                 y1,
                 category: category.clone(),
                 series,
+                share,
             });
         }
     }
@@ -115,9 +120,5 @@ pub fn min_stacked_value(stacked: &[StackedValue]) -> f64 {
 }
 
 fn extract_string(row: &Value, field: &str) -> Option<String> {
-    row.get(field).map(|v| match v {
-        Value::String(s) => s.clone(),
-        Value::Number(n) => n.to_string(),
-        _ => v.to_string(),
-    })
+    get_path(row, field).map(value_to_string)
 }
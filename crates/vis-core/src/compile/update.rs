@@ -0,0 +1,212 @@
+use std::cell::RefCell;
+
+use serde_json::Value;
+
+use super::{compile_scene, compile_with_scales, CompileError, ScaleSet};
+use crate::ir::{Group, Mark, Scene, SceneNode};
+use crate::spec::{ChartSpec, DataSpec};
+use crate::theme::Theme;
+
+/// A compiled chart kept with its spec, so new rows for the same spec (e.g. a live
+/// dashboard's latest tick) can update the scene in place.
+///
+/// Updates first build the data marks alone and compare the scales they were drawn with
+/// against the cached ones. While the domains hold, the new marks are swapped into the
+/// existing scene and axes, gridlines and legend are never regenerated, so a renderer that
+/// keeps guides in their own layer can redraw just the data. A changed domain recompiles
+/// the whole chart.
+#[derive(Debug, Clone)]
+pub struct CompiledChart {
+    spec: ChartSpec,
+    scene: Scene,
+    scales: ScaleSet,
+}
+
+/// What [`CompiledChart::update_data`] changed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SceneUpdate {
+    /// Only the data marks; scales, axes, gridlines and legend are untouched
+    DataMarks,
+    /// The whole scene, because a domain, the plot area or the legend changed
+    Full,
+}
+
+impl CompiledChart {
+    pub fn new(spec: ChartSpec) -> Result<Self, CompileError> {
+        let (scene, scales) = compile_with_scales(&spec)?;
+        Ok(Self { spec, scene, scales })
+    }
+
+    pub fn scene(&self) -> &Scene {
+        &self.scene
+    }
+
+    pub fn scales(&self) -> &ScaleSet {
+        &self.scales
+    }
+
+    pub fn spec(&self) -> &ChartSpec {
+        &self.spec
+    }
+
+    /// Replace the chart's inline data with `values`, which should have the same fields.
+    /// On error the chart keeps its previous data and scene.
+    pub fn update_data(&mut self, values: Vec<Value>) -> Result<SceneUpdate, CompileError> {
+        let previous = std::mem::replace(&mut self.spec.data, DataSpec::Inline { values });
+        let update = self.update_scene();
+        if update.is_err() {
+            self.spec.data = previous;
+        }
+        update
+    }
+
+    fn update_scene(&mut self) -> Result<SceneUpdate, CompileError> {
+        let scales = RefCell::default();
+        let marks = compile_scene(&self.spec, &Theme::default(), &scales, true)?;
+        if scales.into_inner() == self.scales && data_marks_in(&marks.root) == data_marks_in(&self.scene.root) {
+            let mut data_marks = Vec::new();
+            collect_data_marks(marks.root, &mut data_marks);
+            replace_data_marks(&mut self.scene.root, &mut data_marks.into_iter());
+            return Ok(SceneUpdate::DataMarks);
+        }
+
+        let (scene, scales) = compile_with_scales(&self.spec)?;
+        self.scene = scene;
+        self.scales = scales;
+        Ok(SceneUpdate::Full)
+    }
+}
+
+fn data_marks_in(group: &Group) -> usize {
+    group
+        .children
+        .iter()
+        .map(|child| match child {
+            SceneNode::Group(group) => data_marks_in(group),
+            SceneNode::Mark(mark) => usize::from(mark.data),
+        })
+        .sum()
+}
+
+fn collect_data_marks(group: Group, out: &mut Vec<Mark>) {
+    for child in group.children {
+        match child {
+            SceneNode::Group(group) => collect_data_marks(*group, out),
+            SceneNode::Mark(mark) if mark.data => out.push(mark),
+            SceneNode::Mark(_) => {}
+        }
+    }
+}
+
+fn replace_data_marks(group: &mut Group, marks: &mut impl Iterator<Item = Mark>) {
+    for child in &mut group.children {
+        match child {
+            SceneNode::Group(group) => replace_data_marks(group, marks),
+            SceneNode::Mark(mark) if mark.data => {
+                if let Some(new) = marks.next() {
+                    *mark = new;
+                }
+            }
+            SceneNode::Mark(_) => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn chart() -> CompiledChart {
+        let spec = crate::parse_spec(
+            r#"{
+                "data": { "values": [{ "k": "A", "v": 30 }, { "k": "B", "v": 80 }] },
+                "mark": "bar",
+                "encoding": { "x": { "field": "k" }, "y": { "field": "v" } }
+            }"#,
+        )
+        .unwrap();
+        CompiledChart::new(spec).unwrap()
+    }
+
+    #[test]
+    fn test_data_within_the_domain_swaps_only_the_data_marks() {
+        let mut chart = chart();
+        let before = chart.scene().clone();
+
+        let values = vec![json!({ "k": "A", "v": 55 }), json!({ "k": "B", "v": 75 })];
+        assert_eq!(chart.update_data(values.clone()).unwrap(), SceneUpdate::DataMarks);
+
+        let mut spec = chart.spec().clone();
+        spec.data = DataSpec::Inline { values };
+        let (expected, _) = compile_with_scales(&spec).unwrap();
+        assert_eq!(*chart.scene(), expected);
+        assert_ne!(*chart.scene(), before);
+    }
+
+    #[test]
+    fn test_changed_domain_or_categories_recompile_the_whole_scene() {
+        let mut chart = chart();
+        let taller = vec![json!({ "k": "A", "v": 30 }), json!({ "k": "B", "v": 250 })];
+        assert_eq!(chart.update_data(taller).unwrap(), SceneUpdate::Full);
+
+        let more = vec![json!({ "k": "A", "v": 30 }), json!({ "k": "B", "v": 250 }), json!({ "k": "C", "v": 1 })];
+        assert_eq!(chart.update_data(more).unwrap(), SceneUpdate::Full);
+
+        assert!(chart.update_data(vec![json!({ "key": "A" })]).is_err());
+        assert_eq!(chart.spec().data.values().unwrap().len(), 3);
+    }
+
+    #[test]
+    fn test_single_series_lines_and_areas_are_swapped() {
+        for mark in ["line", "area"] {
+            let spec = crate::parse_spec(&format!(
+                r#"{{
+                    "data": {{ "values": [{{ "k": "A", "v": 10 }}, {{ "k": "B", "v": 80 }}] }},
+                    "mark": "{mark}",
+                    "encoding": {{ "x": {{ "field": "k" }}, "y": {{ "field": "v" }} }}
+                }}"#
+            ))
+            .unwrap();
+            let mut chart = CompiledChart::new(spec).unwrap();
+            let before = chart.scene().clone();
+
+            let values = vec![json!({ "k": "A", "v": 80 }), json!({ "k": "B", "v": 10 })];
+            assert_eq!(chart.update_data(values.clone()).unwrap(), SceneUpdate::DataMarks, "{mark}");
+
+            let mut spec = chart.spec().clone();
+            spec.data = DataSpec::Inline { values };
+            let (expected, _) = compile_with_scales(&spec).unwrap();
+            assert_eq!(*chart.scene(), expected, "{mark}");
+            assert_ne!(*chart.scene(), before, "{mark}");
+        }
+    }
+
+    #[test]
+    fn test_new_color_category_recompiles_the_legend() {
+        let spec = crate::parse_spec(
+            r#"{
+                "data": { "values": [
+                    { "t": 1, "v": 10, "s": "a" }, { "t": 2, "v": 20, "s": "a" },
+                    { "t": 1, "v": 15, "s": "b" }, { "t": 2, "v": 5, "s": "b" }
+                ] },
+                "mark": "line",
+                "encoding": {
+                    "x": { "field": "t", "type": "quantitative" },
+                    "y": { "field": "v" },
+                    "color": { "field": "s" }
+                }
+            }"#,
+        )
+        .unwrap();
+        let mut chart = CompiledChart::new(spec).unwrap();
+        let row = |t: i32, v: i32, s: &str| json!({ "t": t, "v": v, "s": s });
+
+        let moved = vec![row(1, 20, "a"), row(2, 10, "a"), row(1, 5, "b"), row(2, 15, "b")];
+        assert_eq!(chart.update_data(moved).unwrap(), SceneUpdate::DataMarks);
+
+        let renamed = vec![row(1, 20, "a"), row(2, 10, "a"), row(1, 5, "c"), row(2, 15, "c")];
+        assert_eq!(chart.update_data(renamed).unwrap(), SceneUpdate::Full);
+    }
+}
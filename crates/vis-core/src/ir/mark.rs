@@ -1,9 +1,9 @@
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 
-use super::style::{Color, Font, Stroke, TextAnchor, TextBaseline};
+use super::style::{Fill, Font, Stroke, TextAnchor, TextBaseline};
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
 pub enum MarkType {
     Rect,
@@ -16,21 +16,64 @@ pub enum MarkType {
     Path,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Mark {
     pub mark_type: MarkType,
     pub items: Vec<MarkItem>,
+    /// Tooltip template filled from each item's datum, e.g. `"{category}: {value}"`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tooltip: Option<String>,
+    /// Draw a soft drop shadow under the mark's items
+    #[serde(default)]
+    pub shadow: bool,
+    /// Drawn from the data rather than a guide (axis, gridline, legend), so passes such as
+    /// data updates can find it even when its items carry no datum
+    #[serde(default)]
+    pub data: bool,
+}
+
+impl Mark {
+    pub fn new(mark_type: MarkType, items: Vec<MarkItem>) -> Self {
+        Self {
+            mark_type,
+            items,
+            tooltip: None,
+            shadow: false,
+            data: false,
+        }
+    }
+
+    pub fn with_tooltip(mut self, template: impl Into<String>) -> Self {
+        self.tooltip = Some(template.into());
+        self
+    }
+
+    pub fn with_shadow(mut self) -> Self {
+        self.shadow = true;
+        self
+    }
+
+    pub fn as_data(mut self) -> Self {
+        self.data = true;
+        self
+    }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct MarkItem {
     pub geometry: Geometry,
-    pub fill: Option<Color>,
+    pub fill: Option<Fill>,
     pub stroke: Option<Stroke>,
     pub opacity: f64,
+    /// Opacity of the fill alone, leaving the stroke opaque
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fill_opacity: Option<f64>,
     /// Original datum for interactivity (tooltips, brushing)
     #[serde(skip_serializing_if = "Option::is_none")]
     pub datum: Option<Value>,
+    /// Hover text when the mark has no tooltip, e.g. the full text of a truncated label
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
 }
 
 impl MarkItem {
@@ -40,12 +83,14 @@ impl MarkItem {
             fill: None,
             stroke: None,
             opacity: 1.0,
+            fill_opacity: None,
             datum: None,
+            title: None,
         }
     }
 
-    pub fn with_fill(mut self, color: Color) -> Self {
-        self.fill = Some(color);
+    pub fn with_fill(mut self, fill: impl Into<Fill>) -> Self {
+        self.fill = Some(fill.into());
         self
     }
 
@@ -59,6 +104,16 @@ impl MarkItem {
         self
     }
 
+    pub fn with_fill_opacity(mut self, opacity: f64) -> Self {
+        self.fill_opacity = Some(opacity);
+        self
+    }
+
+    pub fn with_title(mut self, title: impl Into<String>) -> Self {
+        self.title = Some(title.into());
+        self
+    }
+
     pub fn with_datum(mut self, datum: Value) -> Self {
         self.datum = Some(datum);
         self
@@ -1,12 +1,19 @@
 use serde::{Deserialize, Serialize};
 
-use super::mark::Mark;
+use super::mark::{Geometry, Mark, MarkItem, MarkType, Point};
+use crate::scale::{get_path, value_to_string};
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// Opacity multiplier for items outside the emphasized series
+const MUTED_OPACITY: f64 = 0.2;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Scene {
     pub width: f64,
     pub height: f64,
     pub background: Option<super::style::Color>,
+    /// Text is laid out right to left; positions and anchors stay as compiled
+    #[serde(default)]
+    pub rtl: bool,
     pub root: Group,
 }
 
@@ -16,6 +23,7 @@ impl Scene {
             width,
             height,
             background: None,
+            rtl: false,
             root: Group::default(),
         }
     }
@@ -24,18 +32,383 @@ impl Scene {
         self.background = Some(color);
         self
     }
+
+    /// Lay several scenes out in one, e.g. for a hand-built dashboard: each `(scene, x, y)`
+    /// becomes a group translated to `(x, y)`, and the result is just large enough to hold
+    /// them all. A child's background is kept as a rect behind its content; the composed
+    /// scene has none.
+    pub fn compose(scenes: &[(Scene, f64, f64)]) -> Scene {
+        let width = scenes.iter().map(|(s, x, _)| x + s.width).fold(0.0, f64::max);
+        let height = scenes.iter().map(|(s, _, y)| y + s.height).fold(0.0, f64::max);
+        let mut composed = Scene::new(width, height);
+
+        for (scene, x, y) in scenes {
+            let mut group = Group::new().with_transform(Transform::translate(*x, *y));
+            if let Some(background) = scene.background {
+                group.add_mark(Mark::new(
+                    MarkType::Rect,
+                    vec![MarkItem::new(Geometry::Rect {
+                        x: 0.0,
+                        y: 0.0,
+                        width: scene.width,
+                        height: scene.height,
+                        corner_radius: 0.0,
+                    })
+                    .with_fill(background)],
+                ));
+            }
+            group.add_group(scene.root.clone());
+            composed.root.add_group(group);
+        }
+        composed
+    }
+
+    /// Iterate over every mark item in the scene, depth-first in draw order
+    pub fn mark_items(&self) -> impl Iterator<Item = &MarkItem> {
+        MarkItems {
+            stack: vec![self.root.children.iter()],
+            items: [].iter(),
+        }
+    }
+
+    /// Copy of the scene with every group transform baked into item coordinates, for
+    /// renderers without nested transforms.
+    ///
+    /// Marks come out directly under an identity root in draw order. Groups with a clip or
+    /// opacity stay as identity-transform groups, with the clip mapped to its bounding box,
+    /// since fading a group as a whole differs from fading each item. Radii, symbol sizes,
+    /// font sizes and stroke widths scale by the transform's average scale.
+    ///
+    /// A mark whose geometry the transform can't be baked into keeps it instead, in a group
+    /// of its own carrying the full transform to the canvas: paths under any transform, rects
+    /// under rotation or skew, circles, arcs and text under uneven scale or skew, and symbols
+    /// under anything but an even scale and translation.
+    pub fn flatten(&self) -> Scene {
+        let mut root = Group::new();
+        flatten_group(&self.root, &Transform::default(), &mut root);
+        Scene {
+            width: self.width,
+            height: self.height,
+            background: self.background,
+            rtl: self.rtl,
+            root,
+        }
+    }
+
+    /// Mute every item whose datum has a `series_field` value other than `focus`, e.g. to
+    /// pre-render a "hover to highlight" state without recompiling. Items without that field
+    /// (axes, legends) keep their opacity. Muted items are set to a fixed opacity rather than
+    /// faded further, so emphasising the same scene twice gives the same result.
+    pub fn set_series_emphasis(&mut self, series_field: &str, focus: &str) {
+        for item in self.mark_items_mut() {
+            let Some(value) = item.datum.as_ref().and_then(|d| get_path(d, series_field)) else {
+                continue;
+            };
+            if value_to_string(value) != focus {
+                item.opacity = MUTED_OPACITY;
+            }
+        }
+    }
+
+    /// Remove every item whose datum has a `series_field` value in `names`, e.g. to toggle
+    /// series on a dashboard without recompiling. Entries for those values are dropped
+    /// from the color legend and the remaining ones move up into their slots. Scales and
+    /// stacks are left as compiled, so hidden stack layers leave a gap.
+    pub fn hide_series(&mut self, series_field: &str, names: &[&str]) {
+        hide_in_group(&mut self.root, series_field, names, &Group::legend_name(series_field));
+    }
+
+    /// Mutable variant of [`Scene::mark_items`] for restyling items in place
+    pub fn mark_items_mut(&mut self) -> impl Iterator<Item = &mut MarkItem> {
+        MarkItemsMut {
+            stack: vec![self.root.children.iter_mut()],
+            items: [].iter_mut(),
+        }
+    }
+}
+
+struct MarkItems<'a> {
+    stack: Vec<std::slice::Iter<'a, SceneNode>>,
+    items: std::slice::Iter<'a, MarkItem>,
+}
+
+impl<'a> Iterator for MarkItems<'a> {
+    type Item = &'a MarkItem;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(item) = self.items.next() {
+                return Some(item);
+            }
+            match self.stack.last_mut()?.next() {
+                Some(SceneNode::Mark(mark)) => self.items = mark.items.iter(),
+                Some(SceneNode::Group(group)) => self.stack.push(group.children.iter()),
+                None => {
+                    self.stack.pop();
+                }
+            }
+        }
+    }
+}
+
+struct MarkItemsMut<'a> {
+    stack: Vec<std::slice::IterMut<'a, SceneNode>>,
+    items: std::slice::IterMut<'a, MarkItem>,
+}
+
+impl<'a> Iterator for MarkItemsMut<'a> {
+    type Item = &'a mut MarkItem;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(item) = self.items.next() {
+                return Some(item);
+            }
+            match self.stack.last_mut()?.next() {
+                Some(SceneNode::Mark(mark)) => self.items = mark.items.iter_mut(),
+                Some(SceneNode::Group(group)) => self.stack.push(group.children.iter_mut()),
+                None => {
+                    self.stack.pop();
+                }
+            }
+        }
+    }
+}
+
+fn hide_in_group(group: &mut Group, series_field: &str, names: &[&str], legend_name: &str) {
+    let hidden = |value: &str| names.contains(&value);
+
+    for child in &mut group.children {
+        match child {
+            SceneNode::Mark(mark) => mark.items.retain(|item| {
+                let series = item.datum.as_ref().and_then(|d| get_path(d, series_field));
+                !series.is_some_and(|value| hidden(&value_to_string(value)))
+            }),
+            SceneNode::Group(child) => hide_in_group(child, series_field, names, legend_name),
+        }
+    }
+
+    if group.name.as_deref() == Some(legend_name) {
+        // Entries are named by their value; kept ones take the slots in order
+        let slots: Vec<Transform> = legend_entries(group).map(|entry| entry.transform).collect();
+        group.children.retain(|child| match child {
+            SceneNode::Group(entry) => !entry.name.as_deref().is_some_and(hidden),
+            SceneNode::Mark(_) => true,
+        });
+        for (entry, slot) in legend_entries(group).zip(slots) {
+            entry.transform = slot;
+        }
+    }
 }
 
-#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+fn legend_entries(legend: &mut Group) -> impl Iterator<Item = &mut Group> {
+    legend.children.iter_mut().filter_map(|child| match child {
+        SceneNode::Group(entry) if entry.name.is_some() => Some(&mut **entry),
+        _ => None,
+    })
+}
+
+/// Append `group`'s marks to `out` with `parent` (the transform above `group`) applied
+fn flatten_group(group: &Group, parent: &Transform, out: &mut Group) {
+    let transform = group.transform.then(parent);
+    // Clips and group opacity can't be pushed down onto items, so those groups stay
+    // (without their transform)
+    let mut kept = (group.clip.is_some() || group.opacity < 1.0).then(|| Group {
+        clip: group.clip.map(|clip| transform_rect(&clip, &transform)),
+        opacity: group.opacity,
+        ..Group::new()
+    });
+    let target = kept.as_mut().unwrap_or(out);
+
+    for child in &group.children {
+        match child {
+            SceneNode::Mark(mark) if !mark.items.iter().all(|item| can_bake(&item.geometry, &transform)) => {
+                let mut group = Group::new().with_transform(transform);
+                group.add_mark(mark.clone());
+                target.add_group(group);
+            }
+            SceneNode::Mark(mark) => {
+                let mut mark = mark.clone();
+                for item in &mut mark.items {
+                    transform_item(item, &transform);
+                }
+                target.add_mark(mark);
+            }
+            SceneNode::Group(child) => flatten_group(child, &transform, target),
+        }
+    }
+
+    if let Some(kept) = kept {
+        out.add_group(kept);
+    }
+}
+
+/// Whether [`transform_item`] maps `geometry` exactly
+fn can_bake(geometry: &Geometry, transform: &Transform) -> bool {
+    let [a, b, c, d, _, _] = transform.to_matrix();
+    let axis_aligned = b == 0.0 && c == 0.0;
+    // Rotation and an even, unflipped scale, which keep shapes and angles
+    let similar = (a - d).abs() < 1e-9 && (b + c).abs() < 1e-9 && a * d - b * c > 0.0;
+    match geometry {
+        Geometry::Line { .. } | Geometry::Area { .. } | Geometry::Rule { .. } => true,
+        Geometry::Rect { .. } => axis_aligned,
+        Geometry::Circle { .. } | Geometry::Arc { .. } | Geometry::Text { .. } => similar,
+        Geometry::Symbol { .. } => axis_aligned && similar,
+        Geometry::Path { .. } => transform.is_identity(),
+    }
+}
+
+fn transform_item(item: &mut MarkItem, transform: &Transform) {
+    let scale = transform.average_scale();
+    if let Some(stroke) = &mut item.stroke {
+        stroke.width *= scale;
+    }
+    let point = |x: f64, y: f64| transform.apply(x, y);
+    item.geometry = match item.geometry.clone() {
+        Geometry::Rect {
+            x,
+            y,
+            width,
+            height,
+            corner_radius,
+        } => {
+            let bounds = transform_rect(&Rect::new(x, y, width, height), transform);
+            Geometry::Rect {
+                x: bounds.x,
+                y: bounds.y,
+                width: bounds.width,
+                height: bounds.height,
+                corner_radius: corner_radius * scale,
+            }
+        }
+        Geometry::Circle { cx, cy, r } => {
+            let (cx, cy) = point(cx, cy);
+            Geometry::Circle { cx, cy, r: r * scale }
+        }
+        Geometry::Line { points } => Geometry::Line {
+            points: transform_points(&points, transform),
+        },
+        Geometry::Area { points, baseline } => Geometry::Area {
+            points: transform_points(&points, transform),
+            baseline: transform_points(&baseline, transform),
+        },
+        Geometry::Rule { x1, y1, x2, y2 } => {
+            let (x1, y1) = point(x1, y1);
+            let (x2, y2) = point(x2, y2);
+            Geometry::Rule { x1, y1, x2, y2 }
+        }
+        Geometry::Text {
+            x,
+            y,
+            text,
+            mut font,
+            anchor,
+            baseline,
+            angle,
+        } => {
+            let (x, y) = point(x, y);
+            font.size *= scale;
+            Geometry::Text {
+                x,
+                y,
+                text,
+                font,
+                anchor,
+                baseline,
+                angle: angle + transform.rotation().to_degrees(),
+            }
+        }
+        Geometry::Arc {
+            cx,
+            cy,
+            inner_radius,
+            outer_radius,
+            start_angle,
+            end_angle,
+        } => {
+            let (cx, cy) = point(cx, cy);
+            let rotation = transform.rotation();
+            Geometry::Arc {
+                cx,
+                cy,
+                inner_radius: inner_radius * scale,
+                outer_radius: outer_radius * scale,
+                start_angle: start_angle + rotation,
+                end_angle: end_angle + rotation,
+            }
+        }
+        Geometry::Symbol { x, y, size, shape } => {
+            let (x, y) = point(x, y);
+            Geometry::Symbol {
+                x,
+                y,
+                size: size * scale * scale,
+                shape,
+            }
+        }
+        // Only reached under an identity transform, see `can_bake`
+        path @ Geometry::Path { .. } => path,
+    };
+}
+
+fn transform_points(points: &[Point], transform: &Transform) -> Vec<Point> {
+    points
+        .iter()
+        .map(|p| {
+            let (x, y) = transform.apply(p.x, p.y);
+            Point::new(x, y)
+        })
+        .collect()
+}
+
+/// Axis-aligned bounds of a transformed rect
+fn transform_rect(rect: &Rect, transform: &Transform) -> Rect {
+    let corners = [
+        transform.apply(rect.x, rect.y),
+        transform.apply(rect.x + rect.width, rect.y),
+        transform.apply(rect.x, rect.y + rect.height),
+        transform.apply(rect.x + rect.width, rect.y + rect.height),
+    ];
+    let (mut min_x, mut min_y) = (f64::INFINITY, f64::INFINITY);
+    let (mut max_x, mut max_y) = (f64::NEG_INFINITY, f64::NEG_INFINITY);
+    for (x, y) in corners {
+        min_x = min_x.min(x);
+        min_y = min_y.min(y);
+        max_x = max_x.max(x);
+        max_y = max_y.max(y);
+    }
+    Rect::new(min_x, min_y, max_x - min_x, max_y - min_y)
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Group {
     #[serde(default)]
     pub transform: Transform,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub clip: Option<Rect>,
+    /// What the group holds, for post-passes that need to find it: a color legend is named
+    /// by [`Group::legend_name`] and each of its categorical entries by the entry's value
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    /// Opacity of the whole subtree, composited as one layer
+    #[serde(default = "one")]
+    pub opacity: f64,
     #[serde(default)]
     pub children: Vec<SceneNode>,
 }
 
+impl Default for Group {
+    fn default() -> Self {
+        Self {
+            transform: Transform::default(),
+            clip: None,
+            name: None,
+            opacity: 1.0,
+            children: Vec::new(),
+        }
+    }
+}
+
 impl Group {
     pub fn new() -> Self {
         Self::default()
@@ -51,6 +424,21 @@ impl Group {
         self
     }
 
+    pub fn with_opacity(mut self, opacity: f64) -> Self {
+        self.opacity = opacity;
+        self
+    }
+
+    pub fn with_name(mut self, name: impl Into<String>) -> Self {
+        self.name = Some(name.into());
+        self
+    }
+
+    /// Name of the legend group for a color field
+    pub fn legend_name(field: &str) -> String {
+        format!("legend:{field}")
+    }
+
     pub fn add_child(&mut self, node: SceneNode) {
         self.children.push(node);
     }
@@ -64,14 +452,20 @@ impl Group {
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(untagged)]
 pub enum SceneNode {
     Group(Box<Group>),
     Mark(Mark),
 }
 
-#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+/// Affine matrix `[a, b, c, d, e, f]` in SVG order: `x' = a*x + c*y + e`, `y' = b*x + d*y + f`
+pub type Matrix = [f64; 6];
+
+const IDENTITY: Matrix = [1.0, 0.0, 0.0, 1.0, 0.0, 0.0];
+
+/// Group transform, applied to points as rotate (degrees), then scale, then translate
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
 pub struct Transform {
     #[serde(default)]
     pub translate_x: f64,
@@ -83,12 +477,21 @@ pub struct Transform {
     pub scale_y: f64,
     #[serde(default)]
     pub rotate: f64,
+    /// Composed affine matrix; when set it replaces the translate/scale/rotate fields
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub matrix: Option<Matrix>,
 }
 
 fn one() -> f64 {
     1.0
 }
 
+impl Default for Transform {
+    fn default() -> Self {
+        Self::translate(0.0, 0.0)
+    }
+}
+
 impl Transform {
     pub fn translate(x: f64, y: f64) -> Self {
         Self {
@@ -97,10 +500,73 @@ impl Transform {
             scale_x: 1.0,
             scale_y: 1.0,
             rotate: 0.0,
+            matrix: None,
+        }
+    }
+
+    /// Transform for an arbitrary affine matrix, using the plain fields when the matrix
+    /// is only a scale and translation
+    pub fn from_matrix(m: Matrix) -> Self {
+        if m[1] == 0.0 && m[2] == 0.0 {
+            Self {
+                scale_x: m[0],
+                scale_y: m[3],
+                ..Self::translate(m[4], m[5])
+            }
+        } else {
+            Self {
+                matrix: Some(m),
+                ..Self::translate(0.0, 0.0)
+            }
         }
     }
 
+    /// The equivalent affine matrix
+    pub fn to_matrix(&self) -> Matrix {
+        if let Some(m) = self.matrix {
+            return m;
+        }
+        let (sin, cos) = self.rotate.to_radians().sin_cos();
+        let rotate = [cos, sin, -sin, cos, 0.0, 0.0];
+        let scale_translate = [
+            self.scale_x,
+            0.0,
+            0.0,
+            self.scale_y,
+            self.translate_x,
+            self.translate_y,
+        ];
+        multiply(&scale_translate, &rotate)
+    }
+
+    /// A transform that applies `self` and then `other`; for a group nested in a parent,
+    /// `child.then(&parent)` maps child coordinates to the parent's parent
+    pub fn then(&self, other: &Transform) -> Transform {
+        Transform::from_matrix(multiply(&other.to_matrix(), &self.to_matrix()))
+    }
+
+    /// Map a point through the transform
+    pub fn apply(&self, x: f64, y: f64) -> (f64, f64) {
+        let [a, b, c, d, e, f] = self.to_matrix();
+        (a * x + c * y + e, b * x + d * y + f)
+    }
+
+    /// Rotation in radians
+    fn rotation(&self) -> f64 {
+        let m = self.to_matrix();
+        m[1].atan2(m[0])
+    }
+
+    /// Geometric mean of the x and y scale factors, for sizing radii and widths
+    fn average_scale(&self) -> f64 {
+        let m = self.to_matrix();
+        (m[0] * m[3] - m[1] * m[2]).abs().sqrt()
+    }
+
     pub fn to_svg(&self) -> String {
+        if let Some([a, b, c, d, e, f]) = self.matrix {
+            return format!("matrix({a},{b},{c},{d},{e},{f})");
+        }
         let mut parts = Vec::new();
         if self.translate_x != 0.0 || self.translate_y != 0.0 {
             parts.push(format!("translate({},{})", self.translate_x, self.translate_y));
@@ -115,14 +581,31 @@ impl Transform {
     }
 
     pub fn is_identity(&self) -> bool {
-        self.translate_x == 0.0
-            && self.translate_y == 0.0
-            && self.scale_x == 1.0
-            && self.scale_y == 1.0
-            && self.rotate == 0.0
+        match self.matrix {
+            Some(m) => m == IDENTITY,
+            None => {
+                self.translate_x == 0.0
+                    && self.translate_y == 0.0
+                    && self.scale_x == 1.0
+                    && self.scale_y == 1.0
+                    && self.rotate == 0.0
+            }
+        }
     }
 }
 
+/// Matrix product `m1 * m2`: the result applies `m2` first, then `m1`
+fn multiply(m1: &Matrix, m2: &Matrix) -> Matrix {
+    [
+        m1[0] * m2[0] + m1[2] * m2[1],
+        m1[1] * m2[0] + m1[3] * m2[1],
+        m1[0] * m2[2] + m1[2] * m2[3],
+        m1[1] * m2[2] + m1[3] * m2[3],
+        m1[0] * m2[4] + m1[2] * m2[5] + m1[4],
+        m1[1] * m2[4] + m1[3] * m2[5] + m1[5],
+    ]
+}
+
 #[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
 pub struct Rect {
     pub x: f64,
@@ -159,3 +642,245 @@ impl Padding {
         Self { top, right, bottom, left }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ir::{Geometry, MarkType};
+
+    fn rule(x: f64) -> MarkItem {
+        MarkItem::new(Geometry::Rule {
+            x1: x,
+            y1: 0.0,
+            x2: x,
+            y2: 1.0,
+        })
+    }
+
+    #[test]
+    fn test_mark_items_flattens_nested_groups() {
+        let mut inner = Group::new();
+        inner.add_mark(Mark::new(MarkType::Rule, vec![rule(2.0), rule(3.0)]));
+
+        let mut scene = Scene::new(10.0, 10.0);
+        scene.root.add_mark(Mark::new(MarkType::Rule, vec![rule(1.0)]));
+        scene.root.add_group(inner);
+        scene.root.add_mark(Mark::new(MarkType::Rule, vec![rule(4.0)]));
+
+        for item in scene.mark_items_mut() {
+            item.opacity = 0.5;
+        }
+
+        let xs: Vec<f64> = scene
+            .mark_items()
+            .map(|item| match item.geometry {
+                Geometry::Rule { x1, .. } => x1,
+                _ => unreachable!(),
+            })
+            .collect();
+        assert_eq!(xs, vec![1.0, 2.0, 3.0, 4.0]);
+        assert!(scene.mark_items().all(|item| item.opacity == 0.5));
+    }
+
+    #[test]
+    fn test_transform_then_composes_in_application_order() {
+        let child = Transform::translate(5.0, 0.0);
+        let parent = Transform {
+            scale_x: 2.0,
+            scale_y: 2.0,
+            ..Transform::translate(10.0, 20.0)
+        };
+        let composed = child.then(&parent);
+        assert_eq!(composed.to_svg(), "translate(20,20) scale(2,2)");
+
+        // Rotating about a point has no translate/scale/rotate form, so it keeps the matrix
+        let rotate = Transform {
+            rotate: 90.0,
+            ..Transform::default()
+        };
+        let about_point = Transform::translate(-1.0, 0.0)
+            .then(&rotate)
+            .then(&Transform::translate(1.0, 0.0));
+        let m = about_point.to_matrix();
+        assert!(about_point.matrix.is_some());
+        // (2, 0) rotates a quarter turn about (1, 0) to (1, 1)
+        assert!((m[0] * 2.0 + m[4] - 1.0).abs() < 1e-9);
+        assert!((m[1] * 2.0 + m[5] - 1.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_flatten_bakes_nested_transforms_into_coordinates() {
+        let mut inner = Group::new().with_transform(Transform {
+            scale_x: 2.0,
+            scale_y: 2.0,
+            ..Transform::translate(5.0, 5.0)
+        });
+        inner.add_mark(Mark::new(MarkType::Rule, vec![rule(1.0)]));
+
+        let mut outer = Group::new().with_transform(Transform::translate(10.0, 20.0));
+        outer.add_group(inner);
+
+        let mut scene = Scene::new(100.0, 100.0);
+        scene.root.add_group(outer);
+
+        let flat = scene.flatten();
+        assert!(flat.root.transform.is_identity());
+        assert!(flat.root.children.iter().all(|node| matches!(node, SceneNode::Mark(_))));
+        let geometry = &flat.mark_items().next().unwrap().geometry;
+        assert_eq!(
+            *geometry,
+            Geometry::Rule {
+                x1: 17.0,
+                y1: 25.0,
+                x2: 17.0,
+                y2: 27.0
+            }
+        );
+    }
+
+    #[test]
+    fn test_flatten_keeps_group_opacity() {
+        let mut faded = Group::new()
+            .with_transform(Transform::translate(10.0, 0.0))
+            .with_opacity(0.4);
+        faded.add_mark(Mark::new(MarkType::Rule, vec![rule(1.0)]));
+
+        let mut scene = Scene::new(100.0, 100.0);
+        scene.root.add_group(faded);
+
+        let flat = scene.flatten();
+        let [SceneNode::Group(group)] = flat.root.children.as_slice() else {
+            panic!("expected the faded group to survive flattening");
+        };
+        assert_eq!(group.opacity, 0.4);
+        assert!(group.transform.is_identity());
+        assert!(matches!(flat.mark_items().next().unwrap().geometry, Geometry::Rule { x1: 11.0, .. }));
+    }
+
+    #[test]
+    fn test_flatten_keeps_the_transform_on_marks_it_cannot_bake() {
+        let rect = MarkItem::new(Geometry::Rect {
+            x: 0.0,
+            y: 0.0,
+            width: 10.0,
+            height: 4.0,
+            corner_radius: 0.0,
+        });
+        let path = MarkItem::new(Geometry::Path { d: "M0,0L10,0".to_string() });
+        let rotated = Transform {
+            rotate: 30.0,
+            ..Transform::translate(50.0, 50.0)
+        };
+        let mut group = Group::new().with_transform(rotated);
+        group.add_mark(Mark::new(MarkType::Rect, vec![rect.clone()]));
+        group.add_mark(Mark::new(MarkType::Rule, vec![rule(1.0)]));
+        let mut moved = Group::new().with_transform(Transform::translate(5.0, 0.0));
+        moved.add_mark(Mark::new(MarkType::Path, vec![path.clone()]));
+
+        let mut scene = Scene::new(100.0, 100.0);
+        scene.root.add_group(group);
+        scene.root.add_group(moved);
+
+        let flat = scene.flatten();
+        let [SceneNode::Group(kept_rect), SceneNode::Mark(rule), SceneNode::Group(kept_path)] =
+            flat.root.children.as_slice()
+        else {
+            panic!("expected the rect and path marks in groups of their own");
+        };
+        assert_eq!(kept_rect.transform.to_matrix(), rotated.to_matrix());
+        assert!(matches!(&kept_rect.children[..], [SceneNode::Mark(mark)] if mark.items == [rect.clone()]));
+        assert_eq!(kept_path.transform, Transform::translate(5.0, 0.0));
+        assert!(matches!(&kept_path.children[..], [SceneNode::Mark(mark)] if mark.items == [path.clone()]));
+
+        // The rule's end points are still baked in
+        let (x1, y1) = rotated.apply(1.0, 0.0);
+        assert!(matches!(rule.items[0].geometry, Geometry::Rule { x1: bx, y1: by, .. }
+            if (bx - x1).abs() < 1e-9 && (by - y1).abs() < 1e-9));
+    }
+
+    #[test]
+    fn test_compose_offsets_scenes_and_spans_their_extent() {
+        let mut left = Scene::new(100.0, 50.0).with_background(crate::ir::Color::rgb(255, 255, 255));
+        left.root.add_mark(Mark::new(MarkType::Rule, vec![rule(1.0)]));
+        let mut right = Scene::new(80.0, 120.0);
+        right.root.add_mark(Mark::new(MarkType::Rule, vec![rule(2.0)]));
+
+        let composed = Scene::compose(&[(left, 0.0, 0.0), (right, 110.0, 10.0)]);
+        assert_eq!((composed.width, composed.height), (190.0, 130.0));
+
+        let flat = composed.flatten();
+        let geometries: Vec<_> = flat.mark_items().map(|item| &item.geometry).collect();
+        assert!(matches!(geometries[0], Geometry::Rect { width: 100.0, height: 50.0, .. }));
+        assert!(matches!(geometries[1], Geometry::Rule { x1: 1.0, .. }));
+        assert!(matches!(geometries[2], Geometry::Rule { x1: 112.0, y1: 10.0, .. }));
+    }
+
+    #[test]
+    fn test_series_emphasis_mutes_other_series() {
+        let mut scene = crate::chart(
+            r#"{
+                "data": { "values": [
+                    { "x": "A", "y": 1, "s": "north" },
+                    { "x": "B", "y": 2, "s": "north" },
+                    { "x": "A", "y": 3, "s": "south" },
+                    { "x": "B", "y": 4, "s": "south" }
+                ] },
+                "mark": "line",
+                "encoding": { "x": { "field": "x" }, "y": { "field": "y" }, "color": { "field": "s" } }
+            }"#,
+        )
+        .unwrap();
+        scene.set_series_emphasis("s", "south");
+        scene.set_series_emphasis("s", "south");
+
+        for item in scene.mark_items().filter(|item| matches!(item.geometry, Geometry::Line { .. })) {
+            let expected = if item.datum.as_ref().unwrap()["s"] == "south" { 1.0 } else { 0.2 };
+            assert_eq!(item.opacity, expected);
+        }
+        // Axes and legend entries carry no datum and stay opaque
+        assert!(scene
+            .mark_items()
+            .filter(|item| item.datum.is_none())
+            .all(|item| item.opacity == 1.0));
+    }
+
+    #[test]
+    fn test_hide_series_drops_lines_and_legend_entries() {
+        let mut scene = crate::chart(
+            r#"{
+                "data": { "values": [
+                    { "x": "A", "y": 1, "s": "north" }, { "x": "B", "y": 2, "s": "north" },
+                    { "x": "A", "y": 3, "s": "south" }, { "x": "B", "y": 4, "s": "south" },
+                    { "x": "A", "y": 5, "s": "west" }, { "x": "B", "y": 6, "s": "west" }
+                ] },
+                "mark": "line",
+                "encoding": { "x": { "field": "x" }, "y": { "field": "y" }, "color": { "field": "s" } }
+            }"#,
+        )
+        .unwrap();
+        scene.hide_series("s", &["north"]);
+
+        let lines: Vec<_> = scene
+            .mark_items()
+            .filter(|item| matches!(item.geometry, Geometry::Line { .. }))
+            .map(|item| item.datum.as_ref().unwrap()["s"].as_str().unwrap())
+            .collect();
+        assert_eq!(lines.len(), 2);
+        assert!(!lines.contains(&"north"));
+
+        // "south" and "west" move up into the first two legend slots
+        let flat = scene.flatten();
+        let labels: Vec<(&str, f64)> = flat
+            .mark_items()
+            .filter_map(|item| match &item.geometry {
+                Geometry::Text { text, y, .. } if text == "south" || text == "west" || text == "north" => {
+                    Some((text.as_str(), *y))
+                }
+                _ => None,
+            })
+            .collect();
+        assert_eq!(labels.len(), 2);
+        assert_eq!(labels[1].1 - labels[0].1, 20.0);
+        assert!(labels[0].0 == "south" && labels[0].1 < 60.0);
+    }
+}
@@ -31,8 +31,13 @@ impl Color {
         }
     }
 
+    /// Parse `#rrggbb` or `#rrggbbaa` in either case, ignoring surrounding whitespace and
+    /// whitespace after the `#`
     pub fn from_hex(hex: &str) -> Option<Self> {
-        let hex = hex.trim_start_matches('#');
+        let hex = hex.trim().trim_start_matches('#').trim_start();
+        if !hex.is_ascii() {
+            return None;
+        }
         if hex.len() == 6 {
             let r = u8::from_str_radix(&hex[0..2], 16).ok()?;
             let g = u8::from_str_radix(&hex[2..4], 16).ok()?;
@@ -48,6 +53,73 @@ impl Color {
             None
         }
     }
+
+    /// Opaque color from hue in degrees (any value, wrapped to 0..360) and saturation and
+    /// lightness in 0..1 (clamped)
+    pub fn from_hsl(h: f64, s: f64, l: f64) -> Color {
+        let (s, l) = (s.clamp(0.0, 1.0), l.clamp(0.0, 1.0));
+        let h = h.rem_euclid(360.0) / 60.0;
+        let chroma = (1.0 - (2.0 * l - 1.0).abs()) * s;
+        let x = chroma * (1.0 - (h % 2.0 - 1.0).abs());
+        let (r, g, b) = match h as u32 {
+            0 => (chroma, x, 0.0),
+            1 => (x, chroma, 0.0),
+            2 => (0.0, chroma, x),
+            3 => (0.0, x, chroma),
+            4 => (x, 0.0, chroma),
+            _ => (chroma, 0.0, x),
+        };
+        let m = l - chroma / 2.0;
+        let channel = |v: f64| ((v + m) * 255.0).round() as u8;
+        Color::rgb(channel(r), channel(g), channel(b))
+    }
+
+    /// Hue in degrees (0..360), saturation and lightness (0..1); alpha is ignored
+    pub fn to_hsl(&self) -> (f64, f64, f64) {
+        let (r, g, b) = (self.r as f64 / 255.0, self.g as f64 / 255.0, self.b as f64 / 255.0);
+        let max = r.max(g).max(b);
+        let min = r.min(g).min(b);
+        let l = (max + min) / 2.0;
+        let delta = max - min;
+        if delta == 0.0 {
+            return (0.0, 0.0, l);
+        }
+
+        let s = delta / (1.0 - (2.0 * l - 1.0).abs());
+        let h = if max == r {
+            60.0 * ((g - b) / delta).rem_euclid(6.0)
+        } else if max == g {
+            60.0 * ((b - r) / delta + 2.0)
+        } else {
+            60.0 * ((r - g) / delta + 4.0)
+        };
+        (h, s, l)
+    }
+
+    /// Raise HSL lightness by `amount` (0..1, e.g. 0.1 for ten points), keeping hue,
+    /// saturation and alpha
+    pub fn lighten(&self, amount: f64) -> Color {
+        let (h, s, l) = self.to_hsl();
+        Color { a: self.a, ..Color::from_hsl(h, s, l + amount) }
+    }
+
+    /// Lower HSL lightness by `amount`; see [`Color::lighten`]
+    pub fn darken(&self, amount: f64) -> Color {
+        self.lighten(-amount)
+    }
+
+    /// Blend toward `other` channel by channel (alpha included): `t` of 0 is `self`, 1 is
+    /// `other`, and values outside that range are clamped
+    pub fn lerp(&self, other: &Color, t: f64) -> Color {
+        let t = t.clamp(0.0, 1.0);
+        let channel = |x: u8, y: u8| (x as f64 + (y as f64 - x as f64) * t).round() as u8;
+        Color::rgba(
+            channel(self.r, other.r),
+            channel(self.g, other.g),
+            channel(self.b, other.b),
+            channel(self.a, other.a),
+        )
+    }
 }
 
 impl Default for Color {
@@ -56,11 +128,98 @@ impl Default for Color {
     }
 }
 
+/// Paint used to fill a mark item
+///
+/// Serialized untagged, so a solid fill has the same JSON shape as a bare `Color`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum Fill {
+    Solid(Color),
+    LinearGradient {
+        stops: Vec<GradientStop>,
+        /// Direction in degrees, clockwise from left-to-right (90 is top-to-bottom)
+        #[serde(default)]
+        angle: f64,
+    },
+    Pattern {
+        pattern: PatternKind,
+        color: Color,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        background: Option<Color>,
+    },
+}
+
+impl Fill {
+    pub fn linear_gradient(stops: Vec<GradientStop>, angle: f64) -> Self {
+        Self::LinearGradient { stops, angle }
+    }
+
+    pub fn pattern(pattern: PatternKind, color: Color) -> Self {
+        Self::Pattern {
+            pattern,
+            color,
+            background: None,
+        }
+    }
+
+    /// The color of a solid fill, or `None` for gradients
+    pub fn solid_color(&self) -> Option<Color> {
+        match self {
+            Fill::Solid(color) => Some(*color),
+            Fill::LinearGradient { .. } | Fill::Pattern { .. } => None,
+        }
+    }
+}
+
+impl From<Color> for Fill {
+    fn from(color: Color) -> Self {
+        Self::Solid(color)
+    }
+}
+
+/// Hatch/dot pattern for print-safe fills
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PatternKind {
+    Diagonal,
+    Dots,
+    Crosshatch,
+    Horizontal,
+    Vertical,
+    /// Horizontal and vertical lines together, for sketch-style plot backgrounds
+    Grid,
+}
+
+/// Patterns in the order they are assigned to series
+pub const PATTERNS: &[PatternKind] = &[
+    PatternKind::Diagonal,
+    PatternKind::Dots,
+    PatternKind::Crosshatch,
+    PatternKind::Horizontal,
+    PatternKind::Vertical,
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct GradientStop {
+    /// Position along the gradient, 0.0 to 1.0
+    pub offset: f64,
+    pub color: Color,
+}
+
+impl GradientStop {
+    pub const fn new(offset: f64, color: Color) -> Self {
+        Self { offset, color }
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Stroke {
     pub color: Color,
     pub width: f64,
     pub dash: Option<Vec<f64>>,
+    /// Opacity of the stroke alone, independent of the item's opacity
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub opacity: Option<f64>,
 }
 
 impl Stroke {
@@ -69,6 +228,7 @@ impl Stroke {
             color,
             width,
             dash: None,
+            opacity: None,
         }
     }
 
@@ -77,8 +237,14 @@ impl Stroke {
             color,
             width,
             dash: Some(dash),
+            opacity: None,
         }
     }
+
+    pub fn with_opacity(mut self, opacity: f64) -> Self {
+        self.opacity = Some(opacity);
+        self
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
@@ -100,6 +266,57 @@ impl Default for Font {
     }
 }
 
+/// Average glyph advance as a fraction of the font size, for estimating text width
+const AVERAGE_CHAR_WIDTH: f64 = 0.6;
+
+impl Font {
+    /// Distance between baselines of consecutive lines
+    pub fn line_height(&self) -> f64 {
+        self.size * 1.2
+    }
+
+    /// Estimated rendered width of a single line of text
+    pub fn text_width(&self, text: &str) -> f64 {
+        text.chars().count() as f64 * self.size * AVERAGE_CHAR_WIDTH
+    }
+
+    /// Insert line breaks between words so no line is estimated wider than `max_width`.
+    /// Existing newlines are kept, and a single word longer than `max_width` gets its own line.
+    pub fn wrap(&self, text: &str, max_width: f64) -> String {
+        let mut lines = Vec::new();
+        for paragraph in text.split('\n') {
+            let mut line = String::new();
+            for word in paragraph.split_whitespace() {
+                if !line.is_empty() && self.text_width(&format!("{line} {word}")) > max_width {
+                    lines.push(std::mem::take(&mut line));
+                }
+                if !line.is_empty() {
+                    line.push(' ');
+                }
+                line.push_str(word);
+            }
+            lines.push(line);
+        }
+        lines.join("\n")
+    }
+
+    /// Cut `text` so it is estimated no wider than `max_width`, ending it with an ellipsis
+    pub fn truncate(&self, text: &str, max_width: f64) -> String {
+        if self.text_width(text) <= max_width {
+            return text.to_string();
+        }
+        let mut truncated: String = text
+            .chars()
+            .scan(String::new(), |prefix, c| {
+                prefix.push(c);
+                (self.text_width(prefix) + self.text_width("…") <= max_width).then_some(c)
+            })
+            .collect();
+        truncated.push('…');
+        truncated
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
 pub enum FontWeight {
@@ -114,31 +331,80 @@ pub enum FontStyle {
     Italic,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
 pub enum TextAnchor {
+    #[default]
     Start,
     Middle,
     End,
 }
 
-impl Default for TextAnchor {
-    fn default() -> Self {
-        Self::Start
-    }
-}
-
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
 pub enum TextBaseline {
     Top,
     Middle,
     Bottom,
+    #[default]
     Alphabetic,
 }
 
-impl Default for TextBaseline {
-    fn default() -> Self {
-        Self::Alphabetic
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hsl_round_trips_known_colors() {
+        let known = [
+            ((0.0, 1.0, 0.5), Color::rgb(255, 0, 0)),
+            ((120.0, 1.0, 0.25), Color::rgb(0, 128, 0)),
+            ((210.0, 0.5, 0.6), Color::rgb(102, 153, 204)),
+            ((300.0, 1.0, 0.5), Color::rgb(255, 0, 255)),
+            ((0.0, 0.0, 1.0), Color::rgb(255, 255, 255)),
+        ];
+        for ((h, s, l), color) in known {
+            assert_eq!(Color::from_hsl(h, s, l), color);
+            let (h2, s2, l2) = color.to_hsl();
+            assert!((h2 - h).abs() < 0.5 && (s2 - s).abs() < 0.01 && (l2 - l).abs() < 0.01, "{color:?}");
+        }
+        assert_eq!(Color::from_hsl(-240.0, 1.0, 0.5), Color::from_hsl(120.0, 1.0, 0.5));
+    }
+
+    #[test]
+    fn test_lighten_and_darken_shift_lightness_only() {
+        let blue = Color::rgba(102, 153, 204, 128);
+        assert_eq!(blue.darken(0.2), Color::rgba(51, 102, 153, 128));
+        assert_eq!(blue.lighten(0.2), Color::rgba(179, 204, 230, 128));
+        assert_eq!(blue.lighten(1.0), Color::rgba(255, 255, 255, 128));
+        assert_eq!(blue.darken(1.0), Color::rgba(0, 0, 0, 128));
+    }
+
+    #[test]
+    fn test_lerp_blends_every_channel_and_clamps() {
+        let from = Color::rgba(0, 100, 200, 0);
+        let to = Color::rgb(200, 100, 0);
+        assert_eq!(from.lerp(&to, 0.5), Color::rgba(100, 100, 100, 128));
+        assert_eq!(from.lerp(&to, -1.0), from);
+        assert_eq!(from.lerp(&to, 2.0), to);
+    }
+
+    #[test]
+    fn test_from_hex_ignores_whitespace_and_case() {
+        let orange = Some(Color::rgb(255, 170, 0));
+        assert_eq!(Color::from_hex(" #FFAA00 "), orange);
+        assert_eq!(Color::from_hex("\t#ffAa00\n"), orange);
+        assert_eq!(Color::from_hex("# ffaa00"), orange);
+        assert_eq!(Color::from_hex("FFAA0080"), Some(Color::rgba(255, 170, 0, 128)));
+        assert_eq!(Color::from_hex("#ffaa0"), None);
+        assert_eq!(Color::from_hex("#ffaa é"), None);
+    }
+
+    #[test]
+    fn test_truncate_fits_ellipsis_within_width() {
+        let font = Font { size: 10.0, ..Font::default() };
+        // 6px per character: "Very Long Category" is 108px
+        assert_eq!(font.truncate("Very Long Category", 120.0), "Very Long Category");
+        assert_eq!(font.truncate("Very Long Category", 60.0), "Very Long…");
     }
 }
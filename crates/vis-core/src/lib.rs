@@ -2,10 +2,18 @@ pub mod compile;
 pub mod ir;
 pub mod scale;
 pub mod spec;
+pub mod stats;
+pub mod template;
+pub mod theme;
+pub mod transform;
 
-pub use compile::compile;
+pub use compile::{
+    compile, compile_with_scales, compile_with_theme, data_extent, distinct_categories,
+    extract_categories,
+};
 pub use ir::Scene;
 pub use spec::ChartSpec;
+pub use theme::Theme;
 
 /// Parse a JSON chart specification
 pub fn parse_spec(json: &str) -> Result<ChartSpec, serde_json::Error> {
@@ -18,3 +26,22 @@ pub fn chart(json: &str) -> Result<Scene, Box<dyn std::error::Error>> {
     let scene = compile(&spec)?;
     Ok(scene)
 }
+
+/// Compile `json` with `patch` merged over it (objects key by key, other values replaced),
+/// so a test can compare variants of one spec
+#[cfg(test)]
+pub(crate) fn chart_with(json: &str, patch: serde_json::Value) -> Scene {
+    fn merge(base: &mut serde_json::Value, patch: serde_json::Value) {
+        match (base, patch) {
+            (serde_json::Value::Object(base), serde_json::Value::Object(patch)) => {
+                for (key, value) in patch {
+                    merge(base.entry(key).or_insert(serde_json::Value::Null), value);
+                }
+            }
+            (base, patch) => *base = patch,
+        }
+    }
+    let mut spec: serde_json::Value = serde_json::from_str(json).unwrap();
+    merge(&mut spec, patch);
+    compile(&serde_json::from_value(spec).unwrap()).unwrap()
+}
@@ -1,8 +1,15 @@
 use super::Tick;
 
+/// Largest inner padding; a padding of 1 would leave zero-width bands
+const MAX_PADDING_INNER: f64 = 0.99;
+
+/// Widest a lone band may be, as a fraction of the range, so a single category shows as a
+/// centered band rather than filling the range edge to edge
+const MAX_SINGLE_BAND_FRACTION: f64 = 0.5;
+
 /// Band scale for categorical data with width
 /// Used for bar charts where each category gets a band of space
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct BandScale {
     domain: Vec<String>,
     range_min: f64,
@@ -22,9 +29,10 @@ impl BandScale {
         }
     }
 
-    /// Set inner padding (between bands) as fraction of step
+    /// Set inner padding (between bands) as fraction of step.
+    /// Clamped below 1 so bands always keep a positive width.
     pub fn padding_inner(mut self, padding: f64) -> Self {
-        self.padding_inner = padding.clamp(0.0, 1.0);
+        self.padding_inner = padding.clamp(0.0, MAX_PADDING_INNER);
         self
     }
 
@@ -47,7 +55,17 @@ impl BandScale {
         }
 
         let range_span = (self.range_max - self.range_min).abs();
-        range_span / (n as f64 + self.padding_outer * 2.0 - self.padding_inner)
+        range_span / (n as f64 + self.outer_padding() * 2.0 - self.padding_inner)
+    }
+
+    /// Outer padding actually applied: as configured, except that a single band gets at
+    /// least enough to keep it within [`MAX_SINGLE_BAND_FRACTION`] of the range
+    fn outer_padding(&self) -> f64 {
+        if self.domain.len() != 1 {
+            return self.padding_outer;
+        }
+        let min_padding = (1.0 - self.padding_inner) * (1.0 / MAX_SINGLE_BAND_FRACTION - 1.0) / 2.0;
+        self.padding_outer.max(min_padding)
     }
 
     /// Get the bandwidth (just the band, without padding)
@@ -55,12 +73,29 @@ impl BandScale {
         self.step() * (1.0 - self.padding_inner)
     }
 
-    /// Map category to start position of band
+    /// Map category to start position of band: its lower edge, so the band spans
+    /// `start..start + bandwidth` whichever way the range runs
     pub fn scale(&self, value: &str) -> Option<f64> {
         let index = self.domain.iter().position(|v| v == value)?;
         let step = self.step();
-        let offset = self.padding_outer * step;
-        Some(self.range_min + offset + index as f64 * step)
+        let offset = self.outer_padding() * step;
+        Some(self.range_start() + offset + self.slot(index) as f64 * step)
+    }
+
+    /// Lower end of the range
+    fn range_start(&self) -> f64 {
+        self.range_min.min(self.range_max)
+    }
+
+    /// Position of the domain's `index`th band counting up from the lower end of the range;
+    /// a reversed range (`range_min > range_max`) lays the first band out at the upper end.
+    /// The mapping is its own inverse.
+    fn slot(&self, index: usize) -> usize {
+        if self.range_min > self.range_max {
+            self.domain.len() - 1 - index
+        } else {
+            index
+        }
     }
 
     /// Map category to center position of band
@@ -68,6 +103,32 @@ impl BandScale {
         self.scale(value).map(|start| start + self.bandwidth() / 2.0)
     }
 
+    /// Category whose band contains `pos`, or `None` in the padding between or around bands
+    pub fn invert(&self, pos: f64) -> Option<&str> {
+        let step = self.step();
+        if step == 0.0 {
+            return None;
+        }
+        let offset = pos - self.range_start() - self.outer_padding() * step;
+        if offset < 0.0 {
+            return None;
+        }
+        let slot = (offset / step).floor() as usize;
+        if slot >= self.domain.len() || offset - slot as f64 * step > self.bandwidth() {
+            return None;
+        }
+        Some(self.domain[self.slot(slot)].as_str())
+    }
+
+    /// Category whose band center is closest to `pos`; `None` only for an empty domain
+    pub fn invert_nearest(&self, pos: f64) -> Option<&str> {
+        self.domain
+            .iter()
+            .filter_map(|cat| Some((cat, (self.scale_center(cat)? - pos).abs())))
+            .min_by(|a, b| a.1.total_cmp(&b.1))
+            .map(|(cat, _)| cat.as_str())
+    }
+
     /// Get the domain
     pub fn domain(&self) -> &[String] {
         &self.domain
@@ -82,8 +143,7 @@ impl BandScale {
     pub fn ticks(&self) -> Vec<Tick> {
         self.domain
             .iter()
-            .enumerate()
-            .filter_map(|(_, cat)| {
+            .filter_map(|cat| {
                 let pos = self.scale_center(cat)?;
                 Some(Tick {
                     value: pos,
@@ -127,4 +187,53 @@ mod tests {
         assert!((scale.step() - 111.11).abs() < 0.1);
         assert!((scale.bandwidth() - 88.89).abs() < 0.1);
     }
+
+    #[test]
+    fn test_band_scale_invert() {
+        let scale = BandScale::new(
+            vec!["A".to_string(), "B".to_string()],
+            (0.0, 200.0),
+        )
+        .padding_inner(0.2)
+        .padding_outer(0.0);
+
+        assert_eq!(scale.invert(10.0), Some("A"));
+        assert_eq!(scale.invert(150.0), Some("B"));
+        // Gap between A (ends at 88.9) and B (starts at 111.1)
+        assert_eq!(scale.invert(100.0), None);
+        assert_eq!(scale.invert(250.0), None);
+        assert_eq!(scale.invert_nearest(105.0), Some("B"));
+        assert_eq!(scale.invert_nearest(-50.0), Some("A"));
+    }
+
+    #[test]
+    fn test_reversed_range_lays_bands_out_from_the_end() {
+        let domain = vec!["A".to_string(), "B".to_string(), "C".to_string()];
+        let forward = BandScale::new(domain.clone(), (0.0, 300.0)).padding(0.1);
+        let reversed = BandScale::new(domain, (300.0, 0.0)).padding(0.1);
+
+        assert_eq!(reversed.bandwidth(), forward.bandwidth());
+        for (category, mirrored) in [("A", "C"), ("B", "B"), ("C", "A")] {
+            let start = reversed.scale(category).unwrap();
+            assert!((start - forward.scale(mirrored).unwrap()).abs() < 1e-9);
+            assert_eq!(reversed.invert(start + 1.0), Some(category));
+        }
+        assert!(reversed.scale_center("A").unwrap() > reversed.scale_center("B").unwrap());
+        assert_eq!(reversed.invert(299.0), None);
+    }
+
+    #[test]
+    fn test_single_band_is_centered_with_padding() {
+        for (inner, outer) in [(0.0, 0.0), (0.2, 0.2), (0.99, 0.0), (0.0, 0.8)] {
+            let scale = BandScale::new(vec!["A".to_string()], (0.0, 300.0))
+                .padding_inner(inner)
+                .padding_outer(outer);
+            let start = scale.scale("A").unwrap();
+            let width = scale.bandwidth();
+
+            assert!(width > 0.0 && width <= 150.0, "{inner}/{outer}: width {width}");
+            assert!((start + width / 2.0 - 150.0).abs() < 1e-9, "{inner}/{outer}: start {start}");
+            assert_eq!(scale.invert(150.0), Some("A"));
+        }
+    }
 }
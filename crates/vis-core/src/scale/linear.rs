@@ -1,7 +1,7 @@
-use super::{format_number, nice_ticks, Tick};
+use super::{format_number_with, nice_step, nice_ticks, NumberLocale, Tick};
 
 /// Linear scale for quantitative data
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct LinearScale {
     domain_min: f64,
     domain_max: f64,
@@ -21,16 +21,26 @@ impl LinearScale {
         }
     }
 
-    /// Create scale with nice domain boundaries
+    /// Create scale with nice domain boundaries, extending the domain outward
     pub fn nice(mut self) -> Self {
-        let ticks = nice_ticks(self.domain_min, self.domain_max, 10);
-        if let (Some(&first), Some(&last)) = (ticks.first(), ticks.last()) {
-            self.domain_min = first;
-            self.domain_max = last;
+        if self.domain_min >= self.domain_max {
+            return self;
         }
+        let step = nice_step(self.domain_min, self.domain_max, 10);
+        self.domain_min = (self.domain_min / step).floor() * step;
+        self.domain_max = (self.domain_max / step).ceil() * step;
         self
     }
 
+    /// [`LinearScale::nice`] when `nice` is set, otherwise keep the domain as given
+    pub fn nice_if(self, nice: bool) -> Self {
+        if nice {
+            self.nice()
+        } else {
+            self
+        }
+    }
+
     /// Include zero in the domain
     pub fn zero(mut self) -> Self {
         if self.domain_min > 0.0 {
@@ -78,11 +88,29 @@ impl LinearScale {
 
     /// Generate tick values
     pub fn ticks(&self, count: usize) -> Vec<Tick> {
+        self.ticks_with(count, &NumberLocale::default())
+    }
+
+    /// Generate tick values labeled with `locale`'s separators
+    pub fn ticks_with(&self, count: usize, locale: &NumberLocale) -> Vec<Tick> {
         nice_ticks(self.domain_min, self.domain_max, count)
             .into_iter()
             .map(|value| Tick {
                 value,
-                label: format_number(value),
+                label: format_number_with(value, locale),
+            })
+            .collect()
+    }
+
+    /// Ticks exactly at `values`, dropping any outside the domain
+    pub fn ticks_at(&self, values: &[f64], locale: &NumberLocale) -> Vec<Tick> {
+        let (low, high) = (self.domain_min.min(self.domain_max), self.domain_min.max(self.domain_max));
+        values
+            .iter()
+            .filter(|value| (low..=high).contains(*value))
+            .map(|&value| Tick {
+                value,
+                label: format_number_with(value, locale),
             })
             .collect()
     }
@@ -118,9 +146,51 @@ mod tests {
         assert_eq!(scale.invert(500.0), 100.0);
     }
 
+    #[test]
+    fn test_linear_scale_nice_extends_domain() {
+        let scale = LinearScale::new((2.0, 91.0), (0.0, 500.0)).nice();
+        assert_eq!(scale.domain(), (0.0, 100.0));
+    }
+
     #[test]
     fn test_linear_scale_zero() {
         let scale = LinearScale::new((10.0, 100.0), (0.0, 500.0)).zero();
         assert_eq!(scale.domain(), (0.0, 100.0));
     }
+
+    #[test]
+    fn test_ticks_with_locale_separators() {
+        let locale = NumberLocale { decimal: ',', grouping: Some(' '), ..NumberLocale::default() };
+        let labels: Vec<_> = LinearScale::new((0.0, 1.0), (0.0, 100.0))
+            .ticks_with(5, &locale)
+            .into_iter()
+            .map(|t| t.label)
+            .collect();
+        assert!(labels.contains(&"0,20".to_string()));
+        assert_eq!(format_number_with(-1_500_000_000.0, &locale), "-1 500,0M");
+    }
+
+    #[test]
+    fn test_ticks_with_locale_precision() {
+        let locale = NumberLocale { precision: Some(3), ..NumberLocale::default() };
+        let labels: Vec<_> = LinearScale::new((0.0, 1.0), (0.0, 100.0))
+            .ticks_with(5, &locale)
+            .into_iter()
+            .map(|t| t.label)
+            .collect();
+        assert_eq!(labels, ["0", "0.200", "0.400", "0.600", "0.800", "1"]);
+        assert_eq!(format_number_with(12_345.0, &locale), "12.3K");
+        assert_eq!(format_number_with(1.5, &locale), "1.50");
+        assert_eq!(format_number_with(1.5, &NumberLocale::default()), "1.50");
+        assert_eq!(format_number_with(1_500.0, &NumberLocale::default()), "1.5K");
+    }
+
+    #[test]
+    fn test_ticks_at_drops_values_outside_domain() {
+        let ticks = LinearScale::new((-10.0, 100.0), (0.0, 500.0))
+            .ticks_at(&[-40.0, 0.0, 32.0, 100.0, 212.0], &NumberLocale::default());
+        let values: Vec<f64> = ticks.iter().map(|t| t.value).collect();
+        assert_eq!(values, vec![0.0, 32.0, 100.0]);
+        assert_eq!(ticks[1].label, "32");
+    }
 }
@@ -0,0 +1,134 @@
+use super::{format_log, nice_ticks, NumberLocale, Tick};
+
+/// Base-10 logarithmic scale for positive quantitative data. Domain bounds must be positive.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LogScale {
+    domain_min: f64,
+    domain_max: f64,
+    range_min: f64,
+    range_max: f64,
+}
+
+impl LogScale {
+    pub fn new(domain: (f64, f64), range: (f64, f64)) -> Self {
+        Self {
+            domain_min: domain.0,
+            domain_max: domain.1,
+            range_min: range.0,
+            range_max: range.1,
+        }
+    }
+
+    /// Extend the domain outward to whole powers of ten
+    pub fn nice(mut self) -> Self {
+        if self.domain_min > 0.0 && self.domain_max > 0.0 {
+            self.domain_min = 10_f64.powf(self.domain_min.log10().floor());
+            self.domain_max = 10_f64.powf(self.domain_max.log10().ceil());
+        }
+        self
+    }
+
+    /// Map domain value to range value; non-positive values map to the start of the range
+    pub fn scale(&self, value: f64) -> f64 {
+        let (lo, hi) = (self.domain_min.log10(), self.domain_max.log10());
+        if value <= 0.0 || hi == lo || !lo.is_finite() {
+            return self.range_min;
+        }
+        let t = (value.log10() - lo) / (hi - lo);
+        self.range_min + t * (self.range_max - self.range_min)
+    }
+
+    /// Map range value back to domain value
+    pub fn invert(&self, value: f64) -> f64 {
+        let range_span = self.range_max - self.range_min;
+        if range_span == 0.0 {
+            return self.domain_min;
+        }
+        let (lo, hi) = (self.domain_min.log10(), self.domain_max.log10());
+        let t = (value - self.range_min) / range_span;
+        10_f64.powf(lo + t * (hi - lo))
+    }
+
+    /// One tick per power of ten in the domain, or linear ticks when the domain spans less
+    /// than a decade
+    pub fn ticks(&self) -> Vec<Tick> {
+        self.ticks_with(&NumberLocale::default())
+    }
+
+    /// [`LogScale::ticks`] labeled with `locale`'s separators
+    pub fn ticks_with(&self, locale: &NumberLocale) -> Vec<Tick> {
+        if self.domain_min <= 0.0 || self.domain_min >= self.domain_max {
+            return vec![];
+        }
+        let first = self.domain_min.log10().ceil() as i32;
+        let last = self.domain_max.log10().floor() as i32;
+        let values: Vec<f64> = if last > first {
+            (first..=last).map(|exp| 10_f64.powi(exp)).collect()
+        } else {
+            nice_ticks(self.domain_min, self.domain_max, 5)
+        };
+        values
+            .into_iter()
+            .map(|value| Tick {
+                value,
+                label: locale.apply(&format_log(value)),
+            })
+            .collect()
+    }
+
+    /// Ticks exactly at `values`, dropping any outside the domain
+    pub fn ticks_at(&self, values: &[f64], locale: &NumberLocale) -> Vec<Tick> {
+        values
+            .iter()
+            .filter(|value| (self.domain_min..=self.domain_max).contains(*value))
+            .map(|&value| Tick {
+                value,
+                label: locale.apply(&format_log(value)),
+            })
+            .collect()
+    }
+
+    /// Get domain
+    pub fn domain(&self) -> (f64, f64) {
+        (self.domain_min, self.domain_max)
+    }
+
+    /// Get range
+    pub fn range(&self) -> (f64, f64) {
+        (self.range_min, self.range_max)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_log_scale_ticks_at_powers_of_ten() {
+        let scale = LogScale::new((3.0, 2_500_000.0), (0.0, 600.0)).nice();
+        assert_eq!(scale.domain(), (1.0, 10_000_000.0));
+        assert!((scale.scale(1000.0) - 257.14).abs() < 0.01);
+        assert!((scale.invert(300.0) - 3162.28).abs() < 0.01);
+
+        let labels: Vec<_> = scale.ticks().into_iter().map(|t| t.label).collect();
+        assert_eq!(labels, ["1", "10", "100", "1K", "10K", "100K", "1M", "10M"]);
+
+        let small = LogScale::new((0.001, 1.0), (0.0, 100.0));
+        let labels: Vec<_> = small.ticks().into_iter().map(|t| t.label).collect();
+        assert_eq!(labels, ["0.001", "0.01", "0.1", "1"]);
+    }
+
+    #[test]
+    fn test_log_scale_ticks_use_the_locale() {
+        let scale = LogScale::new((0.01, 100.0), (0.0, 100.0));
+        let locale = NumberLocale {
+            decimal: ',',
+            ..NumberLocale::default()
+        };
+        let labels: Vec<_> = scale.ticks_with(&locale).into_iter().map(|t| t.label).collect();
+        assert_eq!(labels, ["0,01", "0,1", "1", "10", "100"]);
+
+        let labels: Vec<_> = scale.ticks_at(&[0.5, 50.0, 500.0], &locale).into_iter().map(|t| t.label).collect();
+        assert_eq!(labels, ["0,5", "50"]);
+    }
+}
@@ -1,10 +1,15 @@
 mod band;
 mod linear;
+mod log;
+mod sequential;
 
 pub use band::BandScale;
 pub use linear::LinearScale;
+pub use log::LogScale;
+pub use sequential::SequentialScale;
 
-use serde_json::Value;
+use serde::{Deserialize, Serialize};
+use serde_json::{Number, Value};
 
 /// Tick mark for axis rendering
 #[derive(Debug, Clone)]
@@ -13,6 +18,18 @@ pub struct Tick {
     pub label: String,
 }
 
+/// Look up `field` in a data row, following dotted paths like `"geo.country"` into nested
+/// objects. A key that itself contains dots is matched as-is first.
+pub fn get_path<'a>(row: &'a Value, field: &str) -> Option<&'a Value> {
+    if let Some(value) = row.get(field) {
+        return Some(value);
+    }
+    if !field.contains('.') {
+        return None;
+    }
+    field.split('.').try_fold(row, |value, key| value.get(key))
+}
+
 /// Extract numeric value from JSON
 pub fn value_to_f64(value: &Value) -> Option<f64> {
     match value {
@@ -26,27 +43,51 @@ pub fn value_to_f64(value: &Value) -> Option<f64> {
 pub fn value_to_string(value: &Value) -> String {
     match value {
         Value::String(s) => s.clone(),
-        Value::Number(n) => n.to_string(),
+        Value::Number(n) => number_to_string(n),
         Value::Bool(b) => b.to_string(),
         Value::Null => "null".to_string(),
         _ => value.to_string(),
     }
 }
 
+/// Display form of a JSON number used as a category: whole numbers without a decimal
+/// (`2020`, even when the JSON held `2020.0`), others as written
+pub fn number_to_string(n: &Number) -> String {
+    match n.as_f64() {
+        Some(v) if n.is_f64() && v.fract() == 0.0 && v.abs() < 1e15 => format!("{v:.0}"),
+        _ => n.to_string(),
+    }
+}
+
 /// Compute nice tick values for a numeric range
 pub fn nice_ticks(min: f64, max: f64, count: usize) -> Vec<f64> {
     if count == 0 || min >= max {
         return vec![];
     }
 
+    let nice_step = nice_step(min, max, count);
+
+    // Generate ticks
+    let start = (min / nice_step).ceil() * nice_step;
+    let mut ticks = Vec::new();
+    let mut tick = start;
+    while tick <= max + nice_step * 0.001 {
+        ticks.push(tick);
+        tick += nice_step;
+    }
+
+    ticks
+}
+
+/// Nice step size (1, 2, 5, 10, 20, 50, etc.) for roughly `count` ticks over a range
+pub fn nice_step(min: f64, max: f64, count: usize) -> f64 {
     let range = max - min;
     let rough_step = range / count as f64;
 
-    // Find a nice step size (1, 2, 5, 10, 20, 50, etc.)
     let magnitude = 10_f64.powf(rough_step.log10().floor());
     let residual = rough_step / magnitude;
 
-    let nice_step = if residual <= 1.5 {
+    if residual <= 1.5 {
         magnitude
     } else if residual <= 3.0 {
         2.0 * magnitude
@@ -54,29 +95,125 @@ pub fn nice_ticks(min: f64, max: f64, count: usize) -> Vec<f64> {
         5.0 * magnitude
     } else {
         10.0 * magnitude
-    };
+    }
+}
 
-    // Generate ticks
-    let start = (min / nice_step).ceil() * nice_step;
-    let mut ticks = Vec::new();
-    let mut tick = start;
-    while tick <= max + nice_step * 0.001 {
-        ticks.push(tick);
-        tick += nice_step;
+/// Separators and precision for formatted numbers, e.g. `{"decimal": ",", "grouping": " "}`
+/// for `1 234,5M`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NumberLocale {
+    /// Decimal separator (default `.`)
+    #[serde(default = "default_decimal")]
+    pub decimal: char,
+    /// Separator between groups of three integer digits (default none)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub grouping: Option<char>,
+    /// Significant digits for fractional and abbreviated (`K`, `M`) values; whole numbers
+    /// below a thousand keep no decimals (default: two decimals, one after `K` and `M`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub precision: Option<usize>,
+}
+
+impl Default for NumberLocale {
+    fn default() -> Self {
+        Self {
+            decimal: default_decimal(),
+            grouping: None,
+            precision: None,
+        }
     }
+}
 
-    ticks
+fn default_decimal() -> char {
+    '.'
+}
+
+impl NumberLocale {
+    /// Rewrite a number formatted with a `.` decimal point and no grouping
+    pub(crate) fn apply(&self, formatted: &str) -> String {
+        let (sign, unsigned) = match formatted.strip_prefix('-') {
+            Some(rest) => ("-", rest),
+            None => ("", formatted),
+        };
+        let digits_end = unsigned.find(|c: char| !c.is_ascii_digit()).unwrap_or(unsigned.len());
+        let (integer, rest) = unsigned.split_at(digits_end);
+
+        let mut out = String::from(sign);
+        for (i, digit) in integer.chars().enumerate() {
+            if let Some(separator) = self.grouping {
+                if i > 0 && (integer.len() - i) % 3 == 0 {
+                    out.push(separator);
+                }
+            }
+            out.push(digit);
+        }
+        match rest.strip_prefix('.') {
+            Some(fraction) => {
+                out.push(self.decimal);
+                out.push_str(fraction);
+            }
+            None => out.push_str(rest),
+        }
+        out
+    }
 }
 
 /// Format a numeric value for display
 pub fn format_number(value: f64) -> String {
-    if value.abs() >= 1_000_000.0 {
-        format!("{:.1}M", value / 1_000_000.0)
+    format_number_precision(value, None)
+}
+
+/// [`format_number`] rounded to `precision` significant digits instead of the default
+/// decimals (`1.23`, `12.3K`, `0.0450` for 3); whole numbers below a thousand stay whole
+pub fn format_number_precision(value: f64, precision: Option<usize>) -> String {
+    let (scaled, suffix, default_decimals) = if value.abs() >= 1_000_000.0 {
+        (value / 1_000_000.0, "M", 1)
     } else if value.abs() >= 1_000.0 {
-        format!("{:.1}K", value / 1_000.0)
+        (value / 1_000.0, "K", 1)
     } else if value.fract() == 0.0 {
-        format!("{:.0}", value)
+        (value, "", 0)
     } else {
-        format!("{:.2}", value)
-    }
+        (value, "", 2)
+    };
+    let decimals = match precision {
+        Some(precision) if default_decimals > 0 => {
+            let integer_digits = scaled.abs().log10().floor() as i64 + 1;
+            (precision as i64 - integer_digits).max(0) as usize
+        }
+        _ => default_decimals,
+    };
+    format!("{scaled:.decimals$}{suffix}")
+}
+
+/// [`format_number`] with `locale`'s separators and precision
+pub fn format_number_with(value: f64, locale: &NumberLocale) -> String {
+    locale.apply(&format_number_precision(value, locale.precision))
+}
+
+/// Format a log-scale tick compactly: `10`, `1K`, `1M`, `1B`, and values below 1 with just
+/// enough decimals (`0.01`), without the `.0` that `format_number` gives clean powers of ten
+pub fn format_log(value: f64) -> String {
+    let abs = value.abs();
+    let (scaled, suffix) = if abs >= 1e9 {
+        (value / 1e9, "B")
+    } else if abs >= 1e6 {
+        (value / 1e6, "M")
+    } else if abs >= 1e3 {
+        (value / 1e3, "K")
+    } else {
+        (value, "")
+    };
+
+    let decimals = if scaled != 0.0 && scaled.abs() < 1.0 {
+        (-scaled.abs().log10()).ceil() as usize
+    } else {
+        1
+    };
+    let formatted = format!("{:.*}", decimals, scaled);
+    let trimmed = if formatted.contains('.') {
+        formatted.trim_end_matches('0').trim_end_matches('.')
+    } else {
+        &formatted
+    };
+    format!("{trimmed}{suffix}")
 }
@@ -0,0 +1,82 @@
+use super::LogScale;
+use crate::ir::Color;
+
+/// Sequential color scale mapping a numeric domain onto a two-color ramp
+#[derive(Debug, Clone, PartialEq)]
+pub struct SequentialScale {
+    domain_min: f64,
+    domain_max: f64,
+    low: Color,
+    high: Color,
+    log: bool,
+}
+
+impl SequentialScale {
+    pub fn new(domain: (f64, f64), range: (Color, Color)) -> Self {
+        Self {
+            domain_min: domain.0,
+            domain_max: domain.1,
+            low: range.0,
+            high: range.1,
+            log: false,
+        }
+    }
+
+    /// Interpolate in log space, so colors spread evenly across orders of magnitude.
+    /// The domain should be positive; values at or below zero get the low color.
+    pub fn log(mut self) -> Self {
+        self.log = true;
+        self
+    }
+
+    /// Whether the scale interpolates in log space
+    pub fn is_log(&self) -> bool {
+        self.log
+    }
+
+    /// Map a domain value to a color, clamping outside the domain
+    pub fn color(&self, value: f64) -> Color {
+        let t = if self.log {
+            LogScale::new(self.domain(), (0.0, 1.0)).scale(value)
+        } else {
+            let span = self.domain_max - self.domain_min;
+            if span == 0.0 {
+                0.0
+            } else {
+                (value - self.domain_min) / span
+            }
+        };
+        self.low.lerp(&self.high, t.clamp(0.0, 1.0))
+    }
+
+    /// Get domain
+    pub fn domain(&self) -> (f64, f64) {
+        (self.domain_min, self.domain_max)
+    }
+
+    /// Get the color ramp endpoints
+    pub fn range(&self) -> (Color, Color) {
+        (self.low, self.high)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sequential_scale() {
+        let scale = SequentialScale::new((0.0, 10.0), (Color::rgb(0, 0, 0), Color::rgb(200, 100, 50)));
+        assert_eq!(scale.color(0.0), Color::rgb(0, 0, 0));
+        assert_eq!(scale.color(5.0), Color::rgb(100, 50, 25));
+        assert_eq!(scale.color(20.0), Color::rgb(200, 100, 50));
+    }
+
+    #[test]
+    fn test_log_sequential_scale() {
+        let scale = SequentialScale::new((1.0, 10_000.0), (Color::rgb(0, 0, 0), Color::rgb(200, 100, 40))).log();
+        assert_eq!(scale.color(100.0), Color::rgb(100, 50, 20));
+        assert_eq!(scale.color(0.0), Color::rgb(0, 0, 0));
+        assert_eq!(scale.color(-5.0), Color::rgb(0, 0, 0));
+    }
+}
@@ -2,7 +2,9 @@ use serde::{Deserialize, Serialize};
 use serde_json::Value;
 
 use super::encoding::Encoding;
-use crate::ir::Padding;
+use super::transform::TransformSpec;
+use crate::ir::{Padding, PatternKind};
+use crate::scale::NumberLocale;
 
 /// Top-level chart specification
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -19,8 +21,18 @@ pub struct ChartSpec {
     /// Background color (CSS color string)
     #[serde(skip_serializing_if = "Option::is_none")]
     pub background: Option<String>,
+    /// Styling of the plot area, as opposed to the whole canvas, or `"fullbleed"` for
+    /// marks that fill the canvas with no padding, axes or legend
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub view: Option<ViewSpec>,
+    /// Placement and wrapping of the color legend
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub legend: Option<LegendConfig>,
     /// Data source
     pub data: DataSpec,
+    /// Transforms applied to the data, in order
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub transform: Option<Vec<TransformSpec>>,
     /// Mark type for single-layer charts
     #[serde(skip_serializing_if = "Option::is_none")]
     pub mark: Option<MarkSpec>,
@@ -33,9 +45,21 @@ pub struct ChartSpec {
     /// Stacking configuration
     #[serde(skip_serializing_if = "Option::is_none")]
     pub stack: Option<StackConfig>,
+    /// Whether layers share scales
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub resolve: Option<Resolve>,
     /// Title
     #[serde(skip_serializing_if = "Option::is_none")]
     pub title: Option<String>,
+    /// Decimal and grouping separators for numbers in axis and value labels
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub locale: Option<NumberLocale>,
+    /// Lay text out right to left
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rtl: Option<bool>,
+    /// Small attribution text in a corner of the canvas
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub watermark: Option<WatermarkSpec>,
 }
 
 fn default_width() -> f64 {
@@ -80,7 +104,7 @@ pub enum MarkSpec {
         #[serde(rename = "type")]
         mark_type: MarkType,
         #[serde(flatten)]
-        config: MarkConfig,
+        config: Box<MarkConfig>,
     },
 }
 
@@ -91,6 +115,13 @@ impl MarkSpec {
             MarkSpec::WithConfig { mark_type, .. } => *mark_type,
         }
     }
+
+    pub fn config(&self) -> Option<&MarkConfig> {
+        match self {
+            MarkSpec::Simple(_) => None,
+            MarkSpec::WithConfig { config, .. } => Some(config),
+        }
+    }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
@@ -103,6 +134,13 @@ pub enum MarkType {
     Rule,
     Text,
     Rect,
+    /// Pie wedges sized by the `theta` channel
+    #[serde(alias = "pie")]
+    Arc,
+    /// One large number: the `text` field reduced to a single value, for KPI tiles
+    Metric,
+    /// Lines through each `color` series' rank by `y` at every `x`, labeled at their ends
+    Bump,
     // Composite marks (expand during compilation)
     Boxplot,
     Bullet,
@@ -110,10 +148,16 @@ pub enum MarkType {
 }
 
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
 pub struct MarkConfig {
-    /// Default fill color
+    /// Default fill color; on line marks, `true` also fills beneath each line down to its
+    /// baseline, in the line's color
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub fill: Option<String>,
+    pub fill: Option<MarkFill>,
+    /// Area marks and filled lines: opacity of the fill (default 0.7 for areas, 0.3 for
+    /// filled lines)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fill_opacity: Option<f64>,
     /// Default stroke color
     #[serde(skip_serializing_if = "Option::is_none")]
     pub stroke: Option<String>,
@@ -126,6 +170,150 @@ pub struct MarkConfig {
     /// Corner radius for rect/bar marks
     #[serde(skip_serializing_if = "Option::is_none")]
     pub corner_radius: Option<f64>,
+    /// Symbol area in square pixels for point marks without a size encoding (default 64)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub size: Option<f64>,
+    /// Bar marks only: minimum pixel length for nonzero bars, so tiny values stay visible
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub min_bar_length: Option<f64>,
+    /// Bar marks only: without a color field, give each category its own palette color
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub color_by_category: Option<bool>,
+    /// Bar marks only: snap bar edges to whole canvas pixels, so neighbouring bars and
+    /// stacked segments meet without hairline gaps or overlaps
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub round: Option<bool>,
+    /// Stacked bars only: outline color separating segments, or `"none"` (default: the
+    /// background color)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stack_stroke: Option<String>,
+    /// Stacked bars only: label each stack with the sum of its values, just past its end
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub total_label: Option<bool>,
+    /// Fill series with hatch/dot patterns (in the series color) for print
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub patterns: Option<bool>,
+    /// What to do with rows whose quantitative fields are null, missing or not numbers
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub invalid: Option<InvalidMode>,
+    /// Tooltip template filled from each datum, e.g. `"{category}: {value}"`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tooltip: Option<String>,
+    /// Draw a soft drop shadow under the data marks (bars, points, lines, wedges)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub shadow: Option<bool>,
+    /// Arc marks: wedge label template, e.g. `"{category}: {__percent}"`; metric marks:
+    /// caption above the number
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub label: Option<String>,
+    /// Metric marks only: earlier or target value the number is compared with; the change
+    /// shows below the number
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub comparison: Option<f64>,
+    /// Arc marks only: where the first wedge starts, in degrees clockwise from 12 o'clock
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub start_angle: Option<f64>,
+    /// Arc marks only: lay wedges out clockwise (default) or counterclockwise
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub clockwise: Option<bool>,
+    /// Draw order for mark items
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sort: Option<MarkSort>,
+    /// Band padding for bars as a fraction of the step, inner and outer (default 0.2)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub bar_padding: Option<f64>,
+    /// Padding between bars, overrides `barPadding`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub padding_inner: Option<f64>,
+    /// Bar marks only: padding between the bars of a group (bars with a color field but no
+    /// stacking) as a fraction of each bar's step (default 0.1)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub group_padding: Option<f64>,
+    /// Padding before the first and after the last bar, overrides `barPadding`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub padding_outer: Option<f64>,
+    /// Line marks only: draw just the line filling the whole canvas, with no padding,
+    /// axes or legend
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sparkline: Option<bool>,
+    /// Line marks only: where categorical x positions sit
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub align: Option<LineAlign>,
+    /// Line marks only: `false` breaks unstacked lines where an x value has no data
+    /// instead of joining across the gap (default `true`)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub connect_nulls: Option<bool>,
+    /// Line marks only: how points are joined; steps also apply to stacked area baselines
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub interpolate: Option<Interpolate>,
+    /// Line marks only: tightness of `catmull-rom` curves from 0 (the standard spline) to 1
+    /// (straight segments)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tension: Option<f64>,
+    /// Area marks and filled lines: constant value unstacked areas fill down (or up) to
+    /// when `y2` is not encoded (default 0)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub baseline: Option<f64>,
+    /// Point marks only: spread points across a categorical x band by a per-row offset
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub jitter: Option<bool>,
+}
+
+/// A mark's `fill` value: a color, or whether a line mark fills beneath itself
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum MarkFill {
+    Enabled(bool),
+    Color(String),
+}
+
+impl MarkFill {
+    /// Whether a line mark should fill the area beneath it
+    pub fn fills_line(&self) -> bool {
+        matches!(self, MarkFill::Enabled(true))
+    }
+}
+
+/// Placement of categorical x positions for line marks
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LineAlign {
+    /// Centered in equal bands, leaving half a band at each edge
+    Center,
+    /// First and last points flush with the plot edges
+    Edge,
+}
+
+/// Handling of invalid values in quantitative fields
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum InvalidMode {
+    /// Leave the row out of that channel (lines join across it unless `connectNulls` is false)
+    Filter,
+    /// Treat the value as 0
+    Zero,
+}
+
+/// How line marks join consecutive points
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Interpolate {
+    /// Straight segments between points
+    Linear,
+    /// Change to the next value at the current point's x
+    StepBefore,
+    /// Hold each value until the next point's x
+    StepAfter,
+    /// Smooth cardinal spline through every point, tightened by `tension`
+    CatmullRom,
+}
+
+/// Draw order for mark items within a mark
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum MarkSort {
+    /// Largest symbols first, so small points aren't hidden underneath
+    SizeDescending,
 }
 
 /// Layer specification for multi-layer charts
@@ -138,6 +326,164 @@ pub struct LayerSpec {
     /// Optional layer-specific data
     #[serde(skip_serializing_if = "Option::is_none")]
     pub data: Option<DataSpec>,
+    /// Draw order; higher values draw on top (defaults to 0, ties keep spec order)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub z: Option<i32>,
+    /// Opacity of the whole layer, so a context layer can sit faded under a focus layer
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub opacity: Option<f64>,
+}
+
+/// Plot area styling, or a view mode
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum ViewSpec {
+    Mode(ViewMode),
+    WithConfig(ViewConfig),
+}
+
+impl ViewSpec {
+    pub fn fill(&self) -> Option<&str> {
+        match self {
+            ViewSpec::Mode(_) => None,
+            ViewSpec::WithConfig(config) => config.fill.as_deref(),
+        }
+    }
+
+    pub fn pattern(&self) -> Option<PatternKind> {
+        match self {
+            ViewSpec::Mode(_) => None,
+            ViewSpec::WithConfig(config) => config.pattern,
+        }
+    }
+
+    /// Whether the marks fill the whole canvas, ignoring padding and drawing no axes,
+    /// gridlines or legend
+    pub fn is_fullbleed(&self) -> bool {
+        matches!(self, ViewSpec::Mode(ViewMode::Fullbleed))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ViewMode {
+    /// Data marks only, edge to edge, e.g. for background or hero charts
+    Fullbleed,
+}
+
+/// Plot area styling
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ViewConfig {
+    /// Hex color drawn behind the marks and gridlines, inside the padding
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fill: Option<String>,
+    /// Faint dot or line pattern tiled across the plot area beneath everything else,
+    /// over `fill` when both are set
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pattern: Option<PatternKind>,
+}
+
+/// Color legend placement
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+pub struct LegendConfig {
+    /// Side of the plot the legend sits on; the plot area shrinks on that side
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub orient: Option<LegendOrient>,
+    /// Categorical entries per row before wrapping (default 1 on the left or right, all of
+    /// them on the top or bottom)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub columns: Option<usize>,
+    /// Longest categorical label in pixels; longer ones are cut with an ellipsis
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub label_limit: Option<f64>,
+}
+
+/// Side of the plot area a legend is placed on
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LegendOrient {
+    Top,
+    Bottom,
+    Left,
+    #[default]
+    Right,
+}
+
+/// Watermark text, or text with placement
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum WatermarkSpec {
+    Text(String),
+    WithConfig(WatermarkConfig),
+}
+
+impl WatermarkSpec {
+    pub fn text(&self) -> &str {
+        match self {
+            WatermarkSpec::Text(text) => text,
+            WatermarkSpec::WithConfig(config) => &config.text,
+        }
+    }
+
+    pub fn position(&self) -> WatermarkPosition {
+        match self {
+            WatermarkSpec::Text(_) => WatermarkPosition::default(),
+            WatermarkSpec::WithConfig(config) => config.position.unwrap_or_default(),
+        }
+    }
+
+    /// Opacity of the text (default 0.4)
+    pub fn opacity(&self) -> f64 {
+        match self {
+            WatermarkSpec::WithConfig(WatermarkConfig { opacity: Some(opacity), .. }) => *opacity,
+            _ => 0.4,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WatermarkConfig {
+    pub text: String,
+    /// Corner of the canvas the text sits in (default bottom-right)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub position: Option<WatermarkPosition>,
+    /// Opacity of the text (default 0.4)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub opacity: Option<f64>,
+}
+
+/// Corner of the canvas a watermark is placed in
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum WatermarkPosition {
+    TopLeft,
+    TopRight,
+    BottomLeft,
+    #[default]
+    BottomRight,
+}
+
+/// Scale resolution across layers
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Resolve {
+    #[serde(default)]
+    pub scale: ScaleResolve,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ScaleResolve {
+    /// Layers default to independent y scales
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub y: Option<ResolveMode>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ResolveMode {
+    /// One scale whose domain spans every layer
+    Shared,
+    /// Each layer scales to its own data
+    Independent,
 }
 
 /// Stacking configuration
@@ -1,6 +1,12 @@
+use std::collections::BTreeMap;
+use std::fmt;
+
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 
+use super::StackConfig;
+use crate::scale::get_path;
+
 /// Encoding channels that map data to visual properties
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct Encoding {
@@ -12,6 +18,9 @@ pub struct Encoding {
     pub x2: Option<ChannelDef>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub y2: Option<ChannelDef>,
+    /// Angular extent of arc wedges
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub theta: Option<ChannelDef>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub color: Option<ChannelDef>,
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -28,20 +37,168 @@ pub struct Encoding {
     pub text: Option<ChannelDef>,
 }
 
+impl Encoding {
+    /// The encoded channels with their spec names, in declaration order
+    pub fn channels(&self) -> impl Iterator<Item = (&'static str, &ChannelDef)> {
+        [
+            ("x", &self.x),
+            ("y", &self.y),
+            ("x2", &self.x2),
+            ("y2", &self.y2),
+            ("theta", &self.theta),
+            ("color", &self.color),
+            ("fill", &self.fill),
+            ("stroke", &self.stroke),
+            ("size", &self.size),
+            ("opacity", &self.opacity),
+            ("shape", &self.shape),
+            ("text", &self.text),
+        ]
+        .into_iter()
+        .filter_map(|(name, channel)| Some((name, channel.as_ref()?)))
+    }
+
+    /// The first channel whose field no row has, which is almost always a typo in the spec,
+    /// with the data's closest field names. Empty data isn't checked, nor are `count`
+    /// aggregates, which count rows whatever their field.
+    pub fn missing_field(&self, data: &[Value]) -> Option<MissingField> {
+        if data.is_empty() {
+            return None;
+        }
+        for (name, channel) in self.channels() {
+            let Some(field) = channel.field() else {
+                continue;
+            };
+            if field == COUNT_FIELD || channel.aggregate().is_some_and(|a| a.op() == Aggregate::Count) {
+                continue;
+            }
+            if data.iter().any(|row| get_path(row, field).is_some()) {
+                continue;
+            }
+
+            let mut known: Vec<&str> = Vec::new();
+            for key in data.iter().filter_map(Value::as_object).flat_map(|row| row.keys()) {
+                if !known.contains(&key.as_str()) {
+                    known.push(key);
+                }
+            }
+            let mut close: Vec<(usize, &str)> = known
+                .into_iter()
+                .map(|key| (edit_distance(field, key), key))
+                .filter(|(distance, _)| *distance <= (field.chars().count() / 3).max(2))
+                .collect();
+            close.sort_by_key(|(distance, _)| *distance);
+
+            return Some(MissingField {
+                channel: name,
+                field: field.to_string(),
+                suggestions: close.into_iter().take(3).map(|(_, key)| key.to_string()).collect(),
+            });
+        }
+        None
+    }
+
+    /// How bar marks lay this encoding out over `data`. The bar compiler and
+    /// [`ChartSpec::validate`](super::ChartSpec::validate) both read it, so they agree on
+    /// which channel carries the values and when bars stack.
+    pub fn bar_layout(&self, data: &[Value], stack: Option<&StackConfig>) -> BarLayout {
+        let x = self.x.as_ref().and_then(|x| Some((x, x.field()?)));
+        let y = self.y.as_ref().and_then(|y| Some((y, y.field()?)));
+        // A quantitative x against categorical y lays the bars along x
+        let horizontal = x.is_some_and(|(x, field)| channel_type(x, data, field) == DataType::Quantitative)
+            && y.is_some_and(|(y, field)| matches!(channel_type(y, data, field), DataType::Nominal | DataType::Ordinal));
+        let value_field = if horizontal { x } else { y }.map(|(_, field)| field);
+
+        // Coloring bars by their own quantitative value grades them along a ramp, so only
+        // a color field that splits the bars into series stacks them, unless `stack` is off
+        let series = self.color.as_ref().is_some_and(|color| match color.field() {
+            Some(field) if Some(field) == value_field => channel_type(color, data, field) != DataType::Quantitative,
+            Some(_) => true,
+            None => false,
+        });
+        let stacked = series && stack.is_some_and(|stack| !matches!(stack, StackConfig::Enabled(false)));
+        BarLayout { horizontal, stacked }
+    }
+}
+
+/// Orientation and stacking of bar marks, found by [`Encoding::bar_layout`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BarLayout {
+    /// Values run along x and categories down y
+    pub horizontal: bool,
+    /// Each category's bar is a stack of segments, one per color series
+    pub stacked: bool,
+}
+
+impl BarLayout {
+    /// Spec name of the channel carrying the bars' values
+    pub fn value_channel(&self) -> &'static str {
+        if self.horizontal { "x" } else { "y" }
+    }
+
+    /// Spec name of the channel carrying the bars' categories
+    pub fn category_channel(&self) -> &'static str {
+        if self.horizontal { "y" } else { "x" }
+    }
+}
+
+/// A channel whose field isn't in the data, found by [`Encoding::missing_field`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct MissingField {
+    /// Spec name of the channel, e.g. `"x"`
+    pub channel: &'static str,
+    pub field: String,
+    /// The data's field names closest to `field`, closest first
+    pub suggestions: Vec<String>,
+}
+
+impl fmt::Display for MissingField {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "field \"{}\" is not in the data", self.field)?;
+        if !self.suggestions.is_empty() {
+            let names: Vec<String> = self.suggestions.iter().map(|key| format!("\"{key}\"")).collect();
+            write!(f, "; did you mean {}?", names.join(" or "))?;
+        }
+        Ok(())
+    }
+}
+
+/// Levenshtein distance between two strings, counted in chars
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(ca != *cb);
+            current.push(substitution.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
 /// Definition of how a channel maps data to visual property
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(untagged)]
 pub enum ChannelDef {
     /// Full channel definition
-    Full(ChannelDefFull),
+    Full(Box<ChannelDefFull>),
     /// Shorthand: just the field name (type will be inferred)
     Field(String),
 }
 
+/// Field a `count` aggregate without a field is read from, holding each group's row count
+pub const COUNT_FIELD: &str = "*";
+
 impl ChannelDef {
+    /// The channel's field, or [`COUNT_FIELD`] for a `count` aggregate that names none
     pub fn field(&self) -> Option<&str> {
         match self {
-            ChannelDef::Full(def) => def.field.as_deref(),
+            ChannelDef::Full(def) => def.field.as_deref().or_else(|| {
+                let count = def.aggregate.as_ref().is_some_and(|a| a.op() == Aggregate::Count);
+                count.then_some(COUNT_FIELD)
+            }),
             ChannelDef::Field(f) => Some(f.as_str()),
         }
     }
@@ -60,9 +217,9 @@ impl ChannelDef {
         }
     }
 
-    pub fn aggregate(&self) -> Option<Aggregate> {
+    pub fn aggregate(&self) -> Option<&AggregateSpec> {
         match self {
-            ChannelDef::Full(def) => def.aggregate,
+            ChannelDef::Full(def) => def.aggregate.as_ref(),
             ChannelDef::Field(_) => None,
         }
     }
@@ -74,15 +231,60 @@ impl ChannelDef {
         }
     }
 
+    pub fn scale_domain(&self) -> Option<&[String]> {
+        match self {
+            ChannelDef::Full(def) => def.scale_domain.as_deref(),
+            ChannelDef::Field(_) => None,
+        }
+    }
+
+    pub fn color_map(&self) -> Option<&BTreeMap<String, String>> {
+        match self {
+            ChannelDef::Full(def) => def.color_map.as_ref(),
+            ChannelDef::Field(_) => None,
+        }
+    }
+
+    pub fn color_scale_type(&self) -> ColorScaleType {
+        match self {
+            ChannelDef::Full(def) => def.color_scale_type.unwrap_or_default(),
+            ChannelDef::Field(_) => ColorScaleType::default(),
+        }
+    }
+
+    pub fn scale_type(&self) -> ScaleType {
+        match self {
+            ChannelDef::Full(def) => def.scale_type.unwrap_or_default(),
+            ChannelDef::Field(_) => ScaleType::default(),
+        }
+    }
+
+    /// Whether a linear scale for the channel rounds its domain out to nice values (default)
+    pub fn nice(&self) -> bool {
+        match self {
+            ChannelDef::Full(def) => def.nice != Some(false),
+            ChannelDef::Field(_) => true,
+        }
+    }
+
     pub fn axis(&self) -> Option<&AxisConfig> {
         match self {
-            ChannelDef::Full(def) => def.axis.as_ref(),
+            ChannelDef::Full(def) => match &def.axis {
+                Some(AxisSpec::Config(config)) => Some(config),
+                _ => None,
+            },
             ChannelDef::Field(_) => None,
         }
     }
+
+    /// Whether the channel asked for no axis with `axis: false` or `axis: null`
+    pub fn axis_disabled(&self) -> bool {
+        matches!(self, ChannelDef::Full(def) if matches!(def.axis, Some(AxisSpec::Enabled(false))))
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
 pub struct ChannelDefFull {
     /// Data field name
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -96,12 +298,46 @@ pub struct ChannelDefFull {
     /// Named scale reference
     #[serde(skip_serializing_if = "Option::is_none")]
     pub scale: Option<String>,
-    /// Aggregation function (must be explicit)
+    /// Explicit ordered categories for a band scale; rows with other values are dropped
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub scale_domain: Option<Vec<String>>,
+    /// Color channel only: fixed hex color per category; other categories cycle the palette
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub color_map: Option<BTreeMap<String, String>>,
+    /// Color channel only: how a quantitative field spreads along the sequential ramp
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub aggregate: Option<Aggregate>,
-    /// Axis configuration
+    pub color_scale_type: Option<ColorScaleType>,
+    /// Quantitative position channels only: `false` ends the axis exactly at the data's
+    /// extent (or zero) instead of extending it to round tick values
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub axis: Option<AxisConfig>,
+    pub nice: Option<bool>,
+    /// Quantitative position channels only: how values are spaced along the axis; `log` is
+    /// supported on the value axis of unstacked bars
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub scale_type: Option<ScaleType>,
+    /// Aggregation function (must be explicit), alone or with its parameters
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub aggregate: Option<AggregateSpec>,
+    /// Axis configuration, or `false`/`null` to draw no axis
+    #[serde(default, deserialize_with = "deserialize_axis", skip_serializing_if = "Option::is_none")]
+    pub axis: Option<AxisSpec>,
+}
+
+/// A channel's `axis` value: `true`/`false`, or a configuration (which implies `true`)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum AxisSpec {
+    Enabled(bool),
+    Config(AxisConfig),
+}
+
+/// Read a present `axis` field, treating an explicit `null` as `false` rather than absent
+fn deserialize_axis<'de, D>(deserializer: D) -> Result<Option<AxisSpec>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let axis = Option::<AxisSpec>::deserialize(deserializer)?;
+    Ok(Some(axis.unwrap_or(AxisSpec::Enabled(false))))
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
@@ -117,6 +353,153 @@ pub enum DataType {
     Temporal,
 }
 
+/// Data type of a channel: the explicit `type` if given, otherwise inferred from the data
+pub fn channel_type(channel: &ChannelDef, data: &[Value], field: &str) -> DataType {
+    match (channel.data_type(), channel.scale_domain()) {
+        (Some(data_type), _) => data_type,
+        // An explicit domain is a category order, whatever the values look like
+        (None, Some(_)) => DataType::Ordinal,
+        (None, None) => infer_data_type(data, field),
+    }
+}
+
+/// Infer data type from values
+///
+/// Integer columns that repeat a few distinct values (such as years shared by several
+/// series) are treated as ordinal categories rather than a continuous range.
+pub fn infer_data_type(values: &[Value], field: &str) -> DataType {
+    for value in values {
+        if let Some(v) = get_path(value, field) {
+            match v {
+                Value::Number(_) => {
+                    if is_low_cardinality_integer(values, field) {
+                        return DataType::Ordinal;
+                    }
+                    return DataType::Quantitative;
+                }
+                Value::String(s) => {
+                    if is_iso_date(s) {
+                        return DataType::Temporal;
+                    }
+                    return DataType::Nominal;
+                }
+                _ => continue,
+            }
+        }
+    }
+    DataType::Nominal
+}
+
+/// Whether `s` starts with an ISO-like `YYYY-MM` or `YYYY-MM-DD` date
+pub(crate) fn is_iso_date(s: &str) -> bool {
+    let bytes = s.as_bytes();
+    let number = |start: usize, len: usize| -> Option<u32> {
+        let digits = bytes.get(start..start + len)?;
+        if !digits.iter().all(u8::is_ascii_digit) {
+            return None;
+        }
+        s[start..start + len].parse().ok()
+    };
+
+    if number(0, 4).is_none() || bytes.get(4) != Some(&b'-') {
+        return false;
+    }
+    if !number(5, 2).is_some_and(|month| (1..=12).contains(&month)) {
+        return false;
+    }
+    match bytes.get(7) {
+        None => true,
+        Some(b'-') => {
+            number(8, 2).is_some_and(|day| (1..=31).contains(&day))
+                && !bytes.get(10).is_some_and(u8::is_ascii_digit)
+        }
+        Some(_) => false,
+    }
+}
+
+/// Most distinct values an integer column can have and still be inferred ordinal
+const MAX_ORDINAL_INTEGERS: usize = 12;
+
+/// Whether every value of `field` is an integer and each distinct value repeats on average
+fn is_low_cardinality_integer(values: &[Value], field: &str) -> bool {
+    let mut distinct = std::collections::HashSet::new();
+    let mut count = 0;
+    for v in values.iter().filter_map(|row| get_path(row, field)) {
+        match v.as_i64() {
+            Some(n) => {
+                distinct.insert(n);
+                count += 1;
+            }
+            None if v.is_null() => {}
+            None => return false,
+        }
+    }
+    distinct.len() <= MAX_ORDINAL_INTEGERS && distinct.len() * 2 <= count
+}
+
+/// Interpolation of a sequential color scale
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ColorScaleType {
+    #[default]
+    Linear,
+    /// In log space, for positive data spanning orders of magnitude (population, income)
+    Log,
+}
+
+/// Spacing of values along a position axis
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ScaleType {
+    #[default]
+    Linear,
+    /// Powers of ten evenly spaced, for positive data spanning orders of magnitude
+    Log,
+}
+
+/// A channel's `aggregate` value: the function name, or `{"op": ..., "weight": ...}`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum AggregateSpec {
+    Op(Aggregate),
+    Config(AggregateConfig),
+}
+
+impl AggregateSpec {
+    pub fn op(&self) -> Aggregate {
+        match self {
+            AggregateSpec::Op(op) => *op,
+            AggregateSpec::Config(config) => config.op,
+        }
+    }
+
+    pub fn weight(&self) -> Option<&str> {
+        match self {
+            AggregateSpec::Op(_) => None,
+            AggregateSpec::Config(config) => config.weight.as_deref(),
+        }
+    }
+
+    /// Fraction for `quantile` (default 0.5, the median)
+    pub fn p(&self) -> f64 {
+        match self {
+            AggregateSpec::Config(AggregateConfig { p: Some(p), .. }) => *p,
+            _ => 0.5,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AggregateConfig {
+    pub op: Aggregate,
+    /// `mean` only: field weighting each value, giving `sum(value * weight) / sum(weight)`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub weight: Option<String>,
+    /// `quantile` only: fraction of the way through the sorted values, e.g. `0.9` for p90
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub p: Option<f64>,
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
 pub enum Aggregate {
@@ -124,12 +507,15 @@ pub enum Aggregate {
     Sum,
     Mean,
     Median,
+    /// Value at fraction `p` of the sorted values, interpolated between neighbours
+    Quantile,
     Min,
     Max,
     Distinct,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
 pub struct AxisConfig {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub orient: Option<AxisOrient>,
@@ -137,12 +523,28 @@ pub struct AxisConfig {
     pub title: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub grid: Option<bool>,
+    /// Dash pattern for gridlines (default `[2, 2]`; an empty array draws solid lines)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub grid_dash: Option<Vec<f64>>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub ticks: Option<bool>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub labels: Option<bool>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub tick_count: Option<u32>,
+    /// Exact tick values for a quantitative axis, replacing the automatic ones; values
+    /// outside the scale's domain are dropped
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub values: Option<Vec<f64>>,
+    /// Tick mark length in pixels (defaults to the theme's tick length)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tick_size: Option<f64>,
+    /// Gap between the end of a tick and its label in pixels (default 10)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub label_padding: Option<f64>,
+    /// Longest tick label in pixels; longer ones are cut with an ellipsis
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub label_limit: Option<f64>,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
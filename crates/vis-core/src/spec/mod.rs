@@ -1,5 +1,9 @@
 mod chart;
 mod encoding;
+mod transform;
+mod validate;
 
 pub use chart::*;
 pub use encoding::*;
+pub use transform::*;
+pub use validate::*;
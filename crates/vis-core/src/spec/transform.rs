@@ -0,0 +1,15 @@
+use serde::{Deserialize, Serialize};
+
+/// Data transform applied to the chart's rows before marks are compiled
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum TransformSpec {
+    /// Pivot the named columns into one row per column, with the column name in a key
+    /// field and its value in a value field
+    Fold {
+        fold: Vec<String>,
+        /// Output field names for the key and value, defaulting to `key` and `value`
+        #[serde(rename = "as", skip_serializing_if = "Option::is_none")]
+        as_fields: Option<[String; 2]>,
+    },
+}
@@ -0,0 +1,311 @@
+use std::fmt;
+
+use serde::Serialize;
+use serde_json::Value;
+
+use super::{Aggregate, ChartSpec, DataSpec, Encoding, MarkType, ScaleType, StackConfig, ViewSpec};
+use crate::scale::{get_path, value_to_f64};
+use crate::transform::apply_transforms;
+
+/// A problem found by [`ChartSpec::validate`], located by its path in the spec
+#[derive(Debug, Clone, PartialEq)]
+pub struct SpecIssue {
+    /// Where the problem is, e.g. `"layer[1].encoding.y"`
+    pub path: String,
+    pub message: String,
+}
+
+impl SpecIssue {
+    fn new(path: impl Into<String>, message: impl Into<String>) -> Self {
+        Self {
+            path: path.into(),
+            message: message.into(),
+        }
+    }
+}
+
+impl fmt::Display for SpecIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.path, self.message)
+    }
+}
+
+impl ChartSpec {
+    /// Check the spec for problems that would stop it compiling or leave the chart empty,
+    /// without compiling it. Unlike compiling, this reports every issue rather than the first.
+    pub fn validate(&self) -> Vec<SpecIssue> {
+        let mut issues = Vec::new();
+
+        let padding = &self.padding;
+        let fullbleed = self.view.as_ref().is_some_and(ViewSpec::is_fullbleed);
+        if !fullbleed && padding.left + padding.right >= self.width {
+            issues.push(SpecIssue::new("padding", "left and right padding leave no room for the plot"));
+        }
+        if !fullbleed && padding.top + padding.bottom >= self.height {
+            issues.push(SpecIssue::new("padding", "top and bottom padding leave no room for the plot"));
+        }
+
+        let transforms = self.transform.as_deref().unwrap_or_default();
+        match (&self.mark, &self.layer) {
+            (Some(mark), _) => match &self.encoding {
+                Some(encoding) => {
+                    validate_mark(mark.mark_type(), encoding, &self.data, transforms, "", "data", &mut issues);
+                    validate_scale_types(mark.mark_type(), encoding, &self.data, transforms, self.stack.as_ref(), "", &mut issues);
+                }
+                None => issues.push(SpecIssue::new("encoding", "mark has no encoding")),
+            },
+            (None, Some(layers)) => {
+                if layers.is_empty() {
+                    issues.push(SpecIssue::new("layer", "no layers"));
+                }
+                for (i, layer) in layers.iter().enumerate() {
+                    let prefix = format!("layer[{i}].");
+                    let (data, data_path) = match &layer.data {
+                        Some(data) => (data, format!("{prefix}data")),
+                        None => (&self.data, "data".to_string()),
+                    };
+                    let mark_type = layer.mark.mark_type();
+                    validate_mark(mark_type, &layer.encoding, data, transforms, &prefix, &data_path, &mut issues);
+                    validate_scale_types(mark_type, &layer.encoding, data, transforms, self.stack.as_ref(), &prefix, &mut issues);
+                }
+            }
+            (None, None) => issues.push(SpecIssue::new("mark", "neither mark nor layer is given")),
+        }
+
+        // Layers sharing the top-level data report its problems once
+        let mut unique: Vec<SpecIssue> = Vec::new();
+        for issue in issues {
+            if !unique.contains(&issue) {
+                unique.push(issue);
+            }
+        }
+        unique
+    }
+}
+
+fn validate_mark(
+    mark_type: MarkType,
+    encoding: &Encoding,
+    data: &DataSpec,
+    transforms: &[super::TransformSpec],
+    prefix: &str,
+    data_path: &str,
+    issues: &mut Vec<SpecIssue>,
+) {
+    let required: &[&str] = match mark_type {
+        MarkType::Bar | MarkType::Line | MarkType::Point | MarkType::Area => &["x", "y"],
+        MarkType::Arc => &["theta"],
+        MarkType::Metric => &["text"],
+        MarkType::Bump => &["x", "y", "color"],
+        _ => {
+            issues.push(SpecIssue::new(
+                format!("{prefix}mark"),
+                format!("{} marks are not supported yet", spec_name(mark_type)),
+            ));
+            &[]
+        }
+    };
+    for channel in required {
+        if !encoding.channels().any(|(name, _)| name == *channel) {
+            issues.push(SpecIssue::new(
+                format!("{prefix}encoding"),
+                format!("{} marks need encoding.{channel}", spec_name(mark_type)),
+            ));
+        }
+    }
+
+    for (name, channel) in encoding.channels() {
+        let Some(aggregate) = channel.aggregate() else {
+            continue;
+        };
+        if channel.field().is_none() {
+            issues.push(SpecIssue::new(
+                format!("{prefix}encoding.{name}"),
+                format!("aggregate {} needs a field", spec_name(aggregate.op())),
+            ));
+        }
+    }
+
+    let values = match data {
+        DataSpec::Inline { values } => values,
+        DataSpec::Named { name } => {
+            issues.push(SpecIssue::new(
+                data_path,
+                format!("named data \"{name}\" can't be compiled; give the values inline"),
+            ));
+            return;
+        }
+    };
+    if values.is_empty() {
+        issues.push(SpecIssue::new(data_path, "no data values"));
+        return;
+    }
+
+    let rows = apply_transforms(values, transforms);
+    if let Some(missing) = encoding.missing_field(&rows) {
+        issues.push(SpecIssue::new(format!("{prefix}encoding.{}", missing.channel), missing.to_string()));
+    }
+    for (name, channel) in encoding.channels() {
+        let (Some(field), Some(aggregate)) = (channel.field(), channel.aggregate()) else {
+            continue;
+        };
+        if matches!(aggregate.op(), Aggregate::Count | Aggregate::Distinct) {
+            continue;
+        }
+        let present: Vec<&Value> = rows.iter().filter_map(|row| get_path(row, field)).collect();
+        if !present.is_empty() && !present.iter().any(|value| value_to_f64(value).is_some()) {
+            issues.push(SpecIssue::new(
+                format!("{prefix}encoding.{name}"),
+                format!("aggregate {} needs numbers, but field \"{field}\" has none", spec_name(aggregate.op())),
+            ));
+        }
+    }
+}
+
+/// `scaleType: "log"` is only drawn on the value axis of unstacked bars; stacked bars fail
+/// to compile with it, and anywhere else it would be silently ignored
+fn validate_scale_types(
+    mark_type: MarkType,
+    encoding: &Encoding,
+    data: &DataSpec,
+    transforms: &[super::TransformSpec],
+    stack: Option<&StackConfig>,
+    prefix: &str,
+    issues: &mut Vec<SpecIssue>,
+) {
+    if !encoding.channels().any(|(_, channel)| channel.scale_type() == ScaleType::Log) {
+        return;
+    }
+    let rows = apply_transforms(data.values().unwrap_or_default(), transforms);
+    let layout = encoding.bar_layout(&rows, stack);
+    for (name, channel) in encoding.channels() {
+        if channel.scale_type() != ScaleType::Log {
+            continue;
+        }
+        let message = match mark_type {
+            MarkType::Bar if name != layout.value_channel() => "log scales are only supported on a bar's value axis",
+            MarkType::Bar if layout.stacked => "log scales aren't supported on stacked bars",
+            MarkType::Bar => continue,
+            _ => "log scales are only supported on bars",
+        };
+        issues.push(SpecIssue::new(format!("{prefix}encoding.{name}"), message));
+    }
+}
+
+/// The name a spec writes for an enum value, e.g. `"sum"` for [`Aggregate::Sum`]
+fn spec_name(value: impl Serialize) -> String {
+    match serde_json::to_value(value) {
+        Ok(Value::String(name)) => name,
+        _ => String::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::parse_spec;
+
+    fn paths(json: &str) -> Vec<String> {
+        parse_spec(json).unwrap().validate().into_iter().map(|issue| issue.path).collect()
+    }
+
+    #[test]
+    fn test_valid_spec_has_no_issues() {
+        let issues = paths(
+            r#"{
+                "data": { "values": [{ "k": "A", "v": 1 }] },
+                "mark": "bar",
+                "encoding": { "x": { "field": "k" }, "y": { "field": "v", "aggregate": "sum" } }
+            }"#,
+        );
+        assert!(issues.is_empty(), "{issues:?}");
+    }
+
+    #[test]
+    fn test_reports_every_issue_with_its_path() {
+        let spec = parse_spec(
+            r#"{
+                "width": 100,
+                "padding": { "top": 10, "right": 60, "bottom": 10, "left": 60 },
+                "data": { "values": [{ "key": "A", "value": 1 }] },
+                "layer": [
+                    { "mark": "bar", "encoding": { "x": { "field": "kye" }, "y": { "aggregate": "sum" } } },
+                    { "mark": "rule", "encoding": { "y": { "field": "key", "aggregate": "mean" } } },
+                    { "mark": "line", "data": { "values": [] }, "encoding": { "x": { "field": "key" } } }
+                ]
+            }"#,
+        )
+        .unwrap();
+        let issues: Vec<String> = spec.validate().iter().map(ToString::to_string).collect();
+        assert_eq!(
+            issues,
+            [
+                "padding: left and right padding leave no room for the plot",
+                "layer[0].encoding.y: aggregate sum needs a field",
+                "layer[0].encoding.x: field \"kye\" is not in the data; did you mean \"key\"?",
+                "layer[1].mark: rule marks are not supported yet",
+                "layer[1].encoding.y: aggregate mean needs numbers, but field \"key\" has none",
+                "layer[2].encoding: line marks need encoding.y",
+                "layer[2].data: no data values",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_reports_log_scales_that_would_be_ignored() {
+        let spec = r#"{
+            "data": { "values": [{ "k": "A", "s": "x", "v": 10 }] },
+            "mark": "MARK",
+            "encoding": {
+                "x": { "field": "k" },
+                "y": { "field": "v", "scaleType": "log" },
+                "color": { "field": "s" }
+            }
+            STACK
+        }"#;
+        let issues = |mark: &str, stack: &str| {
+            let spec = spec.replace("MARK", mark).replace("STACK", stack);
+            parse_spec(&spec).unwrap().validate().iter().map(ToString::to_string).collect::<Vec<_>>()
+        };
+        assert!(issues("bar", "").is_empty());
+        assert_eq!(issues("bar", r#", "stack": true"#), ["encoding.y: log scales aren't supported on stacked bars"]);
+        assert_eq!(issues("line", ""), ["encoding.y: log scales are only supported on bars"]);
+        assert_eq!(issues("point", ""), ["encoding.y: log scales are only supported on bars"]);
+    }
+
+    #[test]
+    fn test_log_scale_checks_follow_the_bar_layout() {
+        let issues = |encoding: &str| {
+            let spec = format!(
+                r#"{{
+                    "data": {{ "values": [{{ "k": "A", "s": "x", "v": 10 }}, {{ "k": "B", "s": "y", "v": 20 }}] }},
+                    "mark": "bar", "stack": true, "encoding": {encoding}
+                }}"#
+            );
+            parse_spec(&spec).unwrap().validate().iter().map(ToString::to_string).collect::<Vec<_>>()
+        };
+        // Coloring bars by their own quantitative value grades them instead of stacking them
+        assert!(issues(r#"{ "x": { "field": "k" }, "y": { "field": "v", "scaleType": "log" }, "color": { "field": "v" } }"#).is_empty());
+        assert_eq!(
+            issues(r#"{ "x": { "field": "k" }, "y": { "field": "v", "scaleType": "log" }, "color": { "field": "v", "type": "nominal" } }"#),
+            ["encoding.y: log scales aren't supported on stacked bars"]
+        );
+        // Horizontal bars carry their values on x and their categories on y
+        assert!(issues(r#"{ "x": { "field": "v", "scaleType": "log" }, "y": { "field": "k" } }"#).is_empty());
+        assert_eq!(
+            issues(r#"{ "x": { "field": "v" }, "y": { "field": "k", "scaleType": "log" } }"#),
+            ["encoding.y: log scales are only supported on a bar's value axis"]
+        );
+        assert_eq!(
+            issues(r#"{ "x": { "field": "k", "scaleType": "log" }, "y": { "field": "v" } }"#),
+            ["encoding.x: log scales are only supported on a bar's value axis"]
+        );
+    }
+
+    #[test]
+    fn test_reports_missing_encoding_and_named_data() {
+        let issues = paths(r#"{ "data": { "name": "table" }, "mark": "bar" }"#);
+        assert_eq!(issues, ["encoding"]);
+        let issues = paths(r#"{ "data": { "name": "table" }, "mark": "bar", "encoding": { "x": { "field": "k" }, "y": { "field": "v" } } }"#);
+        assert_eq!(issues, ["data"]);
+    }
+}
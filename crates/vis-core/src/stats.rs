@@ -0,0 +1,103 @@
+//! Reducers over numeric values, shared by aggregation and composite marks
+//!
+//! Reducers that have no answer for an empty slice return `None`.
+
+/// Number of values
+pub fn count(values: &[f64]) -> usize {
+    values.len()
+}
+
+/// Number of distinct values
+pub fn distinct_count(values: &[f64]) -> usize {
+    let mut distinct = sorted(values);
+    distinct.dedup();
+    distinct.len()
+}
+
+/// Total of the values (0 for none)
+pub fn sum(values: &[f64]) -> f64 {
+    values.iter().sum()
+}
+
+pub fn min(values: &[f64]) -> Option<f64> {
+    values.iter().copied().reduce(f64::min)
+}
+
+pub fn max(values: &[f64]) -> Option<f64> {
+    values.iter().copied().reduce(f64::max)
+}
+
+/// Arithmetic mean
+pub fn mean(values: &[f64]) -> Option<f64> {
+    (!values.is_empty()).then(|| sum(values) / values.len() as f64)
+}
+
+/// `sum(value * weight) / sum(weight)` over `(value, weight)` pairs; `None` when the
+/// weights add up to zero
+pub fn weighted_mean(pairs: &[(f64, f64)]) -> Option<f64> {
+    let total_weight: f64 = pairs.iter().map(|(_, weight)| weight).sum();
+    (total_weight != 0.0).then(|| pairs.iter().map(|(value, weight)| value * weight).sum::<f64>() / total_weight)
+}
+
+pub fn median(values: &[f64]) -> Option<f64> {
+    quantile(values, 0.5)
+}
+
+/// Value at fraction `p` of the sorted values, see [`percentile`]
+pub fn quantile(values: &[f64], p: f64) -> Option<f64> {
+    percentile(&sorted(values), p)
+}
+
+/// Value at fraction `p` (clamped to 0..=1) of already sorted values, interpolating linearly
+/// between the neighbouring order statistics
+pub fn percentile(sorted: &[f64], p: f64) -> Option<f64> {
+    let last = sorted.len().checked_sub(1)?;
+    let rank = p.clamp(0.0, 1.0) * last as f64;
+    let (lo, hi) = (rank.floor() as usize, rank.ceil() as usize);
+    Some(sorted[lo] + (sorted[hi] - sorted[lo]) * (rank - lo as f64))
+}
+
+/// Sample standard deviation; `None` for fewer than two values
+pub fn stddev(values: &[f64]) -> Option<f64> {
+    if values.len() < 2 {
+        return None;
+    }
+    let mean = mean(values)?;
+    let squares: f64 = values.iter().map(|v| (v - mean).powi(2)).sum();
+    Some((squares / (values.len() - 1) as f64).sqrt())
+}
+
+fn sorted(values: &[f64]) -> Vec<f64> {
+    let mut sorted = values.to_vec();
+    sorted.sort_by(f64::total_cmp);
+    sorted
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_empty_and_single_values() {
+        assert_eq!((count(&[]), distinct_count(&[]), sum(&[])), (0, 0, 0.0));
+        assert_eq!((min(&[]), max(&[]), mean(&[]), median(&[])), (None, None, None, None));
+        assert_eq!((quantile(&[], 0.9), stddev(&[]), weighted_mean(&[])), (None, None, None));
+
+        assert_eq!((count(&[4.0]), distinct_count(&[4.0]), sum(&[4.0])), (1, 1, 4.0));
+        assert_eq!((min(&[4.0]), max(&[4.0]), mean(&[4.0])), (Some(4.0), Some(4.0), Some(4.0)));
+        assert_eq!((median(&[4.0]), quantile(&[4.0], 0.9)), (Some(4.0), Some(4.0)));
+        assert_eq!(stddev(&[4.0]), None);
+    }
+
+    #[test]
+    fn test_reducers_over_several_values() {
+        let values = [4.0, 2.0, 8.0, 2.0, 6.0];
+        assert_eq!(distinct_count(&values), 4);
+        assert_eq!(mean(&values), Some(4.4));
+        assert_eq!(median(&values), Some(4.0));
+        assert_eq!(median(&[1.0, 2.0, 3.0, 10.0]), Some(2.5));
+        assert_eq!(quantile(&values, 0.9), Some(7.2));
+        assert_eq!(weighted_mean(&[(10.0, 3.0), (20.0, 1.0)]), Some(12.5));
+        assert!((stddev(&values).unwrap() - 2.607_680_962).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,52 @@
+//! Minimal `{field}` templates filled from a datum, used for tooltips and labels
+
+use serde_json::Value;
+
+use crate::scale::{get_path, value_to_string};
+
+/// Replace each `{field}` in `template` with the datum's value for that field.
+///
+/// Missing fields render as an empty string; a `{` without a closing `}` is kept as-is.
+pub fn format_template(template: &str, datum: &Value) -> String {
+    let mut out = String::with_capacity(template.len());
+    let mut rest = template;
+
+    while let Some(start) = rest.find('{') {
+        out.push_str(&rest[..start]);
+        let after = &rest[start + 1..];
+        match after.find('}') {
+            Some(end) => {
+                let field = after[..end].trim();
+                match get_path(datum, field) {
+                    Some(Value::Null) | None => {}
+                    Some(value) => out.push_str(&value_to_string(value)),
+                }
+                rest = &after[end + 1..];
+            }
+            None => {
+                out.push_str(&rest[start..]);
+                rest = "";
+            }
+        }
+    }
+    out.push_str(rest);
+
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_format_template() {
+        let datum = json!({ "category": "A", "value": 28 });
+        assert_eq!(format_template("{category}: {value}", &datum), "A: 28");
+        assert_eq!(format_template("{missing}!", &datum), "!");
+        assert_eq!(format_template("open {brace", &datum), "open {brace");
+
+        let nested = json!({ "geo": { "country": "US" } });
+        assert_eq!(format_template("{geo.country}", &nested), "US");
+    }
+}
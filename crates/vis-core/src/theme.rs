@@ -0,0 +1,109 @@
+//! Visual defaults shared by the compilers (colors, fonts, axis styling)
+
+use crate::compile::COLORS;
+use crate::ir::{Color, Font};
+
+/// Styling applied while compiling a chart
+#[derive(Debug, Clone, PartialEq)]
+pub struct Theme {
+    /// Canvas background; a spec-level `background` takes precedence
+    pub background: Option<Color>,
+    /// Axis lines, ticks and tick labels
+    pub axis_color: Color,
+    /// Gridlines
+    pub grid_color: Color,
+    /// Axis titles
+    pub title_color: Color,
+    pub font_family: String,
+    pub font_size: f64,
+    pub title_font_size: f64,
+    /// Length of axis tick marks in pixels
+    pub tick_length: f64,
+    /// Categorical palette, cycled by series index
+    pub palette: Vec<Color>,
+    /// Low and high ends of the ramp used for quantitative color fields
+    pub sequential_range: (Color, Color),
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self {
+            background: None,
+            axis_color: Color::rgb(100, 100, 100),
+            grid_color: Color::rgb(220, 220, 220),
+            title_color: Color::rgb(50, 50, 50),
+            font_family: "sans-serif".to_string(),
+            font_size: 12.0,
+            title_font_size: 14.0,
+            tick_length: 6.0,
+            palette: COLORS.iter().filter_map(|hex| Color::from_hex(hex)).collect(),
+            sequential_range: (Color::rgb(222, 235, 247), Color::rgb(8, 81, 156)),
+        }
+    }
+}
+
+impl Theme {
+    /// Light text and axes on a dark background
+    pub fn dark() -> Self {
+        Self {
+            background: Some(Color::rgb(30, 30, 30)),
+            axis_color: Color::rgb(170, 170, 170),
+            grid_color: Color::rgb(70, 70, 70),
+            title_color: Color::rgb(225, 225, 225),
+            ..Self::default()
+        }
+    }
+
+    /// Palette color for the series at `index`, cycling if there are more series than colors
+    pub fn palette_color(&self, index: usize) -> Color {
+        if self.palette.is_empty() {
+            return Color::default();
+        }
+        self.palette[index % self.palette.len()]
+    }
+
+    /// Font for tick labels
+    pub fn label_font(&self) -> Font {
+        Font {
+            family: self.font_family.clone(),
+            size: self.font_size,
+            ..Font::default()
+        }
+    }
+
+    /// Font for axis titles
+    pub fn title_font(&self) -> Font {
+        Font {
+            family: self.font_family.clone(),
+            size: self.title_font_size,
+            ..Font::default()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ir::{Fill, Geometry};
+
+    #[test]
+    fn test_dark_theme_styles_axes_and_background() {
+        let spec = crate::parse_spec(
+            r#"{
+                "data": { "values": [{ "category": "A", "value": 28 }] },
+                "mark": "bar",
+                "encoding": { "x": { "field": "category" }, "y": { "field": "value" } }
+            }"#,
+        )
+        .unwrap();
+        let theme = Theme::dark();
+        let scene = crate::compile_with_theme(&spec, &theme).unwrap();
+
+        assert_eq!(scene.background, theme.background);
+        let label = scene
+            .mark_items()
+            .find(|item| matches!(item.geometry, Geometry::Text { .. }))
+            .unwrap();
+        assert_eq!(label.fill, Some(Fill::Solid(theme.axis_color)));
+    }
+}
@@ -0,0 +1,81 @@
+//! Row-level data transforms run before compilation
+
+use std::borrow::Cow;
+
+use serde_json::{Map, Value};
+
+use crate::scale::get_path;
+use crate::spec::TransformSpec;
+
+/// Apply `transforms` to `data` in order, borrowing the data when there are none
+pub fn apply_transforms<'a>(data: &'a [Value], transforms: &[TransformSpec]) -> Cow<'a, [Value]> {
+    let mut rows = Cow::Borrowed(data);
+    for transform in transforms {
+        rows = match transform {
+            TransformSpec::Fold { fold: fields, as_fields } => {
+                let (key, value) = match as_fields {
+                    Some([key, value]) => (key.as_str(), value.as_str()),
+                    None => ("key", "value"),
+                };
+                Cow::Owned(fold(&rows, fields, key, value))
+            }
+        };
+    }
+    rows
+}
+
+/// Turn each row into one row per folded field that the row has, keeping the other fields
+/// and adding `key_field` (the folded field's name) and `value_field` (its value)
+pub fn fold(data: &[Value], fields: &[String], key_field: &str, value_field: &str) -> Vec<Value> {
+    let mut folded = Vec::with_capacity(data.len() * fields.len());
+    for row in data {
+        let Some(object) = row.as_object() else {
+            continue;
+        };
+        for field in fields {
+            let Some(value) = get_path(row, field) else {
+                continue;
+            };
+            let mut out: Map<String, Value> = object
+                .iter()
+                .filter(|(name, _)| !fields.contains(name))
+                .map(|(name, v)| (name.clone(), v.clone()))
+                .collect();
+            out.insert(key_field.to_string(), Value::String(field.clone()));
+            out.insert(value_field.to_string(), value.clone());
+            folded.push(Value::Object(out));
+        }
+    }
+    folded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_fold_pivots_columns_into_key_value_rows() {
+        let data = vec![json!({ "month": "Jan", "sales": 10, "costs": 4 })];
+        let fields = vec!["sales".to_string(), "costs".to_string()];
+
+        assert_eq!(
+            fold(&data, &fields, "key", "value"),
+            vec![
+                json!({ "month": "Jan", "key": "sales", "value": 10 }),
+                json!({ "month": "Jan", "key": "costs", "value": 4 }),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_fold_reads_dotted_paths() {
+        let data = vec![json!({ "month": "Jan", "totals": { "sales": 10 } })];
+        let fields = vec!["totals.sales".to_string()];
+
+        assert_eq!(
+            fold(&data, &fields, "key", "value"),
+            vec![json!({ "month": "Jan", "totals": { "sales": 10 }, "key": "totals.sales", "value": 10 })]
+        );
+    }
+}
@@ -1,6 +1,14 @@
+mod primitives;
+mod stats;
 mod svg;
+mod table;
+mod vega;
 
-pub use svg::render_svg;
+pub use primitives::{scene_to_primitives, Primitive};
+pub use stats::{SceneStats, StatsRenderer};
+pub use svg::{render_svg, render_svg_filtered, render_svg_with_options, render_svg_with_spec, SvgOptions};
+pub use table::HtmlTableRenderer;
+pub use vega::VegaSceneRenderer;
 
 use vis_core::Scene;
 
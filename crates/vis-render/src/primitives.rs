@@ -0,0 +1,176 @@
+use vis_core::ir::{Color, Fill, Geometry, Group, MarkItem, MarkType, Rect, Scene, SceneNode, Stroke, Transform};
+
+/// One resolved drawing operation: geometry with every opacity (item, fill, stroke and
+/// enclosing groups) folded into the paint's alpha, ready to draw without tracking any state
+#[derive(Debug, Clone, PartialEq)]
+pub struct Primitive {
+    pub mark_type: MarkType,
+    /// In canvas coordinates once mapped through `transform`
+    pub geometry: Geometry,
+    /// Maps `geometry` to the canvas: identity unless [`Scene::flatten`] couldn't bake the
+    /// transform into it, as for paths under a transform or rects under rotation
+    pub transform: Transform,
+    pub fill: Option<Fill>,
+    /// Stroke with `opacity` folded into its color, so `opacity` is always `None`
+    pub stroke: Option<Stroke>,
+    /// Canvas-space clip, the intersection of every enclosing group's clip
+    pub clip: Option<Rect>,
+}
+
+/// Flatten a scene into the primitives it draws, in draw order, starting with a
+/// full-canvas rect for the background when the scene has one.
+///
+/// Builds on [`Scene::flatten`], so clips under rotation become their bounding box.
+pub fn scene_to_primitives(scene: &Scene) -> Vec<Primitive> {
+    let mut primitives = Vec::new();
+    if let Some(background) = scene.background {
+        primitives.push(Primitive {
+            mark_type: MarkType::Rect,
+            geometry: Geometry::Rect {
+                x: 0.0,
+                y: 0.0,
+                width: scene.width,
+                height: scene.height,
+                corner_radius: 0.0,
+            },
+            transform: Transform::default(),
+            fill: Some(Fill::Solid(background)),
+            stroke: None,
+            clip: None,
+        });
+    }
+    collect(&scene.flatten().root, &Transform::default(), 1.0, None, &mut primitives);
+    primitives
+}
+
+fn collect(group: &Group, parent: &Transform, opacity: f64, clip: Option<Rect>, out: &mut Vec<Primitive>) {
+    // Flattened groups keep a transform only around marks it couldn't be baked into, and
+    // those groups hold no clip
+    let transform = group.transform.then(parent);
+    let opacity = opacity * group.opacity;
+    let clip = match (clip, group.clip) {
+        (Some(outer), Some(inner)) => Some(intersect(&outer, &inner)),
+        (outer, inner) => inner.or(outer),
+    };
+    for child in &group.children {
+        match child {
+            SceneNode::Group(child) => collect(child, &transform, opacity, clip, out),
+            SceneNode::Mark(mark) => out.extend(
+                mark.items
+                    .iter()
+                    .map(|item| resolve(mark.mark_type, item, transform, opacity, clip)),
+            ),
+        }
+    }
+}
+
+fn resolve(mark_type: MarkType, item: &MarkItem, transform: Transform, opacity: f64, clip: Option<Rect>) -> Primitive {
+    let opacity = opacity * item.opacity;
+    let fill_opacity = opacity * item.fill_opacity.unwrap_or(1.0);
+    Primitive {
+        mark_type,
+        geometry: item.geometry.clone(),
+        transform,
+        fill: item.fill.as_ref().map(|fill| fade_fill(fill, fill_opacity)),
+        stroke: item.stroke.as_ref().map(|stroke| Stroke {
+            color: fade(stroke.color, opacity * stroke.opacity.unwrap_or(1.0)),
+            opacity: None,
+            ..stroke.clone()
+        }),
+        clip,
+    }
+}
+
+fn fade_fill(fill: &Fill, opacity: f64) -> Fill {
+    match fill {
+        Fill::Solid(color) => Fill::Solid(fade(*color, opacity)),
+        Fill::LinearGradient { stops, angle } => {
+            let mut stops = stops.clone();
+            for stop in &mut stops {
+                stop.color = fade(stop.color, opacity);
+            }
+            Fill::LinearGradient { stops, angle: *angle }
+        }
+        Fill::Pattern {
+            pattern,
+            color,
+            background,
+        } => Fill::Pattern {
+            pattern: *pattern,
+            color: fade(*color, opacity),
+            background: background.map(|background| fade(background, opacity)),
+        },
+    }
+}
+
+fn fade(color: Color, opacity: f64) -> Color {
+    Color {
+        a: (color.a as f64 * opacity.clamp(0.0, 1.0)).round() as u8,
+        ..color
+    }
+}
+
+/// Overlap of two rects, empty (zero-sized) when they don't overlap
+fn intersect(a: &Rect, b: &Rect) -> Rect {
+    let x = a.x.max(b.x);
+    let y = a.y.max(b.y);
+    let right = (a.x + a.width).min(b.x + b.width);
+    let bottom = (a.y + a.height).min(b.y + b.height);
+    Rect::new(x, y, (right - x).max(0.0), (bottom - y).max(0.0))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use vis_core::ir::{Mark, Transform};
+
+    #[test]
+    fn test_primitives_are_absolute_with_opacity_folded_into_color() {
+        let mut inner = Group::new()
+            .with_transform(Transform::translate(10.0, 20.0))
+            .with_opacity(0.5);
+        inner.clip = Some(Rect::new(0.0, 0.0, 50.0, 50.0));
+        inner.add_mark(Mark::new(
+            MarkType::Rect,
+            vec![MarkItem::new(Geometry::Rect {
+                x: 5.0,
+                y: 5.0,
+                width: 10.0,
+                height: 10.0,
+                corner_radius: 0.0,
+            })
+            .with_fill(Color::rgb(255, 0, 0))
+            .with_opacity(0.5)],
+        ));
+        let mut root = Group::new().with_transform(Transform::translate(100.0, 0.0));
+        root.add_group(inner);
+        let mut scene = Scene::new(300.0, 200.0);
+        scene.background = Some(Color::rgb(255, 255, 255));
+        scene.root = root;
+
+        let primitives = scene_to_primitives(&scene);
+        assert_eq!(primitives.len(), 2);
+        assert_eq!(primitives[0].fill, Some(Fill::Solid(Color::rgb(255, 255, 255))));
+
+        let bar = &primitives[1];
+        assert!(matches!(bar.geometry, Geometry::Rect { x, y, .. } if x == 115.0 && y == 25.0));
+        assert_eq!(bar.fill, Some(Fill::Solid(Color::rgba(255, 0, 0, 64))));
+        assert_eq!(bar.clip, Some(Rect::new(110.0, 20.0, 50.0, 50.0)));
+        assert!(bar.transform.is_identity());
+    }
+
+    #[test]
+    fn test_unbakeable_geometry_carries_its_transform() {
+        let mut group = Group::new().with_transform(Transform::translate(10.0, 20.0));
+        group.add_mark(Mark::new(
+            MarkType::Path,
+            vec![MarkItem::new(Geometry::Path { d: "M0,0L5,5".to_string() })],
+        ));
+        let mut scene = Scene::new(100.0, 100.0);
+        scene.root.add_group(group);
+
+        let [path] = scene_to_primitives(&scene).try_into().unwrap();
+        assert_eq!(path.geometry, Geometry::Path { d: "M0,0L5,5".to_string() });
+        assert_eq!(path.transform.apply(5.0, 5.0), (15.0, 25.0));
+    }
+}
@@ -0,0 +1,89 @@
+use std::collections::HashMap;
+
+use vis_core::ir::{Group, MarkType, Scene, SceneNode};
+
+use crate::Renderer;
+
+/// Summary counts for a scene, useful for assertions in tests
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SceneStats {
+    /// Number of `Mark` nodes in the tree
+    pub total_marks: usize,
+    /// Number of mark items per mark type
+    pub items_by_type: HashMap<MarkType, usize>,
+    /// Deepest group nesting (the root group counts as 1)
+    pub group_depth: usize,
+}
+
+impl SceneStats {
+    /// Number of items of the given mark type
+    pub fn items(&self, mark_type: MarkType) -> usize {
+        self.items_by_type.get(&mark_type).copied().unwrap_or(0)
+    }
+
+    /// Number of items across all mark types
+    pub fn total_items(&self) -> usize {
+        self.items_by_type.values().sum()
+    }
+}
+
+/// Renderer that walks the scene and counts marks instead of drawing them
+#[derive(Debug, Clone, Copy, Default)]
+pub struct StatsRenderer;
+
+impl Renderer for StatsRenderer {
+    type Output = SceneStats;
+
+    fn render(&self, scene: &Scene) -> SceneStats {
+        let mut stats = SceneStats::default();
+        collect_group(&mut stats, &scene.root, 1);
+        stats
+    }
+}
+
+fn collect_group(stats: &mut SceneStats, group: &Group, depth: usize) {
+    stats.group_depth = stats.group_depth.max(depth);
+
+    for child in &group.children {
+        match child {
+            SceneNode::Group(g) => collect_group(stats, g, depth + 1),
+            SceneNode::Mark(m) => {
+                stats.total_marks += 1;
+                *stats.items_by_type.entry(m.mark_type).or_insert(0) += m.items.len();
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bar_chart_stats() {
+        let scene = vis_core::chart(
+            r#"{
+                "data": { "values": [
+                    { "category": "A", "value": 28 },
+                    { "category": "B", "value": 55 },
+                    { "category": "C", "value": 43 },
+                    { "category": "D", "value": 91 },
+                    { "category": "E", "value": 81 },
+                    { "category": "F", "value": 53 }
+                ] },
+                "mark": "bar",
+                "encoding": {
+                    "x": { "field": "category", "type": "nominal" },
+                    "y": { "field": "value", "type": "quantitative" }
+                }
+            }"#,
+        )
+        .unwrap();
+
+        let stats = StatsRenderer.render(&scene);
+        assert_eq!(stats.items(MarkType::Rect), 6);
+        assert_eq!(stats.group_depth, 1);
+        // Bars plus line, ticks and labels for each of the two axes
+        assert_eq!(stats.total_marks, 7);
+    }
+}
@@ -1,12 +1,258 @@
+use std::collections::HashSet;
 use std::fmt::Write;
 
 use vis_core::ir::{
-    Geometry, Group, Mark, MarkItem, MarkType, Scene, SceneNode, SymbolShape, TextAnchor,
-    TextBaseline,
+    Fill, Geometry, Group, Mark, MarkItem, MarkType, PatternKind, Rect, Scene, SceneNode,
+    Stroke, SymbolShape, TextAnchor, TextBaseline,
 };
+use vis_core::template::format_template;
+
+/// Side length of a pattern tile in pixels
+const PATTERN_SIZE: f64 = 8.0;
+
+/// Downward offset, blur radius and opacity of mark drop shadows
+const SHADOW_OFFSET: f64 = 1.0;
+const SHADOW_BLUR: f64 = 1.5;
+const SHADOW_OPACITY: f64 = 0.3;
+
+/// Options for [`render_svg_with_options`]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SvgOptions {
+    /// Snap horizontal and vertical 1px rules (axes, ticks, gridlines) to half-pixel
+    /// coordinates so the stroke covers a single pixel row instead of blurring across two
+    pub crisp_edges: bool,
+    /// Align text vertically with `dy` offsets estimated from the font size instead of
+    /// `dominant-baseline`, which some renderers (e.g. PDF and print pipelines) ignore
+    pub baseline_via_dy: bool,
+    /// Lay text out right to left; always on for scenes with `rtl` set
+    pub rtl: bool,
+    /// Define each distinct non-circle symbol path once and draw points as `<use>`
+    /// references to it, which keeps large scatter plots much smaller
+    pub reuse_symbols: bool,
+    /// Gather each element's presentation attributes (`fill`, `stroke`, fonts, clip paths,
+    /// ...) into a single `style` attribute, for tools and CSS overrides that expect inline
+    /// styles. Gradients and patterns inside `<defs>` keep their attributes.
+    pub use_style_attr: bool,
+}
+
+/// Properties whose values are lengths, which need units in CSS
+const LENGTH_PROPERTIES: &[&str] = &["stroke-width", "stroke-dasharray", "font-size"];
+
+/// Presentation attributes of one element, written either as separate attributes or, with
+/// [`SvgOptions::use_style_attr`], as a single `style` attribute
+#[derive(Debug, Default)]
+struct Style(Vec<(&'static str, String)>);
+
+impl Style {
+    fn set(&mut self, name: &'static str, value: impl Into<String>) {
+        self.0.push((name, value.into()));
+    }
+
+    fn write(&self, svg: &mut String, options: &SvgOptions) {
+        if !options.use_style_attr {
+            for (name, value) in &self.0 {
+                write!(svg, r#" {}="{}""#, name, value).unwrap();
+            }
+            return;
+        }
+        if self.0.is_empty() {
+            return;
+        }
+        let properties: Vec<String> = self
+            .0
+            .iter()
+            .map(|(name, value)| {
+                if LENGTH_PROPERTIES.contains(name) {
+                    let lengths: Vec<String> = value.split(',').map(|length| format!("{length}px")).collect();
+                    format!("{}:{}", name, lengths.join(","))
+                } else {
+                    format!("{}:{}", name, value)
+                }
+            })
+            .collect();
+        write!(svg, r#" style="{}""#, properties.join(";")).unwrap();
+    }
+}
+
+/// Definitions (gradients, patterns, clip paths) collected while rendering, emitted in
+/// `<defs>`.
+///
+/// Ids are derived from the definition's content rather than a counter, so the same scene
+/// always renders the same ids (stable snapshots) and identical definitions are shared.
+#[derive(Debug, Default)]
+struct Defs {
+    content: String,
+    ids: HashSet<String>,
+}
+
+impl Defs {
+    /// Resolve a fill to an SVG paint value, registering a definition if needed
+    fn paint(&mut self, fill: &Fill) -> String {
+        match fill {
+            Fill::Solid(color) => color.to_css(),
+            Fill::LinearGradient { stops, angle } => {
+                // Gradient vector through the center of the bounding box
+                let dx = angle.to_radians().cos() / 2.0;
+                let dy = angle.to_radians().sin() / 2.0;
+                let attrs = format!(
+                    r#" x1="{:.3}" y1="{:.3}" x2="{:.3}" y2="{:.3}""#,
+                    0.5 - dx,
+                    0.5 - dy,
+                    0.5 + dx,
+                    0.5 + dy
+                );
+                let mut body = String::new();
+                for stop in stops {
+                    writeln!(
+                        body,
+                        r#"      <stop offset="{:.3}" stop-color="{}"/>"#,
+                        stop.offset,
+                        stop.color.to_css()
+                    )
+                    .unwrap();
+                }
+
+                format!("url(#{})", self.define("linearGradient", "gradient", &attrs, &body))
+            }
+            Fill::Pattern {
+                pattern,
+                color,
+                background,
+            } => {
+                let attrs = format!(
+                    r#" width="{}" height="{}" patternUnits="userSpaceOnUse""#,
+                    PATTERN_SIZE, PATTERN_SIZE
+                );
+                let mut body = String::new();
+                if let Some(bg) = background {
+                    writeln!(
+                        body,
+                        r#"      <rect width="{}" height="{}" fill="{}"/>"#,
+                        PATTERN_SIZE,
+                        PATTERN_SIZE,
+                        bg.to_css()
+                    )
+                    .unwrap();
+                }
+                let css = color.to_css();
+                match pattern {
+                    PatternKind::Dots => writeln!(
+                        body,
+                        r#"      <circle cx="4" cy="4" r="1.5" fill="{}"/>"#,
+                        css
+                    ),
+                    _ => writeln!(
+                        body,
+                        r#"      <path d="{}" stroke="{}" stroke-width="1.5" fill="none"/>"#,
+                        pattern_path(*pattern),
+                        css
+                    ),
+                }
+                .unwrap();
+
+                format!("url(#{})", self.define("pattern", "pattern", &attrs, &body))
+            }
+        }
+    }
+
+    /// Register the path of a `shape` symbol of `size` (centered on the origin), returning its id
+    fn symbol(&mut self, shape: SymbolShape, size: f64) -> String {
+        let attrs = format!(r#" d="{}""#, shape.to_path(size));
+        self.define("path", "symbol", &attrs, "")
+    }
+
+    /// Register the drop shadow filter for shadowed marks, returning its id
+    fn shadow(&mut self) -> String {
+        let attrs = r#" x="-20%" y="-20%" width="140%" height="140%""#;
+        let body = format!(
+            "      <feDropShadow dx=\"0\" dy=\"{}\" stdDeviation=\"{}\" flood-opacity=\"{}\"/>\n",
+            SHADOW_OFFSET, SHADOW_BLUR, SHADOW_OPACITY
+        );
+        self.define("filter", "shadow", attrs, &body)
+    }
+
+    /// Register a clip path for `clip` (in the clipped group's coordinates), returning its id
+    fn clip(&mut self, clip: &Rect) -> String {
+        let body = format!(
+            "      <rect x=\"{:.2}\" y=\"{:.2}\" width=\"{:.2}\" height=\"{:.2}\"/>\n",
+            clip.x, clip.y, clip.width, clip.height
+        );
+        self.define("clipPath", "clip", "", &body)
+    }
+
+    /// Add `<tag id="..."attrs>body</tag>` unless an identical definition exists, and return
+    /// its id: `prefix` plus a hash of the definition
+    fn define(&mut self, tag: &str, prefix: &str, attrs: &str, body: &str) -> String {
+        let id = format!("{}-{:016x}", prefix, content_hash(&[tag, attrs, body]));
+        if self.ids.insert(id.clone()) {
+            writeln!(self.content, r#"    <{} id="{}"{}>"#, tag, id, attrs).unwrap();
+            self.content.push_str(body);
+            writeln!(self.content, "    </{}>", tag).unwrap();
+        }
+        id
+    }
+}
+
+/// 64-bit FNV-1a over `parts` (each followed by a separator byte). Unlike `DefaultHasher`
+/// its output is fixed across Rust versions, which keeps rendered ids stable.
+fn content_hash(parts: &[&str]) -> u64 {
+    const OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+    const PRIME: u64 = 0x0100_0000_01b3;
+    let mut hash = OFFSET_BASIS;
+    for part in parts {
+        for byte in part.bytes().chain([0xff]) {
+            hash ^= u64::from(byte);
+            hash = hash.wrapping_mul(PRIME);
+        }
+    }
+    hash
+}
+
+/// Line work for a pattern tile; diagonals overshoot the tile so strokes join seamlessly
+fn pattern_path(pattern: PatternKind) -> &'static str {
+    match pattern {
+        PatternKind::Diagonal => "M-2,2L2,-2M0,8L8,0M6,10L10,6",
+        PatternKind::Crosshatch => "M-2,2L2,-2M0,8L8,0M6,10L10,6M-2,6L2,10M0,0L8,8M6,-2L10,2",
+        PatternKind::Horizontal => "M0,4H8",
+        PatternKind::Vertical => "M4,0V8",
+        PatternKind::Grid => "M0,4H8M4,0V8",
+        PatternKind::Dots => "",
+    }
+}
 
 /// Render a scene to an SVG string
 pub fn render_svg(scene: &Scene) -> String {
+    render_svg_with_options(scene, &SvgOptions::default())
+}
+
+/// Render a scene to an SVG string with non-default [`SvgOptions`]
+pub fn render_svg_with_options(scene: &Scene, options: &SvgOptions) -> String {
+    render(scene, options, None, &|_| true)
+}
+
+/// Render a scene with its source spec JSON written into the SVG's `<metadata>`, so a saved
+/// chart carries what generated it
+pub fn render_svg_with_spec(scene: &Scene, options: &SvgOptions, spec: &str) -> String {
+    render(scene, options, Some(spec), &|_| true)
+}
+
+/// Render only the marks whose type `keep` accepts, e.g. `|t| t != MarkType::Text` to drop
+/// labels for a data-only overlay. Groups are still walked, so transforms and clips apply
+/// as usual.
+pub fn render_svg_filtered(scene: &Scene, keep: impl Fn(MarkType) -> bool) -> String {
+    render(scene, &SvgOptions::default(), None, &keep)
+}
+
+fn render(
+    scene: &Scene,
+    options: &SvgOptions,
+    spec: Option<&str>,
+    keep: &dyn Fn(MarkType) -> bool,
+) -> String {
+    let options = &SvgOptions {
+        rtl: options.rtl || scene.rtl,
+        ..*options
+    };
     let mut svg = String::with_capacity(8192);
 
     // SVG header
@@ -18,45 +264,63 @@ pub fn render_svg(scene: &Scene) -> String {
     .unwrap();
     svg.push('\n');
 
+    if let Some(spec) = spec {
+        writeln!(svg, "  <metadata>{}</metadata>", escape_xml(spec)).unwrap();
+    }
+
     // Background
     if let Some(bg) = &scene.background {
-        write!(
-            &mut svg,
-            r#"  <rect width="100%" height="100%" fill="{}"/>"#,
-            bg.to_css()
-        )
-        .unwrap();
-        svg.push('\n');
+        svg.push_str(r#"  <rect width="100%" height="100%""#);
+        let mut style = Style::default();
+        style.set("fill", bg.to_css());
+        style.write(&mut svg, options);
+        svg.push_str("/>\n");
     }
 
-    // Render root group
-    render_group(&mut svg, &scene.root, 1);
+    // Render root group, collecting definitions as we go
+    let mut defs = Defs::default();
+    let mut body = String::with_capacity(8192);
+    render_group(&mut body, &mut defs, options, keep, &scene.root, 1);
+
+    if !defs.content.is_empty() {
+        svg.push_str("  <defs>\n");
+        svg.push_str(&defs.content);
+        svg.push_str("  </defs>\n");
+    }
 
+    svg.push_str(&body);
     svg.push_str("</svg>\n");
     svg
 }
 
-fn render_group(svg: &mut String, group: &Group, indent: usize) {
+fn render_group(
+    svg: &mut String,
+    defs: &mut Defs,
+    options: &SvgOptions,
+    keep: &dyn Fn(MarkType) -> bool,
+    group: &Group,
+    indent: usize,
+) {
     let pad = "  ".repeat(indent);
 
     // Open group
     let has_transform = !group.transform.is_identity();
     let has_clip = group.clip.is_some();
+    let has_opacity = group.opacity < 1.0;
 
-    if has_transform || has_clip {
+    if has_transform || has_clip || has_opacity {
         write!(svg, "{}<g", pad).unwrap();
         if has_transform {
             write!(svg, r#" transform="{}""#, group.transform.to_svg()).unwrap();
         }
+        let mut style = Style::default();
         if let Some(clip) = &group.clip {
-            // For simplicity, use inline clip-path
-            write!(
-                svg,
-                r#" clip-path="url(#clip-{}-{})""#,
-                clip.x as i32, clip.y as i32
-            )
-            .unwrap();
+            style.set("clip-path", format!("url(#{})", defs.clip(clip)));
+        }
+        if has_opacity {
+            style.set("opacity", format!("{:.2}", group.opacity));
         }
+        style.write(svg, options);
         svg.push_str(">\n");
     }
 
@@ -64,35 +328,51 @@ fn render_group(svg: &mut String, group: &Group, indent: usize) {
     for child in &group.children {
         match child {
             SceneNode::Group(g) => {
-                render_group(svg, g, indent + 1);
+                render_group(svg, defs, options, keep, g, indent + 1);
             }
-            SceneNode::Mark(m) => {
-                render_mark(svg, m, indent + 1);
+            SceneNode::Mark(m) if keep(m.mark_type) => {
+                render_mark(svg, defs, options, m, indent + 1);
             }
+            SceneNode::Mark(_) => {}
         }
     }
 
     // Close group
-    if has_transform || has_clip {
-        write!(svg, "{}</g>\n", pad).unwrap();
+    if has_transform || has_clip || has_opacity {
+        writeln!(svg, "{}</g>", pad).unwrap();
     }
 }
 
-fn render_mark(svg: &mut String, mark: &Mark, indent: usize) {
+fn render_mark(svg: &mut String, defs: &mut Defs, options: &SvgOptions, mark: &Mark, indent: usize) {
     let pad = "  ".repeat(indent);
 
     // Group for mark (optional, for organization)
-    write!(svg, "{}<g class=\"mark-{:?}\">\n", pad, mark.mark_type).unwrap();
+    write!(svg, "{}<g class=\"mark-{:?}\"", pad, mark.mark_type).unwrap();
+    if mark.shadow {
+        write!(svg, r#" filter="url(#{})""#, defs.shadow()).unwrap();
+    }
+    svg.push_str(">\n");
 
     for item in &mark.items {
-        render_item(svg, item, &mark.mark_type, indent + 1);
+        render_item(svg, defs, options, item, mark.tooltip.as_deref(), indent + 1);
     }
 
-    write!(svg, "{}</g>\n", pad).unwrap();
+    writeln!(svg, "{}</g>", pad).unwrap();
 }
 
-fn render_item(svg: &mut String, item: &MarkItem, _mark_type: &MarkType, indent: usize) {
+fn render_item(
+    svg: &mut String,
+    defs: &mut Defs,
+    options: &SvgOptions,
+    item: &MarkItem,
+    tooltip: Option<&str>,
+    indent: usize,
+) {
     let pad = "  ".repeat(indent);
+    let title = tooltip
+        .zip(item.datum.as_ref())
+        .map(|(t, d)| format_template(t, d))
+        .or_else(|| item.title.clone());
 
     match &item.geometry {
         Geometry::Rect {
@@ -111,8 +391,8 @@ fn render_item(svg: &mut String, item: &MarkItem, _mark_type: &MarkType, indent:
             if *corner_radius > 0.0 {
                 write!(svg, r#" rx="{:.2}""#, corner_radius).unwrap();
             }
-            write_style(svg, item);
-            svg.push_str("/>\n");
+            write_style(svg, defs, options, item);
+            close_element(svg, "rect", title.as_deref());
         }
 
         Geometry::Circle { cx, cy, r } => {
@@ -122,8 +402,8 @@ fn render_item(svg: &mut String, item: &MarkItem, _mark_type: &MarkType, indent:
                 pad, cx, cy, r
             )
             .unwrap();
-            write_style(svg, item);
-            svg.push_str("/>\n");
+            write_style(svg, defs, options, item);
+            close_element(svg, "circle", title.as_deref());
         }
 
         Geometry::Symbol { x, y, size, shape } => {
@@ -135,8 +415,13 @@ fn render_item(svg: &mut String, item: &MarkItem, _mark_type: &MarkType, indent:
                     pad, x, y, r
                 )
                 .unwrap();
-                write_style(svg, item);
-                svg.push_str("/>\n");
+                write_style(svg, defs, options, item);
+                close_element(svg, "circle", title.as_deref());
+            } else if options.reuse_symbols {
+                let id = defs.symbol(*shape, *size);
+                write!(svg, r##"{}<use href="#{}" x="{:.2}" y="{:.2}""##, pad, id, x, y).unwrap();
+                write_style(svg, defs, options, item);
+                close_element(svg, "use", title.as_deref());
             } else {
                 let path = shape.to_path(*size);
                 write!(
@@ -145,8 +430,8 @@ fn render_item(svg: &mut String, item: &MarkItem, _mark_type: &MarkType, indent:
                     pad, path, x, y
                 )
                 .unwrap();
-                write_style(svg, item);
-                svg.push_str("/>\n");
+                write_style(svg, defs, options, item);
+                close_element(svg, "path", title.as_deref());
             }
         }
 
@@ -164,31 +449,16 @@ fn render_item(svg: &mut String, item: &MarkItem, _mark_type: &MarkType, indent:
             }
             svg.push('"');
             // Lines typically have no fill
-            svg.push_str(r#" fill="none""#);
+            let mut style = Style::default();
+            style.set("fill", "none");
             if let Some(stroke) = &item.stroke {
-                write!(
-                    svg,
-                    r#" stroke="{}" stroke-width="{:.2}""#,
-                    stroke.color.to_css(),
-                    stroke.width
-                )
-                .unwrap();
-                if let Some(dash) = &stroke.dash {
-                    write!(
-                        svg,
-                        r#" stroke-dasharray="{}""#,
-                        dash.iter()
-                            .map(|d| format!("{:.2}", d))
-                            .collect::<Vec<_>>()
-                            .join(",")
-                    )
-                    .unwrap();
-                }
+                write_stroke(&mut style, stroke);
             }
             if item.opacity < 1.0 {
-                write!(svg, r#" opacity="{:.2}""#, item.opacity).unwrap();
+                style.set("opacity", format!("{:.2}", item.opacity));
             }
-            svg.push_str("/>\n");
+            style.write(svg, options);
+            close_element(svg, "path", title.as_deref());
         }
 
         Geometry::Area { points, baseline } => {
@@ -209,32 +479,39 @@ fn render_item(svg: &mut String, item: &MarkItem, _mark_type: &MarkType, indent:
                 write!(svg, "L{:.2},{:.2}", pt.x, pt.y).unwrap();
             }
             svg.push_str("Z\"");
-            write_style(svg, item);
-            svg.push_str("/>\n");
+            write_style(svg, defs, options, item);
+            close_element(svg, "path", title.as_deref());
         }
 
         Geometry::Rule { x1, y1, x2, y2 } => {
+            let (mut x1, mut y1, mut x2, mut y2) = (*x1, *y1, *x2, *y2);
+            let one_pixel = item.stroke.as_ref().is_none_or(|stroke| stroke.width == 1.0);
+            if options.crisp_edges && one_pixel {
+                if x1 == x2 {
+                    x1 = x1.floor() + 0.5;
+                    x2 = x1;
+                } else if y1 == y2 {
+                    y1 = y1.floor() + 0.5;
+                    y2 = y1;
+                }
+            }
             write!(
                 svg,
                 r#"{}<line x1="{:.2}" y1="{:.2}" x2="{:.2}" y2="{:.2}""#,
                 pad, x1, y1, x2, y2
             )
             .unwrap();
+            let mut style = Style::default();
             if let Some(stroke) = &item.stroke {
-                write!(
-                    svg,
-                    r#" stroke="{}" stroke-width="{:.2}""#,
-                    stroke.color.to_css(),
-                    stroke.width
-                )
-                .unwrap();
+                write_stroke(&mut style, stroke);
             } else if let Some(fill) = &item.fill {
-                write!(svg, r#" stroke="{}""#, fill.to_css()).unwrap();
+                style.set("stroke", defs.paint(fill));
             }
             if item.opacity < 1.0 {
-                write!(svg, r#" opacity="{:.2}""#, item.opacity).unwrap();
+                style.set("opacity", format!("{:.2}", item.opacity));
             }
-            svg.push_str("/>\n");
+            style.write(svg, options);
+            close_element(svg, "line", title.as_deref());
         }
 
         Geometry::Text {
@@ -248,30 +525,39 @@ fn render_item(svg: &mut String, item: &MarkItem, _mark_type: &MarkType, indent:
         } => {
             write!(svg, r#"{}<text x="{:.2}" y="{:.2}""#, pad, x, y).unwrap();
 
-            // Text anchor
-            let anchor_str = match anchor {
-                TextAnchor::Start => "start",
-                TextAnchor::Middle => "middle",
-                TextAnchor::End => "end",
+            // Text anchor. Anchors are compiled as left/right edges, and right-to-left text
+            // starts on the right, so the two swap to keep labels where they were placed.
+            let mut style = Style::default();
+            let anchor_str = match (anchor, options.rtl) {
+                (TextAnchor::Start, false) | (TextAnchor::End, true) => "start",
+                (TextAnchor::Middle, _) => "middle",
+                (TextAnchor::End, false) | (TextAnchor::Start, true) => "end",
             };
-            write!(svg, r#" text-anchor="{}""#, anchor_str).unwrap();
-
-            // Dominant baseline
-            let baseline_str = match baseline {
-                TextBaseline::Top => "hanging",
-                TextBaseline::Middle => "middle",
-                TextBaseline::Bottom => "ideographic",
-                TextBaseline::Alphabetic => "alphabetic",
+            style.set("text-anchor", anchor_str);
+            if options.rtl {
+                style.set("direction", "rtl");
+            }
+
+            // Dominant baseline, or the equivalent shift off the alphabetic baseline
+            let baseline_dy = if options.baseline_via_dy {
+                baseline_offset(*baseline, font.size)
+            } else {
+                let baseline_str = match baseline {
+                    TextBaseline::Top => "hanging",
+                    TextBaseline::Middle => "middle",
+                    TextBaseline::Bottom => "ideographic",
+                    TextBaseline::Alphabetic => "alphabetic",
+                };
+                style.set("dominant-baseline", baseline_str);
+                0.0
             };
-            write!(svg, r#" dominant-baseline="{}""#, baseline_str).unwrap();
+            if baseline_dy != 0.0 && !text.contains('\n') {
+                write!(svg, r#" dy="{:.2}""#, baseline_dy).unwrap();
+            }
 
             // Font
-            write!(
-                svg,
-                r#" font-family="{}" font-size="{:.1}""#,
-                font.family, font.size
-            )
-            .unwrap();
+            style.set("font-family", font.family.clone());
+            style.set("font-size", format!("{:.1}", font.size));
 
             // Rotation
             if *angle != 0.0 {
@@ -280,16 +566,20 @@ fn render_item(svg: &mut String, item: &MarkItem, _mark_type: &MarkType, indent:
 
             // Fill (text color)
             if let Some(fill) = &item.fill {
-                write!(svg, r#" fill="{}""#, fill.to_css()).unwrap();
+                style.set("fill", defs.paint(fill));
             }
 
             if item.opacity < 1.0 {
-                write!(svg, r#" opacity="{:.2}""#, item.opacity).unwrap();
+                style.set("opacity", format!("{:.2}", item.opacity));
             }
+            style.write(svg, options);
 
-            // Escape text content
-            let escaped = escape_xml(text);
-            write!(svg, ">{}</text>\n", escaped).unwrap();
+            svg.push('>');
+            if let Some(title) = &title {
+                write!(svg, "<title>{}</title>", escape_xml(title)).unwrap();
+            }
+            write_text_lines(svg, text, *x, font.line_height(), *baseline, baseline_dy);
+            svg.push_str("</text>\n");
         }
 
         Geometry::Arc {
@@ -303,50 +593,103 @@ fn render_item(svg: &mut String, item: &MarkItem, _mark_type: &MarkType, indent:
             // Generate arc path
             let path = arc_path(*cx, *cy, *inner_radius, *outer_radius, *start_angle, *end_angle);
             write!(svg, r#"{}<path d="{}""#, pad, path).unwrap();
-            write_style(svg, item);
-            svg.push_str("/>\n");
+            write_style(svg, defs, options, item);
+            close_element(svg, "path", title.as_deref());
         }
 
         Geometry::Path { d } => {
             write!(svg, r#"{}<path d="{}""#, pad, d).unwrap();
-            write_style(svg, item);
-            svg.push_str("/>\n");
+            write_style(svg, defs, options, item);
+            close_element(svg, "path", title.as_deref());
         }
     }
 }
 
-fn write_style(svg: &mut String, item: &MarkItem) {
+fn write_style(svg: &mut String, defs: &mut Defs, options: &SvgOptions, item: &MarkItem) {
+    let mut style = Style::default();
     if let Some(fill) = &item.fill {
-        write!(svg, r#" fill="{}""#, fill.to_css()).unwrap();
+        style.set("fill", defs.paint(fill));
+        if let Some(opacity) = item.fill_opacity {
+            style.set("fill-opacity", format!("{:.2}", opacity));
+        }
     } else {
-        svg.push_str(r#" fill="none""#);
+        style.set("fill", "none");
     }
     if let Some(stroke) = &item.stroke {
-        write!(
-            svg,
-            r#" stroke="{}" stroke-width="{:.2}""#,
-            stroke.color.to_css(),
-            stroke.width
-        )
-        .unwrap();
-        if let Some(dash) = &stroke.dash {
-            write!(
-                svg,
-                r#" stroke-dasharray="{}""#,
-                dash.iter()
-                    .map(|d| format!("{:.2}", d))
-                    .collect::<Vec<_>>()
-                    .join(",")
-            )
-            .unwrap();
-        }
+        write_stroke(&mut style, stroke);
     }
     if item.opacity < 1.0 {
-        write!(svg, r#" opacity="{:.2}""#, item.opacity).unwrap();
+        style.set("opacity", format!("{:.2}", item.opacity));
     }
+    style.write(svg, options);
 }
 
-fn escape_xml(s: &str) -> String {
+/// Add stroke color, width, dash pattern and opacity
+fn write_stroke(style: &mut Style, stroke: &Stroke) {
+    style.set("stroke", stroke.color.to_css());
+    style.set("stroke-width", format!("{:.2}", stroke.width));
+    if let Some(dash) = &stroke.dash {
+        let dash: Vec<String> = dash.iter().map(|d| format!("{:.2}", d)).collect();
+        style.set("stroke-dasharray", dash.join(","));
+    }
+    if let Some(opacity) = stroke.opacity {
+        style.set("stroke-opacity", format!("{:.2}", opacity));
+    }
+}
+
+/// Shift from the alphabetic baseline to `baseline`, as fractions of the font size
+/// approximating the ascent and descent of common sans-serif fonts
+fn baseline_offset(baseline: TextBaseline, font_size: f64) -> f64 {
+    let em = match baseline {
+        TextBaseline::Top => 0.8,
+        TextBaseline::Middle => 0.35,
+        TextBaseline::Bottom => -0.2,
+        TextBaseline::Alphabetic => 0.0,
+    };
+    em * font_size
+}
+
+/// Write text content, one `<tspan>` per line when it contains newlines. The baseline applies
+/// to the whole block: middle-aligned text is shifted up by half its extra lines, bottom-aligned
+/// text by all of them. `first_dy` is added to the first line's offset (the element's own `dy`
+/// doesn't reach into tspans that set one).
+fn write_text_lines(
+    svg: &mut String,
+    text: &str,
+    x: f64,
+    line_height: f64,
+    baseline: TextBaseline,
+    first_dy: f64,
+) {
+    if !text.contains('\n') {
+        svg.push_str(&escape_xml(text));
+        return;
+    }
+
+    let lines: Vec<&str> = text.split('\n').collect();
+    let extra_lines = (lines.len() - 1) as f64;
+    let block_shift = match baseline {
+        TextBaseline::Top => 0.0,
+        TextBaseline::Middle => extra_lines / 2.0,
+        TextBaseline::Bottom | TextBaseline::Alphabetic => extra_lines,
+    };
+    for (i, line) in lines.iter().enumerate() {
+        let dy = if i == 0 { first_dy - block_shift * line_height } else { line_height };
+        write!(svg, r#"<tspan x="{:.2}" dy="{:.2}">{}</tspan>"#, x, dy, escape_xml(line)).unwrap();
+    }
+}
+
+/// Finish an element opened with `<tag ...`, nesting a `<title>` tooltip when present
+fn close_element(svg: &mut String, tag: &str, title: Option<&str>) {
+    match title {
+        Some(title) => {
+            writeln!(svg, "><title>{}</title></{}>", escape_xml(title), tag).unwrap();
+        }
+        None => svg.push_str("/>\n"),
+    }
+}
+
+pub(crate) fn escape_xml(s: &str) -> String {
     s.replace('&', "&amp;")
         .replace('<', "&lt;")
         .replace('>', "&gt;")
@@ -362,6 +705,16 @@ fn arc_path(
     start_angle: f64,
     end_angle: f64,
 ) -> String {
+    // Start and end points coincide for a full sweep, so draw it as two half circles
+    if (end_angle - start_angle).abs() >= std::f64::consts::TAU - 1e-9 {
+        let mut path = circle_path(cx, cy, outer_radius, start_angle, 1);
+        if inner_radius > 0.0 {
+            // Opposite winding cuts the hole
+            path.push_str(&circle_path(cx, cy, inner_radius, start_angle, 0));
+        }
+        return path;
+    }
+
     let cos_start = start_angle.cos();
     let sin_start = start_angle.sin();
     let cos_end = end_angle.cos();
@@ -416,3 +769,420 @@ fn arc_path(
         )
     }
 }
+
+/// Closed circle subpath from `angle`, as two half-circle arcs in the given sweep direction
+fn circle_path(cx: f64, cy: f64, radius: f64, angle: f64, sweep: u8) -> String {
+    let (sin, cos) = angle.sin_cos();
+    let (x, y) = (cx + radius * cos, cy + radius * sin);
+    let (opposite_x, opposite_y) = (cx - radius * cos, cy - radius * sin);
+    format!(
+        "M{x:.2},{y:.2}A{r:.2},{r:.2} 0 1 {sweep} {opposite_x:.2},{opposite_y:.2}A{r:.2},{r:.2} 0 1 {sweep} {x:.2},{y:.2}Z",
+        r = radius
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f64::consts::PI;
+
+    use vis_core::ir::{Color, Font, GradientStop, MarkType, Point};
+
+    #[test]
+    fn test_dashed_rule_emits_dasharray_and_stroke_opacity() {
+        let mut scene = Scene::new(100.0, 100.0);
+        scene.root.add_mark(Mark::new(
+            MarkType::Rule,
+            vec![MarkItem::new(Geometry::Rule {
+                x1: 0.0,
+                y1: 50.0,
+                x2: 100.0,
+                y2: 50.0,
+            })
+            .with_stroke(Stroke::dashed(Color::rgb(0, 0, 0), 1.0, vec![4.0, 2.0]).with_opacity(0.5))],
+        ));
+
+        let svg = render_svg(&scene);
+        assert!(svg.contains(
+            r##"<line x1="0.00" y1="50.00" x2="100.00" y2="50.00" stroke="#000000" stroke-width="1.00" stroke-dasharray="4.00,2.00" stroke-opacity="0.50"/>"##
+        ));
+    }
+
+    #[test]
+    fn test_crisp_edges_snaps_one_pixel_rules_to_half_pixels() {
+        let mut scene = Scene::new(100.0, 100.0);
+        let rule = |x1, y1, x2, y2, width| {
+            MarkItem::new(Geometry::Rule { x1, y1, x2, y2 }).with_stroke(Stroke::solid(Color::rgb(0, 0, 0), width))
+        };
+        scene.root.add_mark(Mark::new(
+            MarkType::Rule,
+            vec![
+                rule(0.0, 50.0, 100.0, 50.0, 1.0),
+                rule(20.3, 0.0, 20.3, 100.0, 1.0),
+                rule(0.0, 30.0, 100.0, 30.0, 2.0),
+            ],
+        ));
+
+        let svg = render_svg_with_options(&scene, &SvgOptions { crisp_edges: true, ..SvgOptions::default() });
+        assert!(svg.contains(r#"<line x1="0.00" y1="50.50" x2="100.00" y2="50.50""#));
+        assert!(svg.contains(r#"<line x1="20.50" y1="0.00" x2="20.50" y2="100.00""#));
+        assert!(svg.contains(r#"<line x1="0.00" y1="30.00" x2="100.00" y2="30.00""#));
+        assert!(render_svg(&scene).contains(r#"y1="50.00""#));
+    }
+
+    #[test]
+    fn test_filtered_render_skips_rejected_mark_types() {
+        let scene = vis_core::chart(
+            r#"{
+                "data": { "values": [{ "c": "A", "v": 1 }, { "c": "B", "v": 2 }] },
+                "mark": "bar",
+                "encoding": { "x": { "field": "c" }, "y": { "field": "v" } }
+            }"#,
+        )
+        .unwrap();
+
+        let data_only = render_svg_filtered(&scene, |t| t == MarkType::Rect);
+        assert_eq!(data_only.matches("<rect x=").count(), 2);
+        assert!(!data_only.contains("<text") && !data_only.contains("<line"));
+
+        let axes_only = render_svg_filtered(&scene, |t| t != MarkType::Rect);
+        assert!(axes_only.contains("<text") && axes_only.contains("<line"));
+        assert!(!axes_only.contains("<rect x="));
+    }
+
+    #[test]
+    fn test_group_opacity_wraps_group_without_transform() {
+        let mut group = Group::new().with_opacity(0.25);
+        group.add_mark(Mark::new(
+            MarkType::Rect,
+            vec![MarkItem::new(Geometry::Rect {
+                x: 0.0,
+                y: 0.0,
+                width: 10.0,
+                height: 10.0,
+                corner_radius: 0.0,
+            })],
+        ));
+        let mut scene = Scene::new(100.0, 100.0);
+        scene.root.add_group(group);
+
+        let svg = render_svg(&scene);
+        assert!(svg.contains(r#"<g opacity="0.25">"#));
+        assert!(!svg.contains("transform="));
+    }
+
+    #[test]
+    fn test_multiline_text_emits_tspans_centered_on_baseline() {
+        let mut scene = Scene::new(100.0, 100.0);
+        scene.root.add_mark(Mark::new(
+            MarkType::Text,
+            vec![MarkItem::new(Geometry::Text {
+                x: 50.0,
+                y: 50.0,
+                text: "Revenue\n(USD)".to_string(),
+                font: Font::default(),
+                anchor: TextAnchor::Middle,
+                baseline: TextBaseline::Middle,
+                angle: 0.0,
+            })],
+        ));
+
+        let svg = render_svg(&scene);
+        assert!(svg.contains(
+            r#"<tspan x="50.00" dy="-7.20">Revenue</tspan><tspan x="50.00" dy="14.40">(USD)</tspan></text>"#
+        ));
+    }
+
+    #[test]
+    fn test_full_circle_arc_draws_a_disk() {
+        let path = arc_path(50.0, 50.0, 0.0, 40.0, -PI / 2.0, 3.0 * PI / 2.0);
+        assert_eq!(
+            path,
+            "M50.00,10.00A40.00,40.00 0 1 1 50.00,90.00A40.00,40.00 0 1 1 50.00,10.00Z"
+        );
+    }
+
+    #[test]
+    fn test_gradient_fill_emits_defs() {
+        let mut scene = Scene::new(100.0, 100.0);
+        let fill = Fill::linear_gradient(
+            vec![
+                GradientStop::new(0.0, Color::rgb(255, 0, 0)),
+                GradientStop::new(1.0, Color::rgb(0, 0, 255)),
+            ],
+            90.0,
+        );
+        scene.root.add_mark(Mark::new(
+            MarkType::Area,
+            vec![MarkItem::new(Geometry::Area {
+                points: vec![Point::new(0.0, 10.0), Point::new(100.0, 20.0)],
+                baseline: vec![Point::new(0.0, 100.0), Point::new(100.0, 100.0)],
+            })
+            .with_fill(fill)],
+        ));
+
+        let svg = render_svg(&scene);
+        let id = defined_ids(&svg).pop().unwrap();
+        assert!(id.starts_with("gradient-"));
+        assert!(svg.contains(&format!(r#"<linearGradient id="{id}" x1="0.500" y1="0.000" x2="0.500" y2="1.000">"#)));
+        assert!(svg.contains(r##"<stop offset="1.000" stop-color="#0000ff"/>"##));
+        assert!(svg.contains(&format!(r#"fill="url(#{id})""#)));
+        assert!(svg.find("<defs>") < svg.find("<path"));
+    }
+
+    /// Ids of every `<defs>` entry, in order
+    fn defined_ids(svg: &str) -> Vec<&str> {
+        svg.lines()
+            .filter(|line| line.starts_with("    <") && !line.starts_with("    </"))
+            .filter_map(|line| line.split(r#" id=""#).nth(1)?.split('"').next())
+            .collect()
+    }
+
+    #[test]
+    fn test_def_ids_are_content_derived_and_shared() {
+        let scene = || {
+            let mut scene = Scene::new(100.0, 100.0);
+            let fill = |color| Fill::Pattern {
+                pattern: PatternKind::Dots,
+                color,
+                background: None,
+            };
+            let rect = |fill| {
+                MarkItem::new(Geometry::Rect { x: 0.0, y: 0.0, width: 10.0, height: 10.0, corner_radius: 0.0 })
+                    .with_fill(fill)
+            };
+            let mut clipped = Group::new().with_clip(Rect::new(0.0, 0.0, 50.0, 50.0));
+            clipped.add_mark(Mark::new(
+                MarkType::Rect,
+                vec![
+                    rect(fill(Color::rgb(255, 0, 0))),
+                    rect(fill(Color::rgb(0, 0, 255))),
+                    rect(fill(Color::rgb(255, 0, 0))),
+                ],
+            ));
+            scene.root.add_group(clipped);
+            scene
+        };
+
+        let svg = render_svg(&scene());
+        assert_eq!(svg, render_svg(&scene()));
+        let ids = defined_ids(&svg);
+        assert_eq!(ids.len(), 3);
+        assert!(ids[0].starts_with("clip-") && svg.contains(&format!(r#"clip-path="url(#{})""#, ids[0])));
+        assert!(svg.contains(r#"<rect x="0.00" y="0.00" width="50.00" height="50.00"/>"#));
+        assert_ne!(ids[1], ids[2]);
+        assert_eq!(svg.matches(&format!("url(#{})", ids[1])).count(), 2);
+    }
+
+    #[test]
+    fn test_pattern_fills_cycle_per_series() {
+        let scene = vis_core::chart(
+            r#"{
+                "data": { "values": [
+                    { "q": "Q1", "region": "North", "sales": 10 },
+                    { "q": "Q1", "region": "South", "sales": 20 }
+                ] },
+                "mark": { "type": "bar", "patterns": true },
+                "encoding": {
+                    "x": { "field": "q" },
+                    "y": { "field": "sales" },
+                    "color": { "field": "region" }
+                }
+            }"#,
+        )
+        .unwrap();
+
+        let svg = render_svg(&scene);
+        let ids = defined_ids(&svg);
+        assert_eq!(ids.len(), 2);
+        assert!(svg.contains(&format!(r#"<pattern id="{}" width="8" height="8" patternUnits="userSpaceOnUse">"#, ids[0])));
+        assert!(svg.contains(r##"<circle cx="4" cy="4" r="1.5" fill="#f28e2b"/>"##));
+        assert!(svg.contains(&format!(r#"fill="url(#{})""#, ids[1])));
+    }
+
+    #[test]
+    fn test_stacked_area_uses_fill_opacity() {
+        let scene = vis_core::chart(
+            r#"{
+                "data": { "values": [
+                    { "m": "Jan", "s": "A", "v": 1 }, { "m": "Feb", "s": "A", "v": 2 }
+                ] },
+                "mark": "line",
+                "stack": true,
+                "encoding": {
+                    "x": { "field": "m" },
+                    "y": { "field": "v" },
+                    "color": { "field": "s" }
+                }
+            }"#,
+        )
+        .unwrap();
+
+        let svg = render_svg(&scene);
+        let area = svg.lines().find(|line| line.contains("Z\"")).unwrap();
+        assert!(area.contains(r#"fill-opacity="0.70""#));
+        assert!(!area.contains(" opacity="));
+    }
+
+    #[test]
+    fn test_tooltip_template_renders_title() {
+        let scene = vis_core::chart(
+            r#"{
+                "data": { "values": [{ "category": "A", "value": 28 }] },
+                "mark": { "type": "bar", "tooltip": "{category}: {value}" },
+                "encoding": { "x": { "field": "category" }, "y": { "field": "value" } }
+            }"#,
+        )
+        .unwrap();
+
+        let svg = render_svg(&scene);
+        assert!(svg.contains("><title>A: 28</title></rect>"));
+    }
+
+    #[test]
+    fn test_baseline_via_dy_replaces_dominant_baseline() {
+        let text = |text: &str, baseline| {
+            MarkItem::new(Geometry::Text {
+                x: 0.0,
+                y: 50.0,
+                text: text.to_string(),
+                font: Font { size: 10.0, ..Font::default() },
+                anchor: TextAnchor::Start,
+                baseline,
+                angle: 0.0,
+            })
+        };
+        let mut scene = Scene::new(100.0, 100.0);
+        scene.root.add_mark(Mark::new(
+            MarkType::Text,
+            vec![text("Bottom", TextBaseline::Bottom), text("Top\nTwo", TextBaseline::Top)],
+        ));
+
+        let svg = render_svg_with_options(&scene, &SvgOptions { baseline_via_dy: true, ..SvgOptions::default() });
+        assert!(!svg.contains("dominant-baseline"));
+        assert!(svg.contains(r#"dy="-2.00" text-anchor="start" font-family"#));
+        // Multi-line text folds the shift into its first tspan
+        assert!(svg.contains(r#"<tspan x="0.00" dy="8.00">Top</tspan>"#));
+    }
+
+    #[test]
+    fn test_rtl_scene_sets_direction_and_mirrors_anchors() {
+        let mut scene = Scene::new(100.0, 100.0);
+        scene.rtl = true;
+        scene.root.add_mark(Mark::new(
+            MarkType::Text,
+            vec![MarkItem::new(Geometry::Text {
+                x: 10.0,
+                y: 10.0,
+                text: "שלום".to_string(),
+                font: Font::default(),
+                anchor: TextAnchor::Start,
+                baseline: TextBaseline::Middle,
+                angle: 0.0,
+            })],
+        ));
+
+        let svg = render_svg(&scene);
+        assert!(svg.contains(r#"text-anchor="end" direction="rtl""#));
+    }
+
+    #[test]
+    fn test_reuse_symbols_defines_each_shape_and_size_once() {
+        let symbol = |x: f64, size: f64| {
+            MarkItem::new(Geometry::Symbol { x, y: 10.0, size, shape: SymbolShape::Diamond })
+        };
+        let mut scene = Scene::new(100.0, 100.0);
+        scene.root.add_mark(Mark::new(
+            MarkType::Symbol,
+            vec![symbol(10.0, 64.0), symbol(20.0, 64.0), symbol(30.0, 64.0), symbol(40.0, 100.0)],
+        ));
+
+        let svg = render_svg_with_options(&scene, &SvgOptions { reuse_symbols: true, ..SvgOptions::default() });
+        assert_eq!(defined_ids(&svg).len(), 2);
+        assert_eq!(svg.matches("<use href=\"#symbol-").count(), 4);
+        assert!(svg.contains(r#"x="20.00" y="10.00""#));
+    }
+
+    #[test]
+    fn test_use_style_attr_moves_presentation_attributes_into_style() {
+        let mut scene = Scene::new(100.0, 100.0);
+        scene.root.add_mark(Mark::new(
+            MarkType::Rect,
+            vec![MarkItem::new(Geometry::Rect { x: 1.0, y: 2.0, width: 3.0, height: 4.0, corner_radius: 0.0 })
+                .with_fill(Color::rgb(255, 0, 0))
+                .with_stroke(Stroke::dashed(Color::rgb(0, 0, 0), 2.0, vec![4.0, 2.0]))],
+        ));
+        scene.root.add_mark(Mark::new(
+            MarkType::Text,
+            vec![MarkItem::new(Geometry::Text {
+                x: 5.0,
+                y: 5.0,
+                text: "a \"b\"".to_string(),
+                font: Font { size: 10.0, ..Font::default() },
+                anchor: TextAnchor::Start,
+                baseline: TextBaseline::Alphabetic,
+                angle: 0.0,
+            })],
+        ));
+
+        let mut clipped = Group::new().with_opacity(0.5);
+        clipped.clip = Some(Rect::new(0.0, 0.0, 10.0, 10.0));
+        scene.root.add_group(clipped);
+
+        let svg = render_svg_with_options(&scene, &SvgOptions { use_style_attr: true, ..SvgOptions::default() });
+        assert!(svg.contains(
+            r#"<rect x="1.00" y="2.00" width="3.00" height="4.00" style="fill:#ff0000;stroke:#000000;stroke-width:2.00px;stroke-dasharray:4.00px,2.00px"/>"#
+        ));
+        assert!(svg.contains(
+            r#"<text x="5.00" y="5.00" style="text-anchor:start;dominant-baseline:alphabetic;font-family:sans-serif;font-size:10.0px">a &quot;b&quot;</text>"#
+        ));
+        assert!(svg.contains(r#"<g style="clip-path:url(#clip-"#));
+        assert!(svg.contains(r#";opacity:0.50">"#));
+        assert!(!svg.contains(" fill=\""));
+    }
+
+    #[test]
+    fn test_shadow_filters_only_the_data_marks() {
+        let spec = r#"{
+            "data": { "values": [{ "k": "A", "v": 28 }, { "k": "B", "v": 55 }] },
+            "mark": { "type": "bar", "shadow": SHADOW },
+            "encoding": { "x": { "field": "k" }, "y": { "field": "v" } }
+        }"#;
+        let svg = |shadow: &str| render_svg(&vis_core::chart(&spec.replace("SHADOW", shadow)).unwrap());
+
+        let shadowed = svg("true");
+        let ids = defined_ids(&shadowed);
+        assert_eq!(ids.len(), 1);
+        assert!(ids[0].starts_with("shadow-"));
+        assert!(shadowed.contains("<feDropShadow "));
+        let filtered: Vec<&str> = shadowed.lines().filter(|line| line.contains(" filter=")).collect();
+        assert_eq!(filtered, [format!(r#"    <g class="mark-Rect" filter="url(#{})">"#, ids[0])]);
+        assert_eq!(shadowed, svg("true"));
+
+        assert!(!svg("false").contains("filter"));
+    }
+
+    #[test]
+    fn test_render_with_spec_writes_escaped_metadata() {
+        let spec = r#"{
+            "data": { "values": [{ "k": "<A & B>", "v": 1 }] },
+            "mark": "bar",
+            "encoding": { "x": { "field": "k" }, "y": { "field": "v" } }
+        }"#;
+        let scene = vis_core::chart(spec).unwrap();
+
+        let svg = render_svg_with_spec(&scene, &SvgOptions::default(), spec);
+        let start = svg.find("<metadata>").unwrap() + "<metadata>".len();
+        let end = svg.find("</metadata>").unwrap();
+        let embedded = &svg[start..end];
+        assert!(embedded.contains("&lt;A &amp; B&gt;"));
+        let unescaped = embedded
+            .replace("&quot;", "\"")
+            .replace("&lt;", "<")
+            .replace("&gt;", ">")
+            .replace("&amp;", "&");
+        assert_eq!(unescaped, spec);
+
+        assert!(!render_svg(&scene).contains("<metadata>"));
+    }
+}
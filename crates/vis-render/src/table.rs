@@ -0,0 +1,110 @@
+use std::fmt::Write;
+
+use vis_core::ir::Geometry;
+use vis_core::scale::value_to_string;
+use vis_core::Scene;
+
+use crate::svg::escape_xml;
+use crate::Renderer;
+
+/// Renderer that rebuilds the chart's data as an HTML `<table>`, as a fallback where SVG
+/// isn't displayed (e.g. email).
+///
+/// Rows come from the `datum` attached to mark items, so only items that carry one (bars,
+/// points, arc wedges) contribute; axes, legends and line geometry don't (a whole-series line
+/// or area only names its series). Each distinct datum is one row, and the columns are every
+/// field any datum has.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct HtmlTableRenderer;
+
+impl Renderer for HtmlTableRenderer {
+    type Output = String;
+
+    fn render(&self, scene: &Scene) -> String {
+        let mut rows = Vec::new();
+        let mut columns: Vec<&str> = Vec::new();
+        let data = scene
+            .mark_items()
+            .filter(|item| !matches!(item.geometry, Geometry::Line { .. } | Geometry::Area { .. }))
+            .filter_map(|item| item.datum.as_ref());
+        for datum in data {
+            let Some(fields) = datum.as_object() else {
+                continue;
+            };
+            if rows.contains(&datum) {
+                continue;
+            }
+            for name in fields.keys() {
+                if !columns.contains(&name.as_str()) {
+                    columns.push(name);
+                }
+            }
+            rows.push(datum);
+        }
+
+        let mut html = String::from("<table>\n  <thead>\n    <tr>");
+        for column in &columns {
+            write!(html, "<th>{}</th>", escape_xml(column)).unwrap();
+        }
+        html.push_str("</tr>\n  </thead>\n  <tbody>\n");
+        for row in rows {
+            html.push_str("    <tr>");
+            for column in &columns {
+                let cell = match row.get(*column) {
+                    Some(value) if !value.is_null() => value_to_string(value),
+                    _ => String::new(),
+                };
+                write!(html, "<td>{}</td>", escape_xml(&cell)).unwrap();
+            }
+            html.push_str("</tr>\n");
+        }
+        html.push_str("  </tbody>\n</table>\n");
+        html
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bar_chart_renders_one_row_per_datum() {
+        let scene = vis_core::chart(
+            r#"{
+                "data": { "values": [
+                    { "category": "A", "value": 28 },
+                    { "category": "B & C", "value": 55 }
+                ] },
+                "mark": "bar",
+                "encoding": {
+                    "x": { "field": "category" },
+                    "y": { "field": "value" }
+                }
+            }"#,
+        )
+        .unwrap();
+
+        let html = HtmlTableRenderer.render(&scene);
+        assert!(html.contains("<th>category</th><th>value</th>"));
+        assert!(html.contains("<tr><td>A</td><td>28</td></tr>"));
+        assert!(html.contains("<tr><td>B &amp; C</td><td>55</td></tr>"));
+        assert_eq!(html.matches("<tr><td>").count(), 2);
+    }
+
+    #[test]
+    fn test_series_lines_add_no_rows() {
+        let scene = vis_core::chart(
+            r#"{
+                "data": { "values": [
+                    { "x": "A", "y": 1, "s": "north" },
+                    { "x": "B", "y": 2, "s": "north" }
+                ] },
+                "mark": "line",
+                "encoding": { "x": { "field": "x" }, "y": { "field": "y" }, "color": { "field": "s" } }
+            }"#,
+        )
+        .unwrap();
+
+        assert!(!HtmlTableRenderer.render(&scene).contains("<td>"));
+    }
+}
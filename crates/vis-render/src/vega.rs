@@ -0,0 +1,371 @@
+use std::f64::consts::FRAC_PI_2;
+
+use serde_json::{json, Map, Value};
+use vis_core::ir::{
+    Fill, FontStyle, FontWeight, Geometry, Group, Mark, MarkItem, MarkType, Scene, SceneNode,
+    SymbolShape, TextAnchor, TextBaseline,
+};
+use vis_core::template::format_template;
+
+use crate::Renderer;
+
+/// Renderer that emits a Vega scenegraph (the JSON `vega-scenegraph` loads with
+/// `sceneFromJSON`), so Vega's canvas and SVG renderers on the JS side can draw a compiled
+/// chart.
+///
+/// Groups become group items positioned by their translation; scaled or rotated groups
+/// should be baked in with [`Scene::flatten`] first. Line and area items become one Vega
+/// mark each, with an item per point as Vega expects; any other mark becomes one Vega mark
+/// typed by its first item, so its items must share one kind of geometry. Vega has no fill
+/// patterns, so a pattern fill is drawn in its foreground color.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct VegaSceneRenderer;
+
+impl Renderer for VegaSceneRenderer {
+    type Output = Value;
+
+    fn render(&self, scene: &Scene) -> Value {
+        let mut root = group_item(&scene.root);
+        root.insert("width".to_string(), json!(scene.width));
+        root.insert("height".to_string(), json!(scene.height));
+        if let Some(background) = scene.background {
+            root.insert("fill".to_string(), json!(background.to_css()));
+        }
+        json!({ "marktype": "group", "role": "frame", "items": [root] })
+    }
+}
+
+fn group_item(group: &Group) -> Map<String, Value> {
+    let [.., x, y] = group.transform.to_matrix();
+    let mut item = Map::new();
+    item.insert("x".to_string(), json!(x));
+    item.insert("y".to_string(), json!(y));
+    if let Some(clip) = &group.clip {
+        // Vega clips a group to its own bounds, which start at the group's origin
+        item.insert("width".to_string(), json!(clip.x + clip.width));
+        item.insert("height".to_string(), json!(clip.y + clip.height));
+        item.insert("clip".to_string(), json!(true));
+    }
+    if group.opacity < 1.0 {
+        item.insert("opacity".to_string(), json!(group.opacity));
+    }
+    if let Some(name) = &group.name {
+        item.insert("name".to_string(), json!(name));
+    }
+
+    let mut marks = Vec::new();
+    for child in &group.children {
+        match child {
+            SceneNode::Group(g) => {
+                marks.push(json!({ "marktype": "group", "items": [group_item(g)] }));
+            }
+            SceneNode::Mark(m) => marks.extend(vega_marks(m)),
+        }
+    }
+    item.insert("items".to_string(), Value::Array(marks));
+    item
+}
+
+/// One Vega mark for most marks, typed by the first item's geometry; line and area marks
+/// split into a mark per item
+fn vega_marks(mark: &Mark) -> Vec<Value> {
+    let tooltip = mark.tooltip.as_deref();
+    match mark.mark_type {
+        MarkType::Line | MarkType::Area => mark
+            .items
+            .iter()
+            .map(|mark_item| vega_mark(marktype(&mark_item.geometry), series_items(mark_item, tooltip)))
+            .collect(),
+        _ => {
+            let Some(first) = mark.items.first() else {
+                return Vec::new();
+            };
+            let items = mark.items.iter().map(|mark_item| item(mark_item, tooltip)).collect();
+            vec![vega_mark(marktype(&first.geometry), items)]
+        }
+    }
+}
+
+fn vega_mark(marktype: &str, items: Vec<Value>) -> Value {
+    json!({ "marktype": marktype, "items": items })
+}
+
+fn marktype(geometry: &Geometry) -> &'static str {
+    match geometry {
+        Geometry::Rect { .. } => "rect",
+        Geometry::Circle { .. } | Geometry::Symbol { .. } => "symbol",
+        Geometry::Line { .. } => "line",
+        Geometry::Area { .. } => "area",
+        Geometry::Rule { .. } => "rule",
+        Geometry::Text { .. } => "text",
+        Geometry::Arc { .. } => "arc",
+        Geometry::Path { .. } => "path",
+    }
+}
+
+/// Vega draws a line or area from one item per point, styled by the first
+fn series_items(mark_item: &MarkItem, tooltip: Option<&str>) -> Vec<Value> {
+    let points: Vec<Map<String, Value>> = match &mark_item.geometry {
+        Geometry::Line { points } => points
+            .iter()
+            .map(|p| geometry_fields(&[("x", p.x), ("y", p.y)]))
+            .collect(),
+        Geometry::Area { points, baseline } => points
+            .iter()
+            .zip(baseline)
+            .map(|(p, b)| geometry_fields(&[("x", p.x), ("y", p.y), ("y2", b.y)]))
+            .collect(),
+        _ => return vec![item(mark_item, tooltip)],
+    };
+    points
+        .into_iter()
+        .enumerate()
+        .map(|(i, mut fields)| {
+            if i == 0 {
+                style(&mut fields, mark_item, tooltip);
+            }
+            Value::Object(fields)
+        })
+        .collect()
+}
+
+fn item(mark_item: &MarkItem, tooltip: Option<&str>) -> Value {
+    let mut fields = match &mark_item.geometry {
+        Geometry::Rect {
+            x,
+            y,
+            width,
+            height,
+            corner_radius,
+        } => {
+            let mut fields = geometry_fields(&[("x", *x), ("y", *y), ("width", *width), ("height", *height)]);
+            if *corner_radius > 0.0 {
+                fields.insert("cornerRadius".to_string(), json!(corner_radius));
+            }
+            fields
+        }
+        Geometry::Circle { cx, cy, r } => {
+            let mut fields = geometry_fields(&[("x", *cx), ("y", *cy), ("size", std::f64::consts::PI * r * r)]);
+            fields.insert("shape".to_string(), json!("circle"));
+            fields
+        }
+        Geometry::Symbol { x, y, size, shape } => {
+            let mut fields = geometry_fields(&[("x", *x), ("y", *y), ("size", *size)]);
+            fields.insert("shape".to_string(), json!(symbol_shape(*shape)));
+            fields
+        }
+        Geometry::Rule { x1, y1, x2, y2 } => {
+            geometry_fields(&[("x", *x1), ("y", *y1), ("x2", *x2), ("y2", *y2)])
+        }
+        Geometry::Text {
+            x,
+            y,
+            text,
+            font,
+            anchor,
+            baseline,
+            angle,
+        } => {
+            let mut fields = geometry_fields(&[("x", *x), ("y", *y), ("fontSize", font.size)]);
+            fields.insert("text".to_string(), json!(text));
+            fields.insert("font".to_string(), json!(font.family));
+            if font.weight == FontWeight::Bold {
+                fields.insert("fontWeight".to_string(), json!("bold"));
+            }
+            if font.style == FontStyle::Italic {
+                fields.insert("fontStyle".to_string(), json!("italic"));
+            }
+            let align = match anchor {
+                TextAnchor::Start => "left",
+                TextAnchor::Middle => "center",
+                TextAnchor::End => "right",
+            };
+            fields.insert("align".to_string(), json!(align));
+            let baseline = match baseline {
+                TextBaseline::Top => "top",
+                TextBaseline::Middle => "middle",
+                TextBaseline::Bottom => "bottom",
+                TextBaseline::Alphabetic => "alphabetic",
+            };
+            fields.insert("baseline".to_string(), json!(baseline));
+            if *angle != 0.0 {
+                fields.insert("angle".to_string(), json!(angle));
+            }
+            fields
+        }
+        Geometry::Arc {
+            cx,
+            cy,
+            inner_radius,
+            outer_radius,
+            start_angle,
+            end_angle,
+        } => {
+            // Vega measures arc angles clockwise from 12 o'clock rather than from 3 o'clock
+            geometry_fields(&[
+                ("x", *cx),
+                ("y", *cy),
+                ("innerRadius", *inner_radius),
+                ("outerRadius", *outer_radius),
+                ("startAngle", start_angle + FRAC_PI_2),
+                ("endAngle", end_angle + FRAC_PI_2),
+            ])
+        }
+        Geometry::Path { d } => {
+            let mut fields = Map::new();
+            fields.insert("path".to_string(), json!(d));
+            fields
+        }
+        Geometry::Line { .. } | Geometry::Area { .. } => Map::new(),
+    };
+    style(&mut fields, mark_item, tooltip);
+    Value::Object(fields)
+}
+
+fn geometry_fields(values: &[(&str, f64)]) -> Map<String, Value> {
+    values.iter().map(|(name, value)| (name.to_string(), json!(value))).collect()
+}
+
+/// Add the item's paint, opacity, tooltip and datum under Vega's property names. The
+/// tooltip is the mark's `tooltip` template filled from the datum, else the item's title.
+fn style(fields: &mut Map<String, Value>, mark_item: &MarkItem, tooltip: Option<&str>) {
+    if let Some(fill) = &mark_item.fill {
+        fields.insert("fill".to_string(), paint(fill));
+        if let Some(opacity) = mark_item.fill_opacity {
+            fields.insert("fillOpacity".to_string(), json!(opacity));
+        }
+    }
+    if let Some(stroke) = &mark_item.stroke {
+        fields.insert("stroke".to_string(), json!(stroke.color.to_css()));
+        fields.insert("strokeWidth".to_string(), json!(stroke.width));
+        if let Some(dash) = &stroke.dash {
+            fields.insert("strokeDash".to_string(), json!(dash));
+        }
+        if let Some(opacity) = stroke.opacity {
+            fields.insert("strokeOpacity".to_string(), json!(opacity));
+        }
+    }
+    if mark_item.opacity < 1.0 {
+        fields.insert("opacity".to_string(), json!(mark_item.opacity));
+    }
+    let title = tooltip
+        .zip(mark_item.datum.as_ref())
+        .map(|(template, datum)| format_template(template, datum))
+        .or_else(|| mark_item.title.clone());
+    if let Some(title) = title {
+        fields.insert("tooltip".to_string(), json!(title));
+    }
+    if let Some(datum) = &mark_item.datum {
+        fields.insert("datum".to_string(), datum.clone());
+    }
+}
+
+/// A CSS color, or a Vega gradient object spanning the item's bounding box
+fn paint(fill: &Fill) -> Value {
+    match fill {
+        Fill::Solid(color) => json!(color.to_css()),
+        Fill::LinearGradient { stops, angle } => {
+            let dx = angle.to_radians().cos() / 2.0;
+            let dy = angle.to_radians().sin() / 2.0;
+            let stops: Vec<Value> = stops
+                .iter()
+                .map(|stop| json!({ "offset": stop.offset, "color": stop.color.to_css() }))
+                .collect();
+            json!({
+                "gradient": "linear",
+                "x1": 0.5 - dx,
+                "y1": 0.5 - dy,
+                "x2": 0.5 + dx,
+                "y2": 0.5 + dy,
+                "stops": stops,
+            })
+        }
+        Fill::Pattern { color, .. } => json!(color.to_css()),
+    }
+}
+
+fn symbol_shape(shape: SymbolShape) -> &'static str {
+    match shape {
+        SymbolShape::Circle => "circle",
+        SymbolShape::Square => "square",
+        SymbolShape::Cross => "cross",
+        SymbolShape::Diamond => "diamond",
+        SymbolShape::Triangle => "triangle-up",
+        SymbolShape::Star => "star",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bar_chart_maps_onto_vega_rect_items() {
+        let scene = vis_core::chart(
+            r#"{
+                "data": { "values": [{ "k": "A", "v": 28 }, { "k": "B", "v": 55 }] },
+                "mark": "bar",
+                "encoding": { "x": { "field": "k" }, "y": { "field": "v" } }
+            }"#,
+        )
+        .unwrap();
+
+        let root = VegaSceneRenderer.render(&scene);
+        assert_eq!(root["marktype"], "group");
+        assert_eq!(root["items"][0]["width"], json!(scene.width));
+
+        let mut bars = Vec::new();
+        collect_items(&root, "rect", &mut bars);
+        let bars: Vec<&Value> = bars.into_iter().filter(|bar| bar.get("datum").is_some()).collect();
+        assert_eq!(bars.len(), 2);
+        assert_eq!(bars[0]["datum"], json!({ "k": "A", "v": 28 }));
+        assert!(bars[0]["height"].as_f64().unwrap() < bars[1]["height"].as_f64().unwrap());
+        assert!(bars[0]["fill"].is_string());
+    }
+
+    #[test]
+    fn test_tooltip_template_fills_each_item_tooltip() {
+        let scene = vis_core::chart(
+            r#"{
+                "data": { "values": [{ "k": "A", "v": 28 }, { "k": "B", "v": 55 }] },
+                "mark": { "type": "bar", "tooltip": "{k}: {v}" },
+                "encoding": { "x": { "field": "k" }, "y": { "field": "v" } }
+            }"#,
+        )
+        .unwrap();
+
+        let root = VegaSceneRenderer.render(&scene);
+        let mut bars = Vec::new();
+        collect_items(&root, "rect", &mut bars);
+        let tooltips: Vec<&Value> = bars.iter().filter_map(|bar| bar.get("tooltip")).collect();
+        assert_eq!(tooltips, [&json!("A: 28"), &json!("B: 55")]);
+    }
+
+    #[test]
+    fn test_line_series_becomes_a_mark_with_an_item_per_point() {
+        let scene = vis_core::chart(
+            r#"{
+                "data": { "values": [{ "x": 1, "y": 1 }, { "x": 2, "y": 3 }, { "x": 3, "y": 2 }] },
+                "mark": "line",
+                "encoding": { "x": { "field": "x" }, "y": { "field": "y" } }
+            }"#,
+        )
+        .unwrap();
+
+        let root = VegaSceneRenderer.render(&scene);
+        let mut points = Vec::new();
+        collect_items(&root, "line", &mut points);
+        assert_eq!(points.len(), 3);
+        assert!(points[0]["stroke"].is_string());
+        assert!(points[1].get("stroke").is_none());
+    }
+
+    fn collect_items<'a>(node: &'a Value, marktype: &str, out: &mut Vec<&'a Value>) {
+        for item in node["items"].as_array().into_iter().flatten() {
+            if node["marktype"] == marktype {
+                out.push(item);
+            }
+            collect_items(item, marktype, out);
+        }
+    }
+}
@@ -3,7 +3,7 @@
 //! Demonstrates float data types and aggregation functions.
 //! Uses mean aggregation to average temperature values per city.
 //!
-//! Supported aggregations: count, sum, mean, median, min, max, distinct
+//! Supported aggregations: count, sum, mean, median, quantile, min, max, distinct
 //!
 //! Run with: cargo run --example aggregated_bar_chart
 //! Save output: cargo run --example aggregated_bar_chart > aggregated_bar_chart.svg
@@ -0,0 +1,25 @@
+//! Pie chart example
+//!
+//! Demonstrates arc marks with outside wedge labels and leader lines.
+//!
+//! Run with: cargo run --example pie_chart
+//! Save output: cargo run --example pie_chart 2>/dev/null > pie_chart.svg
+
+use std::fs;
+use vis_core::chart;
+use vis_render::render_svg;
+
+fn main() {
+    let spec = fs::read_to_string("examples/pie.json")
+        .expect("Failed to read examples/pie.json");
+
+    match chart(&spec) {
+        Ok(scene) => {
+            let svg = render_svg(&scene);
+            println!("{}", svg);
+        }
+        Err(e) => {
+            eprintln!("Error: {}", e);
+        }
+    }
+}
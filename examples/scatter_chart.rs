@@ -0,0 +1,25 @@
+//! Scatter (bubble) chart example
+//!
+//! Demonstrates point marks with size and color encodings, drawn largest-first.
+//!
+//! Run with: cargo run --example scatter_chart
+//! Save output: cargo run --example scatter_chart 2>/dev/null > scatter_chart.svg
+
+use std::fs;
+use vis_core::chart;
+use vis_render::render_svg;
+
+fn main() {
+    let spec = fs::read_to_string("examples/scatter.json")
+        .expect("Failed to read examples/scatter.json");
+
+    match chart(&spec) {
+        Ok(scene) => {
+            let svg = render_svg(&scene);
+            println!("{}", svg);
+        }
+        Err(e) => {
+            eprintln!("Error: {}", e);
+        }
+    }
+}
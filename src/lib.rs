@@ -3,4 +3,4 @@
 //! This is the workspace root crate that re-exports the core functionality.
 
 pub use vis_core::*;
-pub use vis_render::render_svg;
+pub use vis_render::{render_svg, render_svg_filtered, render_svg_with_options, render_svg_with_spec, SvgOptions};